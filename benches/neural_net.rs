@@ -0,0 +1,131 @@
+use std::{env, hint::black_box};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use digitsrecog::neural_net::*;
+
+
+const SEED: u64 = 2049;
+const INPUTS: usize = 28*28;
+
+fn default_network() -> NeuralNet
+{
+    let layers = [
+        DefaultLayerSettings{size: 50, transfer_function: TransferFunction::Tanh},
+        DefaultLayerSettings{size: 50, transfer_function: TransferFunction::Tanh},
+        DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}
+    ];
+
+    NeuralNet::create_seeded(INPUTS, &layers, SEED)
+}
+
+fn samples(amount: usize) -> Vec<TrainSample>
+{
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    (0..amount).map(|_|
+    {
+        let label = rng.gen_range(0..10);
+
+        TrainSample::new(
+            (0..INPUTS).map(|_| rng.gen()).collect::<Vec<f64>>(),
+            (0..10).map(|i| if i==label {1.0} else {0.0}).collect::<Vec<f64>>()
+        )
+    }).collect()
+}
+
+fn feedforward(c: &mut Criterion)
+{
+    let mut network = default_network();
+    let inputs = samples(1).remove(0).inputs;
+
+    c.bench_function("feedforward", |b| b.iter(|| network.feedforward(black_box(&inputs))));
+}
+
+fn feedforward_sparse(c: &mut Criterion)
+{
+    let mut network = default_network();
+    //mostly background like the digits
+    let inputs = samples(1).remove(0).inputs.into_iter()
+        .map(|value| if value<0.8 {0.0} else {value})
+        .collect::<Vec<f64>>();
+
+    let (indices, values): (Vec<usize>, Vec<f64>) = inputs.iter().enumerate()
+        .filter(|(_, value)| **value!=0.0)
+        .unzip();
+
+    c.bench_function("feedforward sparse", |b| b.iter(||
+    {
+        network.feedforward_sparse(black_box(&indices), black_box(&values))
+    }));
+}
+
+fn backpropagate(c: &mut Criterion)
+{
+    let mut network = default_network();
+    let samples = samples(100);
+
+    c.bench_function("backpropagate 100", |b| b.iter(|| network.backpropagate(black_box(&samples))));
+}
+
+//wide relu layer where half the neurons never fire on mostly background inputs,
+//so most of its gradients stay 0
+fn backpropagate_dead_relu(c: &mut Criterion)
+{
+    let layers = [
+        DefaultLayerSettings{size: 256, transfer_function: TransferFunction::Relu},
+        DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}
+    ];
+    let mut network = NeuralNet::create_seeded(INPUTS, &layers, SEED);
+
+    let weights = network.layer_weights(0).iter().enumerate().map(|(neuron, weights)|
+    {
+        let mut weights = weights.clone();
+        if neuron % 2 == 0
+        {
+            *weights.last_mut().unwrap() = -1000.0;
+        }
+
+        weights
+    }).collect::<Vec<Vec<f64>>>();
+    network.set_layer_weights(0, weights).unwrap();
+
+    let samples = samples(100).into_iter().map(|mut sample|
+    {
+        sample.inputs.iter_mut().for_each(|value| if *value<0.8 {*value = 0.0});
+
+        sample
+    }).collect::<Vec<TrainSample>>();
+
+    c.bench_function("backpropagate dead relu 100", |b| b.iter(||
+    {
+        network.backpropagate(black_box(&samples))
+    }));
+}
+
+fn save_load(c: &mut Criterion)
+{
+    let network = default_network();
+
+    let path = env::temp_dir().join("digitsrecog_bench.nn");
+    let path = path.to_str().unwrap();
+
+    c.bench_function("save load", |b| b.iter(||
+    {
+        network.save(path).unwrap();
+        NeuralNet::load(path).unwrap()
+    }));
+
+    let _ = std::fs::remove_file(path);
+}
+
+criterion_group!(
+    benches,
+    feedforward,
+    feedforward_sparse,
+    backpropagate,
+    backpropagate_dead_relu,
+    save_load
+);
+criterion_main!(benches);
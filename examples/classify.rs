@@ -0,0 +1,93 @@
+#![allow(clippy::suspicious_else_formatting)]
+
+use std::{env, fs, process};
+
+use digitsrecog::{
+    digiter::Digiter,
+    neural_net::NeuralNet
+};
+
+
+const IMAGE_SIZE: usize = 28*28;
+
+fn usage() -> !
+{
+    let name = env::args().next().unwrap();
+
+    eprintln!("usage: {name} network.nn image.raw");
+    eprintln!("   or: {name} network.nn labels.idx images.idx index");
+    eprintln!("raw images are {IMAGE_SIZE} bytes of 28x28 grayscale");
+
+    process::exit(1)
+}
+
+fn main()
+{
+    let args = env::args().skip(1).collect::<Vec<String>>();
+
+    let (image, label) = match args.as_slice()
+    {
+        [_, image_path] =>
+        {
+            let image = fs::read(image_path).unwrap_or_else(|err|
+            {
+                eprintln!("cant read {image_path}: {err}");
+                process::exit(1)
+            });
+
+            if image.len()!=IMAGE_SIZE
+            {
+                eprintln!("{image_path} is {} bytes, expected {IMAGE_SIZE}", image.len());
+                process::exit(1)
+            }
+
+            (image, None)
+        },
+        [_, labels_path, images_path, index] =>
+        {
+            let index = index.parse::<usize>().unwrap_or_else(|_| usage());
+
+            let mut digiter = Digiter::create(labels_path, images_path).unwrap_or_else(|err|
+            {
+                eprintln!("cant open the dataset: {err}");
+                process::exit(1)
+            });
+
+            let (label, image) = digiter.nth(index).unwrap_or_else(||
+            {
+                eprintln!("theres no image at index {index}");
+                process::exit(1)
+            });
+
+            (image, Some(label))
+        },
+        _ => usage()
+    };
+
+    let mut network = NeuralNet::load(&args[0]).unwrap_or_else(|err|
+    {
+        eprintln!("cant load {}: {err}", args[0]);
+        process::exit(1)
+    });
+
+    if network.inputs_amount()!=image.len()
+    {
+        eprintln!("network expects {} inputs, image has {}", network.inputs_amount(), image.len());
+        process::exit(1)
+    }
+
+    let outputs = network.predict_raw(&image);
+
+    let mut guesses = outputs.into_iter().enumerate().collect::<Vec<(usize, f64)>>();
+    guesses.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    if let Some(label) = label
+    {
+        println!("correct digit: {label}");
+    }
+
+    for (digit, confidence) in guesses
+    {
+        println!("{digit}: {confidence:.4}");
+    }
+}
@@ -0,0 +1,1594 @@
+use std::{
+    fmt,
+    env,
+    fs,
+    thread,
+    process,
+    str,
+    iter,
+    cell::Cell,
+    time::Duration,
+    num::NonZeroUsize
+};
+
+use crate::neural_net::*;
+
+
+const FASHION_NAMES: [&str; 10] = [
+    "t-shirt", "trouser", "pullover", "dress", "coat",
+    "sandal", "shirt", "sneaker", "bag", "ankle boot"
+];
+
+pub enum ProgramMode
+{
+    Train,
+    Restart
+}
+
+pub enum DatasetKind
+{
+    Idx,
+    Csv,
+    //a label byte then the image bytes for every sample
+    Combined
+}
+
+impl DatasetKind
+{
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            DatasetKind::Idx => "idx",
+            DatasetKind::Csv => "csv",
+            DatasetKind::Combined => "combined"
+        }
+    }
+}
+
+pub enum ProgressStyle
+{
+    Emoji,
+    Ascii
+}
+
+pub enum Pruning
+{
+    Threshold(f64),
+    Fraction(f64)
+}
+
+//labels are bytes
+const MAX_CLASSES: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Classes
+{
+    Amount(usize),
+    //the largest training label plus one, resolved with set_classes once the labels are read
+    Auto
+}
+
+pub enum ConfigError
+{
+    InvalidArg(String),
+    MissingValue,
+    MissingRequired(String),
+    InvalidValue(String),
+    InvalidConfig(String)
+}
+
+impl fmt::Display for ConfigError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let mut val = None;
+        
+        let stringified = match self
+        {
+            ConfigError::InvalidArg(x) =>
+            {
+                val = Some(x);
+                "invalid argument"
+            },
+            ConfigError::MissingValue => "argument value missing",
+            ConfigError::MissingRequired(x) =>
+            {
+                val = Some(x);
+                "missing required argument"
+            },
+            ConfigError::InvalidValue(x) =>
+            {
+                val = Some(x);
+                "invalid value"
+            },
+            ConfigError::InvalidConfig(x) =>
+            {
+                val = Some(x);
+                "invalid config file"
+            }
+        };
+
+        let optional = if let Some(val) = val {format!(" ({val})")} else {"".to_owned()};
+        write!(f, "{}{}", stringified, &optional)
+    }
+}
+
+pub struct Config
+{
+    pub mode: ProgramMode,
+    pub filename: String,
+    pub threads: usize,
+    pub grad_reduce: GradReduce,
+    //the last one is always a dense layer
+    pub layers: Vec<LayerSettings>,
+    //the output layer size has to match the classes when the layers came from --arch
+    explicit_arch: bool,
+    pub classes: Classes,
+    //starting rprop learning rate of every --arch layer that has one
+    pub layer_learning_rates: Vec<Option<f64>>,
+    pub frozen_layers: Vec<usize>,
+    //frozen layers of a loaded network to train again
+    pub unfrozen_layers: Vec<usize>,
+    pub reset_learning_rates: bool,
+    pub zero_head: bool,
+    pub load_unchecksummed: bool,
+    pub optimizer: Optimizer,
+    pub warm_restarts: Option<WarmRestarts>,
+    pub loss: Loss,
+    pub iterations: usize,
+    pub epochs: Option<usize>,
+    pub batch_size: usize,
+    pub accumulate: usize,
+    pub label_smoothing: f64,
+    //share of every batch drawn from the lowest margin samples
+    pub hard_mining: Option<f64>,
+    //the standardization gets filled in once the training set is read if standardize is set,
+    //none keeps the preprocessing a loaded network was trained with
+    pub preprocessing: Option<Preprocessing>,
+    pub standardize: bool,
+    pub multilabel: bool,
+    pub target_accuracy: Option<f64>,
+    //share of the training set held out to check the target accuracy on
+    pub validation_split: f64,
+    pub validation_every: usize,
+    pub max_time: Option<Duration>,
+    pub report_every: Option<usize>,
+    pub log: Option<String>,
+    pub append_log: bool,
+    pub quiet: bool,
+    pub progress_width: usize,
+    pub precision: usize,
+    pub progress_style: ProgressStyle,
+    pub stats: bool,
+    pub list_model: Option<String>,
+    pub selftest: bool,
+    pub dry_run: bool,
+    //network to compare the output network with instead of training
+    pub compare: Option<String>,
+    pub no_test: bool,
+    pub calibration: bool,
+    pub embedding_analysis: bool,
+    pub robustness: bool,
+    pub show_samples: usize,
+    pub saliency: bool,
+    pub label_names: Option<Vec<String>>,
+    pub activation_stats: bool,
+    pub stagnation_warn: bool,
+    pub quantize: Option<String>,
+    pub prune: Option<String>,
+    pub pruning: Pruning,
+    pub dot: Option<String>,
+    pub dot_threshold: f64,
+    pub weight_histogram: bool,
+    pub export_npy: Option<String>,
+    //- writes to stdout
+    pub dump_weights: Option<String>,
+    pub dataset: DatasetKind,
+    pub csv_dimensions: Option<(u32, u32)>,
+    pub combined_dimensions: Option<(u32, u32)>,
+    pub input_size: Option<(u32, u32)>,
+    pub cache: Option<String>,
+    //several sources get chained into one dataset
+    pub train_images: Vec<String>,
+    pub train_labels: Vec<String>,
+    pub test_images: Vec<String>,
+    pub test_labels: Vec<String>,
+    pub verbose: bool,
+    //resolved values and where they came from, printed with --verbose
+    pub decisions: Vec<String>
+}
+
+impl Config
+{
+    pub fn create(args: impl Iterator<Item=String>) -> Result<Self, ConfigError>
+    {
+        let mut args = args.collect::<Vec<String>>();
+
+        //counts consumed args to find the --config and to tell if an error came from its file,
+        //running out of args counts too so errors after the last arg arent blamed on it
+        let consumed = &Cell::new(0);
+        let counted = |args: Vec<String>|
+        {
+            consumed.set(0);
+
+            let mut args = args.into_iter();
+            iter::from_fn(move ||
+            {
+                consumed.set(consumed.get() + 1);
+                args.next()
+            })
+        };
+
+        let mut config_file = None;
+        if let Some(config) = Self::parse(counted(args.clone()), &mut config_file)?
+        {
+            return Ok(config);
+        }
+
+        let path = config_file.clone().unwrap();
+
+        //the files args go first so the ones given on the command line win
+        args.drain(consumed.get()-2..consumed.get());
+
+        let file_args = Self::config_file_args(&path)?;
+
+        let mut ends = Vec::with_capacity(file_args.len());
+        let mut all_args = Vec::new();
+        for (key, key_args) in file_args
+        {
+            all_args.extend(key_args);
+            ends.push((key, all_args.len()));
+        }
+
+        let file_args_amount = all_args.len();
+        all_args.extend(args);
+
+        Self::parse(counted(all_args), &mut config_file).map(|config|
+        {
+            config.expect("the config file was already loaded")
+        }).map_err(|err|
+        {
+            if consumed.get() <= file_args_amount
+            {
+                let key = ends.iter().find(|(_, end)| consumed.get() <= *end).unwrap().0.clone();
+
+                ConfigError::InvalidConfig(format!("{key} in {path}: {err}"))
+            } else
+            {
+                err
+            }
+        })
+    }
+
+    //stops at a --config and returns none with config_file set to its path, parsing has
+    //to start over with the files args then
+    fn parse(
+        mut args: impl Iterator<Item=String>,
+        config_file: &mut Option<String>
+    ) -> Result<Option<Self>, ConfigError>
+    {
+        let mut mode = ProgramMode::Restart;
+        let mut filename = "network.nn".to_owned();
+
+        let mut threads = None;
+
+        let mut layers = Self::default_layers();
+        let mut explicit_arch = false;
+        let mut layer_learning_rates = Vec::new();
+        let mut classes = Classes::Amount(10);
+        let mut frozen_layers = Vec::new();
+        let mut unfrozen_layers = Vec::new();
+        let mut reset_learning_rates = false;
+        let mut zero_head = false;
+        let mut load_unchecksummed = false;
+
+        let mut optimizer_name = "rprop".to_owned();
+        let mut learning_rate = 0.01;
+        let mut momentum = 0.0;
+        let mut restart_cycle = None;
+        let mut restart_multiplier = None;
+
+        let mut loss = None;
+        let mut grad_reduce = GradReduce::default();
+
+        let mut iterations = None;
+        let mut epochs = None;
+        let mut batch_size = 10000;
+        let mut accumulate = 1;
+        let mut label_smoothing = 0.0;
+        let mut hard_mining = None;
+        let mut input_scale = DEFAULT_INPUT_SCALE;
+        let mut binarize = None;
+        let mut standardize = false;
+        let mut preprocessing_given = false;
+        let mut multilabel = false;
+
+        let mut target_accuracy = None;
+        let mut validation_split = None;
+        let mut validation_every = None;
+        let mut max_time = None;
+
+        let mut report_every = None;
+        let mut log = None;
+        let mut append_log = false;
+        let mut quiet = false;
+
+        let mut progress_width = 30;
+        let mut precision = 2;
+        let mut progress_style = ProgressStyle::Emoji;
+
+        let mut stats = false;
+        let mut list_model = None;
+        let mut selftest = false;
+        let mut dry_run = false;
+        let mut compare = None;
+        let mut no_test = false;
+        let mut calibration = false;
+        let mut embedding_analysis = false;
+        let mut robustness = false;
+        let mut show_samples = 0;
+        let mut saliency = false;
+        let mut label_names = None;
+        let mut activation_stats = false;
+        let mut stagnation_warn = false;
+
+        let mut quantize = None;
+
+        let mut prune = None;
+        let mut pruning = Pruning::Threshold(0.01);
+
+        let mut dot = None;
+        let mut export_npy = None;
+        let mut dump_weights = None;
+        let mut dot_threshold = 0.0;
+        let mut weight_histogram = false;
+
+        let mut dataset = DatasetKind::Idx;
+        let mut csv_dimensions = None;
+        let mut combined_dimensions = None;
+        let mut input_size = None;
+        let mut cache = None;
+
+        let mut train_labels = Vec::new();
+        let mut train_images = Vec::new();
+        
+        let mut test_labels = Vec::new();
+        let mut test_images = Vec::new();
+
+        let mut verbose = false;
+
+        //flags as written, to tell set values apart from defaults
+        let mut given = Vec::new();
+
+        while let Some(arg) = args.next()
+        {
+            given.push(arg.clone());
+
+            match arg.as_str()
+            {
+                "-M" | "--mode" =>
+                {
+                    mode = match args.next().ok_or(ConfigError::MissingValue)?.as_str()
+                    {
+                        "restart" => ProgramMode::Restart,
+                        "train" => ProgramMode::Train,
+                        x => return Err(ConfigError::InvalidValue(x.to_owned()))
+                    };
+                },
+                "-o" | "--output" =>
+                {
+                    filename = args.next().ok_or(ConfigError::MissingValue)?;
+                },
+                "--threads" =>
+                {
+                    threads = Some(Self::number_arg::<usize>(&mut args)?);
+                },
+                "--grad-reduce" =>
+                {
+                    grad_reduce = match args.next().ok_or(ConfigError::MissingValue)?.as_str()
+                    {
+                        "sum" => GradReduce::Sum,
+                        "mean" => GradReduce::Mean,
+                        "median" => GradReduce::Median,
+                        x => return Err(ConfigError::InvalidValue(x.to_owned()))
+                    };
+                },
+                "--load-unchecksummed" =>
+                {
+                    load_unchecksummed = true;
+                },
+                "--arch" =>
+                {
+                    (layers, layer_learning_rates) =
+                        Self::parse_layers(&args.next().ok_or(ConfigError::MissingValue)?)?;
+                    explicit_arch = true;
+                },
+                "--classes" =>
+                {
+                    classes = Self::parse_classes(&args.next().ok_or(ConfigError::MissingValue)?)?;
+                },
+                "--freeze" =>
+                {
+                    frozen_layers =
+                        Self::parse_indices(&args.next().ok_or(ConfigError::MissingValue)?)?;
+                },
+                "--unfreeze" =>
+                {
+                    unfrozen_layers =
+                        Self::parse_indices(&args.next().ok_or(ConfigError::MissingValue)?)?;
+                },
+                "--reset-lr" =>
+                {
+                    reset_learning_rates = true;
+                },
+                "--zero-head" =>
+                {
+                    zero_head = true;
+                },
+                "--optimizer" =>
+                {
+                    optimizer_name = args.next().ok_or(ConfigError::MissingValue)?;
+                },
+                "--learning-rate" =>
+                {
+                    learning_rate = Self::number_arg(&mut args)?;
+                },
+                "--momentum" =>
+                {
+                    momentum = Self::number_arg(&mut args)?;
+                },
+                "--restart-cycle" =>
+                {
+                    let value = Self::number_arg(&mut args)?;
+
+                    if value==0
+                    {
+                        return Err(ConfigError::InvalidValue("--restart-cycle must be above 0".to_owned()));
+                    }
+
+                    restart_cycle = Some(value);
+                },
+                "--restart-mult" =>
+                {
+                    let value: f64 = Self::number_arg(&mut args)?;
+
+                    if value<1.0 || value.is_nan()
+                    {
+                        return Err(ConfigError::InvalidValue(
+                            "--restart-mult must be at least 1".to_owned()
+                        ));
+                    }
+
+                    restart_multiplier = Some(value);
+                },
+                "--loss" =>
+                {
+                    loss = Some(Self::parse_loss(&args.next().ok_or(ConfigError::MissingValue)?)?);
+                },
+                "--config" =>
+                {
+                    let path = args.next().ok_or(ConfigError::MissingValue)?;
+
+                    if config_file.is_some()
+                    {
+                        return Err(ConfigError::InvalidValue(
+                            "only one --config file can be used".to_owned()
+                        ));
+                    }
+
+                    *config_file = Some(path);
+
+                    return Ok(None);
+                },
+                "-I" | "--iter" =>
+                {
+                    iterations = Some(Self::number_arg(&mut args)?);
+                },
+                "--epochs" =>
+                {
+                    let value = Self::number_arg(&mut args)?;
+
+                    if value==0
+                    {
+                        return Err(ConfigError::InvalidValue("--epochs must be above 0".to_owned()));
+                    }
+
+                    epochs = Some(value);
+                },
+                "-b" | "--batch" =>
+                {
+                    batch_size = Self::number_arg(&mut args)?;
+                },
+                "--accum" =>
+                {
+                    accumulate = Self::number_arg(&mut args)?;
+
+                    if accumulate==0
+                    {
+                        return Err(ConfigError::InvalidValue("--accum must be above 0".to_owned()));
+                    }
+                },
+                "--label-smoothing" =>
+                {
+                    label_smoothing = Self::number_arg(&mut args)?;
+
+                    if !(0.0..1.0).contains(&label_smoothing)
+                    {
+                        return Err(ConfigError::InvalidValue(
+                            "--label-smoothing must be between 0 and 1".to_owned()
+                        ));
+                    }
+                },
+                "--hard-mining" =>
+                {
+                    let fraction = Self::number_arg(&mut args)?;
+
+                    if !(fraction>0.0 && fraction<=1.0)
+                    {
+                        return Err(ConfigError::InvalidValue(
+                            "--hard-mining must be above 0 and at most 1".to_owned()
+                        ));
+                    }
+
+                    hard_mining = Some(fraction);
+                },
+                "--input-scale" =>
+                {
+                    input_scale = Self::number_arg(&mut args)?;
+                    preprocessing_given = true;
+
+                    if input_scale<=0.0
+                    {
+                        return Err(ConfigError::InvalidValue(
+                            "--input-scale must be above 0".to_owned()
+                        ));
+                    }
+                },
+                "--binarize" =>
+                {
+                    let threshold: f64 = Self::number_arg(&mut args)?;
+
+                    if !(0.0..=1.0).contains(&threshold)
+                    {
+                        return Err(ConfigError::InvalidValue(
+                            "--binarize must be between 0 and 1".to_owned()
+                        ));
+                    }
+
+                    binarize = Some(threshold);
+                    preprocessing_given = true;
+                },
+                "--standardize" =>
+                {
+                    standardize = true;
+                    preprocessing_given = true;
+                },
+                "--multilabel" =>
+                {
+                    multilabel = true;
+                },
+                "--target-accuracy" =>
+                {
+                    target_accuracy = Some(Self::number_arg(&mut args)?);
+                },
+                "--validation-split" =>
+                {
+                    let fraction = Self::number_arg(&mut args)?;
+
+                    if !(fraction>0.0 && fraction<1.0)
+                    {
+                        return Err(ConfigError::InvalidValue(
+                            "--validation-split must be between 0 and 1".to_owned()
+                        ));
+                    }
+
+                    validation_split = Some(fraction);
+                },
+                "--validation-every" =>
+                {
+                    let every = Self::number_arg(&mut args)?;
+
+                    if every==0
+                    {
+                        return Err(ConfigError::InvalidValue(
+                            "--validation-every must be above 0".to_owned()
+                        ));
+                    }
+
+                    validation_every = Some(every);
+                },
+                "--max-time" =>
+                {
+                    max_time = Some(Duration::try_from_secs_f64(Self::number_arg(&mut args)?)
+                        .map_err(|err| ConfigError::InvalidValue(err.to_string()))?);
+                },
+                "--log" =>
+                {
+                    log = Some(args.next().ok_or(ConfigError::MissingValue)?);
+                },
+                "--append-log" =>
+                {
+                    append_log = true;
+                },
+                "--report-every" =>
+                {
+                    let value = Self::number_arg(&mut args)?;
+
+                    if value==0
+                    {
+                        return Err(ConfigError::InvalidValue(
+                            "--report-every must be above 0".to_owned()
+                        ));
+                    }
+
+                    report_every = Some(value);
+                },
+                "-q" | "--quiet" =>
+                {
+                    quiet = true;
+                },
+                "--precision-digits" =>
+                {
+                    precision = Self::number_arg(&mut args)?;
+                },
+                "--progress-width" =>
+                {
+                    progress_width = Self::number_arg(&mut args)?;
+                },
+                "--progress-style" =>
+                {
+                    progress_style = match args.next().ok_or(ConfigError::MissingValue)?.as_str()
+                    {
+                        "emoji" => ProgressStyle::Emoji,
+                        "ascii" => ProgressStyle::Ascii,
+                        x => return Err(ConfigError::InvalidValue(x.to_owned()))
+                    };
+                },
+                "--selftest" =>
+                {
+                    selftest = true;
+                },
+                "--list-model" =>
+                {
+                    list_model = Some(args.next().ok_or(ConfigError::MissingValue)?);
+                },
+                "--stats" =>
+                {
+                    stats = true;
+                },
+                "--dry-run" =>
+                {
+                    dry_run = true;
+                },
+                "--compare" =>
+                {
+                    compare = Some(args.next().ok_or(ConfigError::MissingValue)?);
+                },
+                "--no-test" =>
+                {
+                    no_test = true;
+                },
+                "--calibration" =>
+                {
+                    calibration = true;
+                },
+                "--embedding-analysis" =>
+                {
+                    embedding_analysis = true;
+                },
+                "--robustness" =>
+                {
+                    robustness = true;
+                },
+                "--show-samples" =>
+                {
+                    show_samples = Self::number_arg(&mut args)?;
+                },
+                "--saliency" =>
+                {
+                    saliency = true;
+                },
+                "--labels-names" =>
+                {
+                    label_names =
+                        Some(Self::parse_label_names(&args.next().ok_or(ConfigError::MissingValue)?));
+                },
+                "--activation-stats" =>
+                {
+                    activation_stats = true;
+                },
+                "--stagnation-warn" =>
+                {
+                    stagnation_warn = true;
+                },
+                "--quantize" =>
+                {
+                    quantize = Some(args.next().ok_or(ConfigError::MissingValue)?);
+                },
+                "--prune" =>
+                {
+                    prune = Some(args.next().ok_or(ConfigError::MissingValue)?);
+                },
+                "--prune-threshold" =>
+                {
+                    pruning = Pruning::Threshold(Self::number_arg(&mut args)?);
+                },
+                "--prune-fraction" =>
+                {
+                    let fraction = Self::number_arg(&mut args)?;
+
+                    if !(0.0..=1.0).contains(&fraction)
+                    {
+                        return Err(ConfigError::InvalidValue(
+                            "--prune-fraction must be between 0 and 1".to_owned()
+                        ));
+                    }
+
+                    pruning = Pruning::Fraction(fraction);
+                },
+                "--dot" =>
+                {
+                    dot = Some(args.next().ok_or(ConfigError::MissingValue)?);
+                },
+                "--dot-threshold" =>
+                {
+                    dot_threshold = Self::number_arg(&mut args)?;
+                },
+                "--export-npy" =>
+                {
+                    export_npy = Some(args.next().ok_or(ConfigError::MissingValue)?);
+                },
+                "--weight-hist" =>
+                {
+                    weight_histogram = true;
+                },
+                "--dump-weights" =>
+                {
+                    dump_weights = Some(args.next().ok_or(ConfigError::MissingValue)?);
+                },
+                "--dataset" =>
+                {
+                    dataset = match args.next().ok_or(ConfigError::MissingValue)?.as_str()
+                    {
+                        "idx" => DatasetKind::Idx,
+                        "csv" => DatasetKind::Csv,
+                        "combined" => DatasetKind::Combined,
+                        x => return Err(ConfigError::InvalidValue(x.to_owned()))
+                    };
+                },
+                "--csv-dims" =>
+                {
+                    csv_dimensions = Some(Self::dimensions_arg(&mut args)?);
+                },
+                "--combined-dims" =>
+                {
+                    combined_dimensions = Some(Self::dimensions_arg(&mut args)?);
+                },
+                "--input-size" =>
+                {
+                    input_size = Some(Self::dimensions_arg(&mut args)?);
+                },
+                "--cache" =>
+                {
+                    cache = Some(args.next().ok_or(ConfigError::MissingValue)?);
+                },
+                "-i" | "--images" =>
+                {
+                    Self::paths_arg(&mut args, &mut train_images)?;
+                },
+                "-l" | "--labels" =>
+                {
+                    Self::paths_arg(&mut args, &mut train_labels)?;
+                },
+                "-t" | "--test-images" =>
+                {
+                    Self::paths_arg(&mut args, &mut test_images)?;
+                },
+                "-T" | "--test-labels" =>
+                {
+                    Self::paths_arg(&mut args, &mut test_labels)?;
+                },
+                "-v" | "--verbose" =>
+                {
+                    verbose = true;
+                },
+                "-h" | "--help" =>
+                {
+                    Self::help_message();
+                },
+                x => return Err(ConfigError::InvalidArg(x.to_owned()))
+            }
+        }
+
+        //listing a model and the self test dont touch any dataset
+        let datasetless = list_model.is_some() || selftest;
+
+        if !datasetless
+        {
+            if train_images.is_empty()
+            {
+                return Err(ConfigError::MissingRequired("--images".to_owned()));
+            }
+
+            //csv and combined files have the labels inline
+            if matches!(dataset, DatasetKind::Idx) && train_labels.is_empty()
+            {
+                return Err(ConfigError::MissingRequired("--labels".to_owned()));
+            }
+
+            //combined files dont store the image size
+            if matches!(dataset, DatasetKind::Combined) && combined_dimensions.is_none()
+            {
+                return Err(ConfigError::MissingRequired("--combined-dims".to_owned()));
+            }
+        }
+
+        if test_images.is_empty()
+        {
+            test_images = train_images.clone();
+        }
+
+        if test_labels.is_empty()
+        {
+            test_labels = train_labels.clone();
+        }
+
+        let unpaired = [(&train_images, &train_labels), (&test_images, &test_labels)].iter()
+            .any(|(images, labels)| images.len()!=labels.len());
+
+        if matches!(dataset, DatasetKind::Idx) && !datasetless && unpaired
+        {
+            return Err(ConfigError::InvalidValue(
+                "every idx images file needs its own labels file".to_owned()
+            ));
+        }
+
+        if iterations.is_some() && epochs.is_some()
+        {
+            return Err(ConfigError::InvalidValue(
+                "--iter and --epochs cant be used together".to_owned()
+            ));
+        }
+
+        let iterations = iterations.unwrap_or(10);
+
+        if multilabel
+        {
+            let conflict = if !matches!(dataset, DatasetKind::Csv)
+            {
+                Some("--multilabel needs --dataset csv")
+            } else if cache.is_some()
+            {
+                Some("--multilabel cant be used with --cache")
+            } else if label_smoothing!=0.0
+            {
+                Some("--multilabel cant be used with --label-smoothing")
+            } else
+            {
+                None
+            };
+
+            if let Some(conflict) = conflict
+            {
+                return Err(ConfigError::InvalidValue(conflict.to_owned()));
+            }
+
+            //every output is its own yes or no
+            if let Some(LayerSettings::Dense(head)) = layers.last_mut()
+            {
+                head.transfer_function = TransferFunction::Sigmoid;
+            }
+        }
+
+        if append_log && log.is_none()
+        {
+            return Err(ConfigError::InvalidValue("--append-log needs --log".to_owned()));
+        }
+
+        if saliency && show_samples==0
+        {
+            return Err(ConfigError::InvalidValue("--saliency needs --show-samples".to_owned()));
+        }
+
+        if target_accuracy.is_none() && (validation_split.is_some() || validation_every.is_some())
+        {
+            return Err(ConfigError::InvalidValue(
+                "--validation-split and --validation-every need --target-accuracy".to_owned()
+            ));
+        }
+
+        let validation_split = validation_split.unwrap_or(0.1);
+        let validation_every = validation_every.unwrap_or(10);
+
+        if no_test
+        {
+            let conflict = if calibration
+            {
+                Some("--calibration needs the test set, cant be used with --no-test")
+            } else if embedding_analysis
+            {
+                Some("--embedding-analysis needs the test set, cant be used with --no-test")
+            } else if robustness
+            {
+                Some("--robustness needs the test set, cant be used with --no-test")
+            } else if show_samples>0
+            {
+                Some("--show-samples needs the test set, cant be used with --no-test")
+            } else if compare.is_some()
+            {
+                Some("--compare needs the test set, cant be used with --no-test")
+            } else
+            {
+                None
+            };
+
+            if let Some(conflict) = conflict
+            {
+                return Err(ConfigError::InvalidValue(conflict.to_owned()));
+            }
+        }
+
+        let loss = loss.unwrap_or(if multilabel {Loss::CrossEntropy} else {Loss::default()});
+
+        let optimizer = match optimizer_name.as_str()
+        {
+            "rprop" => Optimizer::Rprop{variant: RpropVariant::IRpropMinus},
+            "rprop+" => Optimizer::Rprop{variant: RpropVariant::RpropPlus},
+            "sgd" => Optimizer::Sgd{learning_rate, momentum},
+            x => return Err(ConfigError::InvalidValue(x.to_owned()))
+        };
+
+        let warm_restarts = match restart_cycle
+        {
+            Some(cycle) =>
+            {
+                if !matches!(optimizer, Optimizer::Sgd{..})
+                {
+                    return Err(ConfigError::InvalidValue(
+                        "--restart-cycle needs --optimizer sgd".to_owned()
+                    ));
+                }
+
+                Some(WarmRestarts{cycle, multiplier: restart_multiplier.unwrap_or(1.0)})
+            },
+            None =>
+            {
+                if restart_multiplier.is_some()
+                {
+                    return Err(ConfigError::InvalidValue("--restart-mult needs --restart-cycle".to_owned()));
+                }
+
+                None
+            }
+        };
+
+        let threads = threads.unwrap_or_else(||
+        {
+            thread::available_parallelism().unwrap_or_else(|_| NonZeroUsize::new(1).unwrap()).get()
+        });
+
+        let source = |flags: &[&str], fallback: &str|
+        {
+            if given.iter().any(|arg| flags.contains(&arg.as_str()))
+            {
+                "flag".to_owned()
+            } else
+            {
+                fallback.to_owned()
+            }
+        };
+
+        let mode_name = match mode
+        {
+            ProgramMode::Restart => "restart",
+            ProgramMode::Train => "train"
+        };
+
+        let dataset_name = dataset.name();
+
+        let arch = layers.iter().enumerate().map(|(index, layer)|
+        {
+            let learning_rate = layer_learning_rates.get(index).copied().flatten()
+                .map(|rate| format!(" lr {rate}"))
+                .unwrap_or_default();
+
+            match layer
+            {
+                LayerSettings::Dense(layer) =>
+                {
+                    format!("{}:{:?}{learning_rate}", layer.size, layer.transfer_function)
+                },
+                LayerSettings::Conv(ConvSettings{channels, kernel_size, stride, transfer_function}) =>
+                {
+                    format!("conv{channels}x{kernel_size}/{stride}:{transfer_function:?}{learning_rate}")
+                },
+                LayerSettings::MaxPool{size} => format!("pool{size}")
+            }
+        }).collect::<Vec<String>>()
+            .join(",");
+
+        let length = match epochs
+        {
+            Some(epochs) => format!("{epochs} epochs (flag)"),
+            None => format!("{iterations} iterations ({})", source(&["-I", "--iter"], "default"))
+        };
+
+        let labels = |name: &str, value: &[String], flags: &[&str], fallback: &str|
+        {
+            match dataset
+            {
+                DatasetKind::Idx =>
+                {
+                    format!("{name}: {} ({})", value.join(","), source(flags, fallback))
+                },
+                DatasetKind::Csv => format!("{name}: inline in the csv"),
+                DatasetKind::Combined => format!("{name}: inline in the combined file")
+            }
+        };
+
+        let loss_fallback = if multilabel {"default for --multilabel"} else {"default"};
+
+        let mut decisions = vec![
+            format!("mode: {mode_name} ({})", source(&["-M", "--mode"], "default")),
+            format!("output: {filename} ({})", source(&["-o", "--output"], "default")),
+            format!("threads: {threads} ({})", source(&["--threads"], "available parallelism")),
+            format!("gradient reduction: {grad_reduce:?} ({})", source(&["--grad-reduce"], "default")),
+            format!("arch: {arch} ({})", source(&["--arch"], "default")),
+            format!("optimizer: {optimizer:?} ({})", source(&["--optimizer"], "default")),
+            format!("loss: {loss:?} ({})", source(&["--loss"], loss_fallback)),
+            format!("training length: {length}"),
+            format!("batch size: {batch_size} ({})", source(&["-b", "--batch"], "default")),
+            format!("dataset: {dataset_name} ({})", source(&["--dataset"], "default")),
+            format!("train_images: {} (flag)", train_images.join(",")),
+            labels("train_labels", &train_labels, &["-l", "--labels"], "")
+        ];
+
+        if let Some(WarmRestarts{cycle, multiplier}) = warm_restarts
+        {
+            decisions.push(format!("warm restarts: every {cycle} iterations, cycle multiplier {multiplier} (flag)"));
+        }
+
+        if no_test
+        {
+            decisions.push("test set: skipped (flag)".to_owned());
+        } else
+        {
+            decisions.push(format!(
+                "test_images: {} ({})",
+                test_images.join(","),
+                source(&["-t", "--test-images"], "using train_images as fallback")
+            ));
+
+            decisions.push(labels(
+                "test_labels",
+                &test_labels,
+                &["-T", "--test-labels"],
+                "using train_labels as fallback"
+            ));
+        }
+
+        //a network being trained further keeps its own preprocessing unless its overridden
+        let preprocessing = (preprocessing_given || matches!(mode, ProgramMode::Restart))
+            .then_some(Preprocessing{scale: input_scale, binarize, standardize: None});
+
+        let mut config = Config{
+            mode, filename,
+            threads, grad_reduce,
+            layers, explicit_arch, layer_learning_rates, classes, frozen_layers, unfrozen_layers, reset_learning_rates, zero_head, load_unchecksummed,
+            optimizer, warm_restarts,
+            loss,
+            iterations, epochs, batch_size, accumulate,
+            label_smoothing, hard_mining, preprocessing, standardize, multilabel,
+            target_accuracy, validation_split, validation_every, max_time,
+            report_every, log, append_log, quiet,
+            progress_width, precision, progress_style,
+            stats, list_model, selftest, dry_run, compare, no_test, calibration,
+            embedding_analysis, robustness, show_samples, saliency,
+            label_names, activation_stats, stagnation_warn,
+            quantize,
+            prune, pruning,
+            dot, dot_threshold,
+            weight_histogram,
+            export_npy, dump_weights,
+            dataset, csv_dimensions, combined_dimensions, input_size, cache,
+            train_images, train_labels,
+            test_images, test_labels,
+            verbose, decisions
+        };
+
+        if let Classes::Amount(classes) = classes
+        {
+            config.set_classes(classes)?;
+        }
+
+        //a loaded networks layers are only known once its loaded
+        if matches!(config.mode, ProgramMode::Restart)
+        {
+            config.check_layers(config.layers.len())?;
+        }
+
+        Ok(Some(config))
+    }
+
+    //checks the layer indices of the flags against the layers of the network
+    pub fn check_layers(&self, layers: usize) -> Result<(), ConfigError>
+    {
+        let flags = [("--freeze", &self.frozen_layers), ("--unfreeze", &self.unfrozen_layers)];
+
+        for (flag, indices) in flags
+        {
+            if let Some(index) = indices.iter().find(|index| **index>=layers)
+            {
+                return Err(ConfigError::InvalidValue(
+                    format!("{flag} {index} but the network has {layers} layers")
+                ));
+            }
+        }
+
+        //the learning rates from --arch only get used on a new network or after --reset-lr
+        let rates_used = matches!(self.mode, ProgramMode::Restart) || self.reset_learning_rates;
+
+        let last_rate = self.layer_learning_rates.iter().rposition(|rate| rate.is_some());
+        if let Some(index) = last_rate.filter(|index| rates_used && *index>=layers)
+        {
+            return Err(ConfigError::InvalidValue(format!(
+                "--arch sets the learning rate of layer {index} but the network has {layers} layers"
+            )));
+        }
+
+        Ok(())
+    }
+
+    //sizes the output layer (or checks the one from --arch) and the label names for the classes
+    pub fn set_classes(&mut self, classes: usize) -> Result<(), ConfigError>
+    {
+        if !(2..=MAX_CLASSES).contains(&classes)
+        {
+            return Err(ConfigError::InvalidValue(
+                format!("{classes} classes (must be between 2 and {MAX_CLASSES})")
+            ));
+        }
+
+        if let Some(LayerSettings::Dense(output)) = self.layers.last_mut()
+        {
+            if !self.explicit_arch
+            {
+                output.size = classes;
+            } else if output.size!=classes
+            {
+                return Err(ConfigError::InvalidValue(format!(
+                    "--arch output layer has {} neurons but there are {classes} classes",
+                    output.size
+                )));
+            }
+        }
+
+        if let Some(names) = &self.label_names
+        {
+            if names.len()!=classes
+            {
+                return Err(ConfigError::InvalidValue(format!(
+                    "--labels-names has {} names but there are {classes} classes",
+                    names.len()
+                )));
+            }
+        }
+
+        self.classes = Classes::Amount(classes);
+
+        Ok(())
+    }
+
+    //amount of output classes, --classes auto has to be resolved with set_classes first
+    pub fn classes_amount(&self) -> usize
+    {
+        match self.classes
+        {
+            Classes::Amount(classes) => classes,
+            Classes::Auto => panic!("--classes auto wasnt resolved")
+        }
+    }
+
+    //the label name if there is one for it, the label itself otherwise
+    pub fn label_name(&self, label: usize) -> String
+    {
+        self.label_names.as_ref().and_then(|names| names.get(label).cloned())
+            .unwrap_or_else(|| label.to_string())
+    }
+
+    //a toml file keyed by the long flags without their dashes, like iter = 100,
+    //switches are booleans and arrays are joined into comma separated values
+    fn config_file_args(path: &str) -> Result<Vec<(String, Vec<String>)>, ConfigError>
+    {
+        let text = fs::read_to_string(path)
+            .map_err(|err| ConfigError::InvalidConfig(format!("{err} (filepath: {path})")))?;
+
+        let table = text.parse::<toml::Table>()
+            .map_err(|err| ConfigError::InvalidConfig(format!("{path}: {err}")))?;
+
+        table.into_iter().map(|(key, value)|
+        {
+            let invalid = |value: &toml::Value|
+            {
+                ConfigError::InvalidConfig(format!("{key} in {path}: unsupported value {value}"))
+            };
+
+            let single = |value: &toml::Value|
+            {
+                match value
+                {
+                    toml::Value::String(value) => Ok(value.clone()),
+                    toml::Value::Integer(value) => Ok(value.to_string()),
+                    toml::Value::Float(value) => Ok(value.to_string()),
+                    value => Err(invalid(value))
+                }
+            };
+
+            let flag = format!("--{key}");
+            let args = match &value
+            {
+                toml::Value::Boolean(true) => vec![flag],
+                toml::Value::Boolean(false) => Vec::new(),
+                toml::Value::Array(values) =>
+                {
+                    let values = values.iter().map(single).collect::<Result<Vec<String>, _>>()?;
+
+                    vec![flag, values.join(",")]
+                },
+                value => vec![flag, single(value)?]
+            };
+
+            Ok((key, args))
+        }).collect()
+    }
+
+    fn default_layers() -> Vec<LayerSettings>
+    {
+        [
+            DefaultLayerSettings{size: 50, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 50, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}
+        ].into_iter().map(LayerSettings::Dense).collect()
+    }
+
+    //a layer is size:function, with the functions numbers after it and optionally
+    //its starting learning rate last, conv layers have convCHANNELSxKERNEL[/STRIDE]
+    //instead of the size and pooling layers are poolSIZE alone
+    fn parse_layers(
+        value: &str
+    ) -> Result<(Vec<LayerSettings>, Vec<Option<f64>>), ConfigError>
+    {
+        let layers = value.split(',').map(|layer|
+        {
+            let invalid = || ConfigError::InvalidValue(layer.to_owned());
+
+            let mut parts = layer.trim().split(':');
+
+            let kind = parts.next().ok_or_else(invalid)?;
+
+            if let Some(size) = kind.strip_prefix("pool")
+            {
+                let size = size.parse::<usize>().map_err(|_| invalid())?;
+                if size==0 || parts.next().is_some()
+                {
+                    return Err(invalid());
+                }
+
+                return Ok((LayerSettings::MaxPool{size}, None));
+            }
+
+            let conv = match kind.strip_prefix("conv")
+            {
+                Some(conv) => Some(Self::parse_conv(conv).ok_or_else(invalid)?),
+                None => None
+            };
+
+            let size = match conv
+            {
+                Some(_) => None,
+                None =>
+                {
+                    let size = kind.parse::<usize>().map_err(|_| invalid())?;
+                    if size==0
+                    {
+                        return Err(invalid());
+                    }
+
+                    Some(size)
+                }
+            };
+
+            let name = parts.next();
+
+            let mut number = || -> Result<f64, ConfigError>
+            {
+                parts.next().ok_or_else(invalid)?.parse::<f64>().map_err(|_| invalid())
+            };
+
+            let transfer_function = match name
+            {
+                Some("nothing") => TransferFunction::Nothing,
+                Some("relu") => TransferFunction::Relu,
+                Some("leakyrelu") => TransferFunction::LeakyRelu,
+                Some("tanh") => TransferFunction::Tanh,
+                Some("sigmoid") => TransferFunction::Sigmoid,
+                Some("sigmoid2") => TransferFunction::Sigmoid2,
+                Some("scaledtanh") =>
+                {
+                    TransferFunction::ScaledTanh{amplitude: number()?, slope: number()?}
+                },
+                _ => return Err(invalid())
+            };
+
+            let learning_rate = match parts.next()
+            {
+                Some(learning_rate) =>
+                {
+                    let learning_rate = learning_rate.parse::<f64>().map_err(|_| invalid())?;
+                    if !(learning_rate>0.0 && learning_rate.is_finite()) || parts.next().is_some()
+                    {
+                        return Err(invalid());
+                    }
+
+                    Some(learning_rate)
+                },
+                None => None
+            };
+
+            let settings = match (size, conv)
+            {
+                (Some(size), _) => LayerSettings::Dense(DefaultLayerSettings{size, transfer_function}),
+                (None, Some((channels, kernel_size, stride))) =>
+                {
+                    LayerSettings::Conv(ConvSettings{channels, kernel_size, stride, transfer_function})
+                },
+                (None, None) => return Err(invalid())
+            };
+
+            Ok((settings, learning_rate))
+        }).collect::<Result<Vec<_>, ConfigError>>()?;
+
+        //the output layer gets sized to the classes
+        if !matches!(layers.last(), Some((LayerSettings::Dense(_), _)))
+        {
+            return Err(ConfigError::InvalidValue(
+                "the last --arch layer has to be a size:function one".to_owned()
+            ));
+        }
+
+        Ok(layers.into_iter().unzip())
+    }
+
+    //channels, kernel size and stride of CHANNELSxKERNEL[/STRIDE]
+    fn parse_conv(value: &str) -> Option<(usize, usize, usize)>
+    {
+        let (shape, stride) = match value.split_once('/')
+        {
+            Some((shape, stride)) => (shape, stride.parse::<usize>().ok()?),
+            None => (value, 1)
+        };
+
+        let (channels, kernel_size) = shape.split_once('x')?;
+        let (channels, kernel_size) = (channels.parse::<usize>().ok()?, kernel_size.parse::<usize>().ok()?);
+
+        (channels>0 && kernel_size>0 && stride>0).then_some((channels, kernel_size, stride))
+    }
+
+    //comma separated layer indices
+    fn parse_indices(value: &str) -> Result<Vec<usize>, ConfigError>
+    {
+        value.split(',').map(|index|
+        {
+            index.trim().parse::<usize>()
+                .map_err(|err| ConfigError::InvalidValue(format!("{err}")))
+        }).collect::<Result<Vec<usize>, ConfigError>>()
+    }
+
+    fn parse_classes(value: &str) -> Result<Classes, ConfigError>
+    {
+        if value=="auto"
+        {
+            return Ok(Classes::Auto);
+        }
+
+        value.parse::<usize>().map(Classes::Amount)
+            .map_err(|_| ConfigError::InvalidValue(value.to_owned()))
+    }
+
+    //the amount of names gets checked against the classes in set_classes
+    fn parse_label_names(value: &str) -> Vec<String>
+    {
+        if value=="fashion"
+        {
+            FASHION_NAMES.iter().map(|name| name.to_string()).collect::<Vec<String>>()
+        } else
+        {
+            value.split(',').map(|name| name.trim().to_owned()).collect::<Vec<String>>()
+        }
+    }
+
+    fn parse_loss(value: &str) -> Result<Loss, ConfigError>
+    {
+        let invalid = || ConfigError::InvalidValue(value.to_owned());
+
+        let mut parts = value.trim().split(':');
+
+        let loss = match parts.next()
+        {
+            Some("mse") => Loss::Mse,
+            Some("huber") =>
+            {
+                let delta = parts.next().map(|delta| delta.parse::<f64>()).unwrap_or(Ok(1.0))
+                    .map_err(|_| invalid())?;
+
+                Loss::Huber{delta}
+            },
+            Some("crossentropy") => Loss::CrossEntropy,
+            _ => return Err(invalid())
+        };
+
+        if parts.next().is_some()
+        {
+            return Err(invalid());
+        }
+
+        Ok(loss)
+    }
+
+    //parses WxH
+    fn dimensions_arg(mut args: impl Iterator<Item=String>) -> Result<(u32, u32), ConfigError>
+    {
+        let value = args.next().ok_or(ConfigError::MissingValue)?;
+
+        let dimensions = value.split_once('x').and_then(|(width, height)|
+        {
+            Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+        }).filter(|(width, height)| *width>0 && *height>0);
+
+        dimensions.ok_or(ConfigError::InvalidValue(value))
+    }
+
+    //repeated flags and comma separated values both add more paths
+    fn paths_arg(
+        mut args: impl Iterator<Item=String>,
+        paths: &mut Vec<String>
+    ) -> Result<(), ConfigError>
+    {
+        let value = args.next().ok_or(ConfigError::MissingValue)?;
+        paths.extend(value.split(',').filter(|path| !path.is_empty()).map(|path| path.to_owned()));
+
+        Ok(())
+    }
+
+    fn number_arg<T>(mut args: impl Iterator<Item=String>) -> Result<T, ConfigError>
+    where
+        T: str::FromStr,
+        <T as str::FromStr>::Err: fmt::Display
+    {
+        args.next().ok_or(ConfigError::MissingValue)?.trim().parse()
+            .map_err(|err| ConfigError::InvalidValue(format!("{err}")))
+    }
+
+    pub fn help_message() -> !
+    {
+        println!("usage: {} [args]", env::args().next().unwrap());
+        println!("args:");
+        println!("    -h, --help         display this help messsage");
+        println!("    -v, --verbose      print the resolved settings and where they came from");
+        println!("    -M, --mode         program mode (default restart)");
+        println!("    -o, --output       output filename (default network.nn)");
+        println!("    --config           read args from a toml file keyed by the long flags, like");
+        println!("                       optimizer = \"sgd\" or arch = [\"50:tanh\", \"10:sigmoid\"],");
+        println!("                       args given on the command line win over the file");
+        println!("    --load-unchecksummed");
+        println!("                       accept networks saved without a checksum");
+        println!("    --threads          override the amount of threads used");
+        println!("    --grad-reduce      how the gradients of the threads get merged (default sum)");
+        println!("    --arch             layers as size:function,... (default 50:tanh,50:tanh,10:sigmoid)");
+        println!("                       size:function:rate starts that layers rprop learning rates at rate");
+        println!("                       conv6x5:relu is 6 channels of 5x5 kernels (conv6x5/2:relu strides");
+        println!("                       by 2) and pool2 takes the maximum of every 2x2 window, both see");
+        println!("                       the inputs as the images, the last layer has to be size:function");
+        println!("    --classes          output classes, or auto to use the largest training label");
+        println!("                       plus one (default 10, sizes the default output layer)");
+        println!("    --freeze           comma separated layer indices to not update");
+        println!("    --unfreeze         comma separated layer indices of a loaded network to update");
+        println!("                       again (frozen layers stay frozen when saved)");
+        println!("    --reset-lr         reset the rprop learning rates of a loaded network");
+        println!("    --zero-head        start a new network with a zeroed output layer");
+        println!("    --optimizer        weight update method (default rprop)");
+        println!("    --learning-rate    sgd learning rate (default 0.01)");
+        println!("    --momentum         sgd momentum (default 0.0)");
+        println!("    --restart-cycle    iterations in the first cosine annealing cycle of the sgd");
+        println!("                       learning rate, restarting it at full size after each cycle");
+        println!("    --restart-mult     length multiplier of every next restart cycle (default 1)");
+        println!("    --loss             error function to train with (default mse)");
+        println!("    -I, --iter         iterations to train for (default 10)");
+        println!("    --epochs           full shuffled passes over the dataset instead of --iter");
+        println!("    -b, --batch        batch size (default 10000)");
+        println!("    --accum            batches to accumulate per weight update (default 1)");
+        println!("    --label-smoothing  target value taken from the correct class (default 0)");
+        println!("    --hard-mining      after the first epoch draw this fraction of every batch from");
+        println!("                       the quarter of samples with the lowest margins, refreshed");
+        println!("                       every epoch");
+        println!("    --input-scale      divisor turning pixels into network inputs (default 255)");
+        println!("    --binarize         pixel threshold (0 to 1) turning images black and white");
+        println!("    --standardize      shift and scale inputs to the mean and deviation of the");
+        println!("                       training set");
+        println!("                       these three are saved with the network, -M train keeps");
+        println!("                       the saved ones unless any of them are given");
+        println!("    --multilabel       quoted csv labels like \"1,3\" are sets of classes, trains");
+        println!("                       sigmoid outputs with crossentropy (unless --loss is set)");
+        println!("    --target-accuracy  stop once the accuracy on samples held out of the training");
+        println!("                       set reaches this percent");
+        println!("    --validation-split share of the training set held out for --target-accuracy");
+        println!("                       (default 0.1)");
+        println!("    --validation-every iterations between checks of --target-accuracy (default 10)");
+        println!("    --max-time         stop training after this many seconds");
+        println!("    --report-every     print the loss of the last batch every N iterations");
+        println!("    --log              write the batch loss and accuracy to a csv file, every");
+        println!("                       --report-every iterations or at each progress step");
+        println!("    --append-log       add to the --log file instead of overwriting it");
+        println!("    -q, --quiet        dont print training progress");
+        println!("    --precision-digits decimals in reported and logged numbers (default 2)");
+        println!("    --progress-width   segments in the progress bar (default 30)");
+        println!("    --progress-style   emoji or ascii progress bar (default emoji)");
+        println!("    --list-model       print the shape of a saved network and exit");
+        println!("    --selftest         train, save and reload a tiny network on synthetic data,");
+        println!("                       prints OK if everything works (no dataset needed)");
+        println!("    --stats            print a summary of the training dataset first");
+        println!("    --dry-run          print a summary of the network without training");
+        println!("    --compare          evaluate the output network and this one on the test set");
+        println!("                       side by side and exit without training");
+        println!("    --no-test          only train and save, skip evaluating on the test set");
+        println!("    --calibration      print a confidence calibration table after testing");
+        println!("    --embedding-analysis");
+        println!("                       compare misclassified samples to the class means of the");
+        println!("                       penultimate layer with cosine similarity");
+        println!("    --robustness       also test on rotated, shifted and noisy copies of the");
+        println!("                       test set (training is unaffected)");
+        println!("    --show-samples     draw this many test images as text with their predictions");
+        println!("    --saliency         also draw how much every pixel affects the guess of a shown sample");
+        println!("    --labels-names     comma separated names for the labels (or fashion)");
+        println!("    --activation-stats print how many hidden neurons are dead or saturated");
+        println!("    --stagnation-warn  report how many rprop learning rates hit the minimum");
+        println!("    --quantize         also save an int8 quantized network to this file");
+        println!("    --prune            also save a copy with small weights zeroed to this file");
+        println!("    --prune-threshold  prune weights smaller than this (default 0.01)");
+        println!("    --prune-fraction   prune this fraction of the smallest weights instead");
+        println!("    --dot              also save the trained network as a graphviz graph");
+        println!("    --dot-threshold    leave out edges with a weaker weight (default 0)");
+        println!("    --export-npy       also save every layers weights and biases as numpy .npy");
+        println!("                       files into this directory");
+        println!("    --weight-hist      print histograms of every layers weights and biases");
+        println!("    --dump-weights     also write every weight and bias as a text grid to this");
+        println!("                       file, - prints it");
+        println!("    --dataset          dataset format (default idx)");
+        println!("    --csv-dims         csv image size as WxH (square by default)");
+        println!("    --combined-dims    image size of a combined dataset as WxH (required)");
+        println!("    --input-size       pad or center crop every image to WxH");
+        println!("    --cache            shuffled training data cache file (rebuilt when stale)");
+        println!("    -i, --images       mnist training images (or csv file)");
+        println!("    -l, --labels       mnist training labels");
+        println!("    -t, --test-images  optional test images (uses training otherwise)");
+        println!("    -T, --test-labels  optional test labels (uses training otherwise)");
+        println!("                       dataset paths can be repeated or comma separated to");
+        println!("                       train on several sources, idx images pair up with labels");
+        println!("program modes:");
+        println!("    restart, train");
+        println!("dataset formats:");
+        println!("    idx, csv, combined (label byte then pixel bytes per sample, no header)");
+        println!("optimizers:");
+        println!("    rprop (irprop-, skips the step when a gradient flips its sign),");
+        println!("    rprop+ (also undoes the previous step when a gradient flips), sgd");
+        println!("gradient reductions:");
+        println!("    sum, mean, median (mean and median are over the sums of every thread)");
+        println!("losses:");
+        println!("    mse, huber:delta (delta defaults to 1), crossentropy");
+        println!("transfer functions:");
+        println!("    nothing, relu, leakyrelu, tanh, sigmoid, sigmoid2, scaledtanh:amplitude:slope");
+
+        process::exit(1)
+    }
+}
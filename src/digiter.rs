@@ -1,22 +1,95 @@
 use std::{
-    io::{self, Read},
+    io::{self, Read, BufReader},
     slice,
     mem,
     path::Path,
     fs::File
 };
 
+pub use csv::*;
+pub use combined::*;
+pub use cache::*;
+pub use resize::*;
+pub use stats::*;
+pub use chain::*;
+pub use sampler::*;
+pub use augment::*;
+pub use memory::*;
+pub use synthetic::*;
+
+use crate::neural_net::Preprocessing;
+
+
+mod csv;
+mod combined;
+mod cache;
+mod resize;
+mod stats;
+mod chain;
+mod sampler;
+mod augment;
+mod memory;
+mod synthetic;
+
+
+//a source of labeled images
+pub trait Dataset: Iterator<Item=(u8, Vec<u8>)>
+{
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+
+    //values per pixel, the images store them channels last (every value of a pixel together)
+    fn channels(&self) -> u32
+    {
+        1
+    }
+
+    //values in a single image
+    #[allow(dead_code)]
+    fn image_size(&self) -> usize
+    {
+        self.width() as usize * self.height() as usize * self.channels() as usize
+    }
+
+    //labels with the images already turned into network inputs
+    #[allow(dead_code)]
+    fn normalized(self, preprocessing: Preprocessing) -> impl Iterator<Item=(u8, Vec<f64>)>
+    where
+        Self: Sized
+    {
+        self.map(move |(label, image)| (label, preprocessing.apply(&image)))
+    }
+}
+
+impl<T: Dataset + ?Sized> Dataset for Box<T>
+{
+    fn width(&self) -> u32
+    {
+        (**self).width()
+    }
+
+    fn height(&self) -> u32
+    {
+        (**self).height()
+    }
+
+    fn channels(&self) -> u32
+    {
+        (**self).channels()
+    }
+}
+
 
 struct LabelsReader
 {
     amount: u32,
     index: u32,
-    inner: File
+    inner: BufReader<File>
 }
 
 impl LabelsReader
 {
-    pub fn create(mut inner: File) -> io::Result<Self>
+    pub fn create(mut inner: BufReader<File>) -> io::Result<Self>
     {
         //checks magic
         {
@@ -52,7 +125,13 @@ impl Iterator for LabelsReader
         if self.index < self.amount
         {
             let mut buf = 0;
-            self.inner.read_exact(slice::from_mut(&mut buf)).unwrap();
+
+            //truncated file, stop early instead of panicking
+            if self.inner.read_exact(slice::from_mut(&mut buf)).is_err()
+            {
+                self.index = self.amount;
+                return None;
+            }
 
             self.index += 1;
 
@@ -64,30 +143,105 @@ impl Iterator for LabelsReader
     }
 }
 
+//type of the values in an idx file, the third byte of the magic
+#[derive(Debug, Clone, Copy)]
+enum ElementType
+{
+    UnsignedByte,
+    SignedByte,
+    Short,
+    Int,
+    Float,
+    Double
+}
+
+impl ElementType
+{
+    fn from_code(code: u8) -> io::Result<Self>
+    {
+        Ok(match code
+        {
+            0x08 => ElementType::UnsignedByte,
+            0x09 => ElementType::SignedByte,
+            0x0b => ElementType::Short,
+            0x0c => ElementType::Int,
+            0x0d => ElementType::Float,
+            0x0e => ElementType::Double,
+            x =>
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unsupported idx element type 0x{x:02x}")
+                ));
+            }
+        })
+    }
+
+    fn size(&self) -> usize
+    {
+        match self
+        {
+            ElementType::UnsignedByte | ElementType::SignedByte => 1,
+            ElementType::Short => 2,
+            ElementType::Int | ElementType::Float => 4,
+            ElementType::Double => 8
+        }
+    }
+
+    //scales a big endian value into a pixel, integers by their maximum and
+    //floating point values from the 0 to 1 range, negative values become 0
+    fn to_pixel(self, bytes: &[u8]) -> u8
+    {
+        let normalized = match self
+        {
+            ElementType::UnsignedByte => return bytes[0],
+            ElementType::SignedByte => bytes[0] as i8 as f64 / i8::MAX as f64,
+            ElementType::Short =>
+            {
+                i16::from_be_bytes(bytes.try_into().unwrap()) as f64 / i16::MAX as f64
+            },
+            ElementType::Int =>
+            {
+                i32::from_be_bytes(bytes.try_into().unwrap()) as f64 / i32::MAX as f64
+            },
+            ElementType::Float => f32::from_be_bytes(bytes.try_into().unwrap()) as f64,
+            ElementType::Double => f64::from_be_bytes(bytes.try_into().unwrap())
+        };
+
+        (normalized.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
+
 struct ImagesReader
 {
     amount: u32,
     index: u32,
     width: u32,
     height: u32,
+    channels: u32,
     image_size: usize,
-    inner: File
+    element_type: ElementType,
+    inner: BufReader<File>
 }
 
 impl ImagesReader
 {
-    pub fn create(mut inner: File) -> io::Result<Self>
+    pub fn create(mut inner: BufReader<File>) -> io::Result<Self>
     {
-        //checks magic
+        //checks magic, two zero bytes then the element type and 3 dimensions,
+        //or 4 with the channels as the last one
+        let (element_type, dimensions) =
         {
             let mut magic_buf = [0;mem::size_of::<u32>()];
             inner.read_exact(&mut magic_buf)?;
 
-            if u32::from_be_bytes(magic_buf)!=2051
+            if magic_buf[0]!=0 || magic_buf[1]!=0 || !(3..=4).contains(&magic_buf[3])
             {
                 return Err(io::Error::from(io::ErrorKind::InvalidData));
             }
-        }
+
+            (ElementType::from_code(magic_buf[2])?, magic_buf[3])
+        };
 
         let mut read_word = || -> io::Result<u32>
         {
@@ -101,10 +255,11 @@ impl ImagesReader
 
         let width = read_word()?;
         let height = read_word()?;
+        let channels = if dimensions==4 {read_word()?} else {1};
 
-        let image_size = (width * height) as usize;
+        let image_size = width as usize * height as usize * channels as usize;
 
-        Ok(ImagesReader{amount, index: 0, width, height, image_size, inner})
+        Ok(ImagesReader{amount, index: 0, width, height, channels, image_size, element_type, inner})
     }
 
     pub fn len(&self) -> usize
@@ -121,6 +276,11 @@ impl ImagesReader
     {
         self.height
     }
+
+    pub fn channels(&self) -> u32
+    {
+        self.channels
+    }
 }
 
 impl Iterator for ImagesReader
@@ -131,12 +291,25 @@ impl Iterator for ImagesReader
     {
         if self.index < self.amount
         {
-            let mut buf = vec![0; self.image_size];
-            self.inner.read_exact(&mut buf).unwrap();
+            let element_size = self.element_type.size();
+            let mut buf = vec![0; self.image_size * element_size];
+
+            if self.inner.read_exact(&mut buf).is_err()
+            {
+                self.index = self.amount;
+                return None;
+            }
 
             self.index += 1;
 
-            Some(buf)
+            if let ElementType::UnsignedByte = self.element_type
+            {
+                return Some(buf);
+            }
+
+            Some(buf.chunks_exact(element_size)
+                .map(|bytes| self.element_type.to_pixel(bytes))
+                .collect())
         } else
         {
             None
@@ -156,30 +329,38 @@ impl Digiter
     {
         if Path::new(labels_path).try_exists().and(Path::new(images_path).try_exists())?
         {
-            let labels = LabelsReader::create(File::open(labels_path)?)?;
-            let images = ImagesReader::create(File::open(images_path)?)?;
+            let labels = LabelsReader::create(BufReader::new(File::open(labels_path)?))?;
+            let images = ImagesReader::create(BufReader::new(File::open(images_path)?))?;
 
             if labels.len()!=images.len()
             {
                 return Err(io::Error::from(io::ErrorKind::InvalidData));
             }
 
-            return Ok(Digiter{labels, images});
+            Ok(Digiter{labels, images})
         } else
         {
-            return Err(io::Error::from(io::ErrorKind::NotFound));
+            Err(io::Error::from(io::ErrorKind::NotFound))
         }
     }
+}
 
-    pub fn width(&self) -> u32
+impl Dataset for Digiter
+{
+    fn width(&self) -> u32
     {
         self.images.width()
     }
 
-    pub fn height(&self) -> u32
+    fn height(&self) -> u32
     {
         self.images.height()
     }
+
+    fn channels(&self) -> u32
+    {
+        self.images.channels()
+    }
 }
 
 impl Iterator for Digiter
@@ -188,12 +369,462 @@ impl Iterator for Digiter
 
     fn next(&mut self) -> Option<Self::Item>
     {
-        if let Some(label) = self.labels.next()
+        let label = self.labels.next()?;
+
+        Some((label, self.images.next()?))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::{env, fs, io::Write};
+
+    #[test]
+    fn csv_rows()
+    {
+        let path = env::temp_dir().join("digitsrecog_csv_rows.csv");
+        fs::write(&path, "label,p0,p1,p2,p3\n3,0,10,20,255\n\n7,1,2,3,4\n").unwrap();
+
+        let digiter = CsvDigiter::create(path.to_str().unwrap(), None).unwrap();
+        assert_eq!((digiter.width(), digiter.height()), (2, 2));
+
+        let samples = digiter.collect::<Vec<_>>();
+        assert_eq!(samples, vec![(3, vec![0, 10, 20, 255]), (7, vec![1, 2, 3, 4])]);
+
+        fs::write(&path, "3,0,10,20,255\n7,1,2,x,4\n").unwrap();
+        let err = CsvDigiter::create(path.to_str().unwrap(), None).err().unwrap();
+        assert!(err.to_string().ends_with(":2: invalid pixel: x"));
+
+        fs::write(&path, "3,0,10,20,255,1,2\n").unwrap();
+        assert!(CsvDigiter::create(path.to_str().unwrap(), None).is_err());
+
+        let digiter = CsvDigiter::create(path.to_str().unwrap(), Some((3, 2))).unwrap();
+        assert_eq!((digiter.width(), digiter.height()), (3, 2));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn combined()
+    {
+        let path = env::temp_dir().join("digitsrecog_combined.bin");
+        fs::write(&path, [3, 0, 10, 20, 255, 7, 1, 2, 3, 4]).unwrap();
+
+        let digiter = CombinedDigiter::create(path.to_str().unwrap(), (2, 2)).unwrap();
+        assert_eq!((digiter.width(), digiter.height()), (2, 2));
+        assert_eq!(
+            digiter.collect::<Vec<_>>(),
+            vec![(3, vec![0, 10, 20, 255]), (7, vec![1, 2, 3, 4])]
+        );
+
+        let err = CombinedDigiter::create(path.to_str().unwrap(), (3, 1)).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        //the pixel count wraps around to 0 in u32
+        let err = CombinedDigiter::create(path.to_str().unwrap(), (65536, 65536)).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = CombinedDigiter::create(path.to_str().unwrap(), (0, 2)).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn csv_label_sets()
+    {
+        let path = env::temp_dir().join("digitsrecog_csv_label_sets.csv");
+        fs::write(&path, "label,p0,p1,p2,p3\n\"1, 3\",0,10,20,255\n7,1,2,3,4\n").unwrap();
+
+        let digiter = CsvDigiter::create(path.to_str().unwrap(), None).unwrap();
+        assert_eq!(digiter.label_sets(), [vec![1, 3], vec![7]]);
+        assert_eq!(digiter.map(|(label, _)| label).collect::<Vec<_>>(), vec![1, 7]);
+
+        fs::write(&path, "\"1,3,0,10,20,255\n").unwrap();
+        let err = CsvDigiter::create(path.to_str().unwrap(), None).err().unwrap();
+        assert!(err.to_string().ends_with(":1: unclosed quote"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn resized()
+    {
+        let path = env::temp_dir().join("digitsrecog_resized.csv");
+        fs::write(&path, "3,1,2,3,4\n").unwrap();
+
+        let open = || CsvDigiter::create(path.to_str().unwrap(), None).unwrap();
+
+        let padded = ResizedDigiter::new(open(), (4, 3));
+        assert_eq!((padded.width(), padded.height()), (4, 3));
+        assert_eq!(
+            padded.collect::<Vec<_>>(),
+            vec![(3, vec![0, 1, 2, 0, 0, 3, 4, 0, 0, 0, 0, 0])]
+        );
+
+        let cropped = ResizedDigiter::new(open(), (1, 2));
+        assert_eq!(cropped.collect::<Vec<_>>(), vec![(3, vec![1, 3])]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn chained()
+    {
+        let first = env::temp_dir().join("digitsrecog_chained_first.csv");
+        let second = env::temp_dir().join("digitsrecog_chained_second.csv");
+        fs::write(&first, "3,1,2,3,4\n5,5,6,7,8\n").unwrap();
+        fs::write(&second, "9,9,9,9,9\n").unwrap();
+
+        let open = |path: &Path, size| -> Box<dyn Dataset>
         {
-            Some((label, self.images.next().unwrap()))
-        } else
+            Box::new(CsvDigiter::create(path.to_str().unwrap(), size).unwrap())
+        };
+
+        let chained = ChainedDigiter::new(vec![open(&first, None), open(&second, None)]).unwrap();
+        assert_eq!((chained.width(), chained.height()), (2, 2));
+        assert_eq!(chained.map(|(label, _)| label).collect::<Vec<_>>(), vec![3, 5, 9]);
+
+        let err = ChainedDigiter::new(vec![open(&first, None), open(&second, Some((4, 1)))])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "datasets have different image sizes (2x2 and 4x1)");
+
+        assert!(ChainedDigiter::new(Vec::new()).is_err());
+
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn memory()
+    {
+        let dataset = MemoryDigiter::from_vecs((2, 1), vec![4, 7], vec![vec![1, 2], vec![3, 4]])
+            .unwrap();
+
+        assert_eq!((dataset.width(), dataset.height()), (2, 1));
+        assert_eq!(dataset.collect::<Vec<_>>(), vec![(4, vec![1, 2]), (7, vec![3, 4])]);
+
+        assert!(MemoryDigiter::from_vecs((2, 1), vec![4], Vec::new()).is_err());
+        assert!(MemoryDigiter::from_vecs((2, 1), vec![4], vec![vec![1, 2, 3]]).is_err());
+    }
+
+    #[test]
+    fn synthetic()
+    {
+        let (width, height) = (8, 8);
+        let image_size = (width * height) as usize;
+
+        let train = generate_dataset(3, 200, 4, width, height).collect::<Vec<_>>();
+        assert_eq!(train, generate_dataset(3, 200, 4, width, height).collect::<Vec<_>>());
+        assert_ne!(train, generate_dataset(4, 200, 4, width, height).collect::<Vec<_>>());
+
+        assert!(train.iter().all(|(label, image)| *label<4 && image.len()==image_size));
+
+        //nearest class mean has to get almost everything right on other samples
+        let means = (0..4).map(|class|
         {
-            None
+            let images = train.iter().filter(|(label, _)| *label==class).collect::<Vec<_>>();
+
+            (0..image_size).map(|pixel|
+            {
+                images.iter().map(|(_, image)| image[pixel] as f64).sum::<f64>()
+                    / images.len() as f64
+            }).collect::<Vec<f64>>()
+        }).collect::<Vec<Vec<f64>>>();
+
+        let test = generate_dataset(9, 100, 4, width, height).collect::<Vec<_>>();
+        let correct = test.iter().filter(|(label, image)|
+        {
+            let distance = |mean: &Vec<f64>|
+            {
+                mean.iter().zip(image.iter()).map(|(a, b)| (a - *b as f64).powi(2)).sum::<f64>()
+            };
+
+            let closest = (0..4).min_by(|a, b|
+            {
+                distance(&means[*a]).total_cmp(&distance(&means[*b]))
+            }).unwrap();
+
+            closest==*label as usize
+        }).count();
+
+        assert!(correct >= 95, "{correct} out of 100");
+    }
+
+    #[test]
+    fn sampler_epochs()
+    {
+        let mut sampler = EpochSampler::new(37, 5);
+
+        let mut first = sampler.by_ref().take(37).collect::<Vec<usize>>();
+        assert_eq!(sampler.epoch(), 0);
+
+        let mut second = sampler.by_ref().take(37).collect::<Vec<usize>>();
+        assert_eq!(sampler.epoch(), 1);
+
+        assert_ne!(first, second);
+
+        first.sort_unstable();
+        second.sort_unstable();
+        assert_eq!(first, (0..37).collect::<Vec<usize>>());
+        assert_eq!(second, first);
+
+        assert_eq!(EpochSampler::new(0, 5).next(), None);
+    }
+
+    #[test]
+    fn hard_mining()
+    {
+        const SAMPLES: usize = 100;
+        const BATCH: usize = 20;
+
+        let mut sampler = EpochSampler::new(SAMPLES, 5);
+        let mut miner = HardMiner::new(0.5, 5);
+        assert_eq!(miner.batch_share(BATCH), 0);
+
+        //the first 10 samples are the hard ones
+        let margins = (0..SAMPLES).map(|index| if index<10 {-0.5} else {0.5 + index as f64 * 0.001})
+            .collect::<Vec<f64>>();
+        miner.update(&margins);
+
+        let hard = miner.batch_share(BATCH);
+        assert_eq!(hard, 10);
+
+        let mut counts = [0_usize; SAMPLES];
+        for _ in 0..1000
+        {
+            sampler.by_ref().take(BATCH - hard).chain(miner.by_ref().take(hard))
+                .for_each(|index| counts[index] += 1);
+        }
+
+        let mean = |range: std::ops::Range<usize>|
+        {
+            counts[range.clone()].iter().sum::<usize>() as f64 / range.len() as f64
+        };
+
+        //the hardest samples come up way more than the easiest ones which only the epochs give
+        assert!(mean(0..10) > 3.0 * mean(50..100));
+        assert!(counts[50..].iter().all(|count| *count>0));
+    }
+
+    #[test]
+    fn normalized()
+    {
+        let path = env::temp_dir().join("digitsrecog_normalized.csv");
+        fs::write(&path, "3,0,51,255,102\n").unwrap();
+
+        let normalized = CsvDigiter::create(path.to_str().unwrap(), None).unwrap()
+            .normalized(Preprocessing::default())
+            .collect::<Vec<_>>();
+
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(normalized, vec![(3, vec![0.0, 0.2, 1.0, 0.4])]);
+    }
+
+    #[test]
+    fn stats()
+    {
+        let path = env::temp_dir().join("digitsrecog_stats.csv");
+        fs::write(&path, "3,1,2,3,4\n7,0,9,9,9\n3,5,5,5,5\n").unwrap();
+
+        let stats = DatasetStats::compute(CsvDigiter::create(path.to_str().unwrap(), None).unwrap());
+
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(stats.samples, 3);
+        assert_eq!((stats.width, stats.height), (2, 2));
+        assert_eq!(stats.label_counts.into_iter().collect::<Vec<_>>(), vec![(3, 2), (7, 1)]);
+        assert_eq!(stats.pixel_range, Some((0, 9)));
+    }
+
+    #[test]
+    fn cache_roundtrip()
+    {
+        let csv_path = env::temp_dir().join("digitsrecog_cache_source.csv");
+        let cache_path = env::temp_dir().join("digitsrecog_cache.bin");
+        let csv_path = csv_path.to_str().unwrap();
+        let cache_path = cache_path.to_str().unwrap();
+
+        let rows = (0..20).map(|i| format!("{},{},{},{},{}\n", i % 10, i, i+1, i+2, i+3))
+            .collect::<String>();
+        fs::write(csv_path, rows).unwrap();
+
+        let _ = fs::remove_file(cache_path);
+
+        let open = || CsvDigiter::create(csv_path, None);
+
+        let created = CachedDigiter::create(cache_path, "csv", &[csv_path], open).unwrap();
+        let seed = created.seed();
+        let created = created.collect::<Vec<_>>();
+
+        let loaded = CachedDigiter::create(cache_path, "csv", &[csv_path], || -> io::Result<CsvDigiter>
+        {
+            panic!("cache should be fresh");
+        }).unwrap();
+        assert_eq!(loaded.seed(), seed);
+        assert_eq!((loaded.width(), loaded.height()), (2, 2));
+
+        let loaded = loaded.collect::<Vec<_>>();
+        assert_eq!(created, loaded);
+
+        let mut sorted = loaded.clone();
+        sorted.sort_by_key(|(_, image)| image[0]);
+        assert_eq!(sorted, open().unwrap().collect::<Vec<_>>());
+
+        //same file read as a different kind of dataset
+        let mut rebuilt = false;
+        CachedDigiter::create(cache_path, "combined", &[csv_path], ||
+        {
+            rebuilt = true;
+            open()
+        }).unwrap();
+        assert!(rebuilt);
+
+        //a cache from another source with a newer mtime
+        let other_path = env::temp_dir().join("digitsrecog_cache_other.csv");
+        let other_path = other_path.to_str().unwrap();
+        fs::write(other_path, "1,1,2,3,4\n").unwrap();
+
+        let mut rebuilt = false;
+        let other = CachedDigiter::create(cache_path, "csv", &[other_path], ||
+        {
+            rebuilt = true;
+            CsvDigiter::create(other_path, None)
+        }).unwrap();
+        assert!(rebuilt);
+        assert_eq!(other.count(), 1);
+
+        fs::remove_file(other_path).unwrap();
+        fs::remove_file(csv_path).unwrap();
+        fs::remove_file(cache_path).unwrap();
+    }
+
+    #[test]
+    fn element_types()
+    {
+        let labels_path = env::temp_dir().join("digitsrecog_element_labels");
+        let images_path = env::temp_dir().join("digitsrecog_element_images");
+
+        {
+            let mut labels = File::create(&labels_path).unwrap();
+            labels.write_all(&2049_u32.to_be_bytes()).unwrap();
+            labels.write_all(&3_u32.to_be_bytes()).unwrap();
+            labels.write_all(&[4, 5, 6]).unwrap();
         }
+
+        let write_images = |magic: u32, pixels: &[u8]|
+        {
+            let mut images = File::create(&images_path).unwrap();
+            for word in [magic, 3, 2, 1]
+            {
+                images.write_all(&word.to_be_bytes()).unwrap();
+            }
+            images.write_all(pixels).unwrap();
+        };
+
+        let read = ||
+        {
+            Digiter::create(labels_path.to_str().unwrap(), images_path.to_str().unwrap())
+                .map(|digiter| digiter.map(|(_, image)| image).collect::<Vec<_>>())
+        };
+
+        let floats = [0.0_f32, 1.0, 0.5, 2.0, -1.0, 0.2].iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect::<Vec<u8>>();
+        write_images(0x0d03, &floats);
+        assert_eq!(read().unwrap(), vec![vec![0, 255], vec![128, 255], vec![0, 51]]);
+
+        let shorts = [0_i16, i16::MAX, i16::MAX / 2, -5, 0, 0].iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect::<Vec<u8>>();
+        write_images(0x0b03, &shorts);
+        assert_eq!(read().unwrap(), vec![vec![0, 255], vec![127, 0], vec![0, 0]]);
+
+        write_images(0x0a03, &[0; 6]);
+        let err = read().err().unwrap();
+        assert!(err.to_string().contains("unsupported idx element type 0x0a"));
+
+        fs::remove_file(labels_path).unwrap();
+        fs::remove_file(images_path).unwrap();
+    }
+
+    #[test]
+    fn channels()
+    {
+        let labels_path = env::temp_dir().join("digitsrecog_channels_labels");
+        let images_path = env::temp_dir().join("digitsrecog_channels_images");
+
+        {
+            let mut labels = File::create(&labels_path).unwrap();
+            labels.write_all(&2049_u32.to_be_bytes()).unwrap();
+            labels.write_all(&2_u32.to_be_bytes()).unwrap();
+            labels.write_all(&[4, 5]).unwrap();
+
+            let mut images = File::create(&images_path).unwrap();
+            for word in [0x0804_u32, 2, 2, 1, 3]
+            {
+                images.write_all(&word.to_be_bytes()).unwrap();
+            }
+            images.write_all(&(0..12).collect::<Vec<u8>>()).unwrap();
+        }
+
+        let digiter = Digiter::create(
+            labels_path.to_str().unwrap(),
+            images_path.to_str().unwrap()
+        ).unwrap();
+
+        assert_eq!((digiter.width(), digiter.height(), digiter.channels()), (2, 1, 3));
+        assert_eq!(digiter.image_size(), 6);
+
+        let padded = ResizedDigiter::new(digiter, (3, 1));
+        assert_eq!(padded.channels(), 3);
+        assert_eq!(
+            padded.collect::<Vec<_>>(),
+            vec![(4, vec![0, 1, 2, 3, 4, 5, 0, 0, 0]), (5, vec![6, 7, 8, 9, 10, 11, 0, 0, 0])]
+        );
+
+        fs::remove_file(labels_path).unwrap();
+        fs::remove_file(images_path).unwrap();
+    }
+
+    #[test]
+    fn truncated_files()
+    {
+        let labels_path = env::temp_dir().join("digitsrecog_truncated_labels");
+        let images_path = env::temp_dir().join("digitsrecog_truncated_images");
+
+        let declared = 10_u32;
+        let actual = 4;
+
+        {
+            let mut labels = File::create(&labels_path).unwrap();
+            labels.write_all(&2049_u32.to_be_bytes()).unwrap();
+            labels.write_all(&declared.to_be_bytes()).unwrap();
+            labels.write_all(&[1; 10]).unwrap();
+
+            let mut images = File::create(&images_path).unwrap();
+            for word in [2051, declared, 2, 2]
+            {
+                images.write_all(&word.to_be_bytes()).unwrap();
+            }
+            images.write_all(&vec![255; actual * 4 + 2]).unwrap();
+        }
+
+        let digiter = Digiter::create(
+            labels_path.to_str().unwrap(),
+            images_path.to_str().unwrap()
+        ).unwrap();
+
+        let samples = digiter.collect::<Vec<_>>();
+
+        fs::remove_file(labels_path).unwrap();
+        fs::remove_file(images_path).unwrap();
+
+        assert_eq!(samples.len(), actual);
+        assert!(samples.iter().all(|(label, image)| *label==1 && image==&vec![255; 4]));
     }
 }
\ No newline at end of file
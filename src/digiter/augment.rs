@@ -0,0 +1,160 @@
+use std::fmt;
+
+use rand::Rng;
+
+
+//transforms on network inputs (0..1 pixels of a width x height image),
+//anything moved in from outside the image is 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Augmentation
+{
+    //degrees clockwise around the center
+    Rotate(f64),
+    //pixels, positive moves right and down
+    Shift(i32, i32),
+    //standard deviation of added gaussian noise, clamped back to 0..1
+    Noise(f64)
+}
+
+impl Augmentation
+{
+    pub fn apply(&self, inputs: &[f64], (width, height): (u32, u32), rng: &mut impl Rng) -> Vec<f64>
+    {
+        let (width, height) = (width as i64, height as i64);
+        let pixel = |x: i64, y: i64|
+        {
+            if (0..width).contains(&x) && (0..height).contains(&y)
+            {
+                inputs[(y * width + x) as usize]
+            } else
+            {
+                0.0
+            }
+        };
+
+        match *self
+        {
+            Augmentation::Rotate(degrees) =>
+            {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                let (center_x, center_y) = ((width - 1) as f64 / 2.0, (height - 1) as f64 / 2.0);
+
+                (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).map(|(x, y)|
+                {
+                    //rotate the output position back to where it came from
+                    let (x, y) = (x as f64 - center_x, y as f64 - center_y);
+                    let source_x = center_x + x * cos + y * sin;
+                    let source_y = center_y - x * sin + y * cos;
+
+                    let (left, top) = (source_x.floor(), source_y.floor());
+                    let (fraction_x, fraction_y) = (source_x - left, source_y - top);
+                    let (left, top) = (left as i64, top as i64);
+
+                    let upper = pixel(left, top) * (1.0 - fraction_x)
+                        + pixel(left + 1, top) * fraction_x;
+                    let lower = pixel(left, top + 1) * (1.0 - fraction_x)
+                        + pixel(left + 1, top + 1) * fraction_x;
+
+                    upper * (1.0 - fraction_y) + lower * fraction_y
+                }).collect()
+            },
+            Augmentation::Shift(shift_x, shift_y) =>
+            {
+                let (shift_x, shift_y) = (shift_x as i64, shift_y as i64);
+
+                (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).map(|(x, y)|
+                {
+                    pixel(x - shift_x, y - shift_y)
+                }).collect()
+            },
+            Augmentation::Noise(deviation) =>
+            {
+                inputs.iter().map(|input|
+                {
+                    //box muller
+                    let (a, b): (f64, f64) = (1.0 - rng.gen::<f64>(), rng.gen());
+                    let normal = (-2.0 * a.ln()).sqrt() * (std::f64::consts::TAU * b).cos();
+
+                    (input + normal * deviation).clamp(0.0, 1.0)
+                }).collect()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Augmentation
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Augmentation::Rotate(degrees) => write!(f, "rotated {degrees} degrees"),
+            Augmentation::Shift(x, y) => write!(f, "shifted by ({x}, {y})"),
+            Augmentation::Noise(deviation) => write!(f, "noise (deviation {deviation})")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //3x3 with a single lit pixel in the top right corner
+    fn corner() -> Vec<f64>
+    {
+        vec![
+            0.0, 0.0, 1.0,
+            0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0
+        ]
+    }
+
+    #[test]
+    fn rotate_and_shift()
+    {
+        let mut rng = rand::thread_rng();
+
+        let rotated = Augmentation::Rotate(90.0).apply(&corner(), (3, 3), &mut rng);
+        let expected = [
+            0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0,
+            0.0, 0.0, 1.0
+        ];
+
+        rotated.iter().zip(expected.iter()).for_each(|(rotated, expected)|
+        {
+            assert!((rotated - expected).abs() < 1e-9, "{rotated} {expected}");
+        });
+
+        assert_eq!(Augmentation::Rotate(0.0).apply(&corner(), (3, 3), &mut rng), corner());
+
+        assert_eq!(
+            Augmentation::Shift(-1, 1).apply(&corner(), (3, 3), &mut rng),
+            vec![
+                0.0, 0.0, 0.0,
+                0.0, 1.0, 0.0,
+                0.0, 0.0, 0.0
+            ]
+        );
+
+        assert_eq!(Augmentation::Shift(1, 0).apply(&corner(), (3, 3), &mut rng), vec![0.0; 9]);
+    }
+
+    #[test]
+    fn noise()
+    {
+        let mut rng = rand::thread_rng();
+
+        assert_eq!(Augmentation::Noise(0.0).apply(&corner(), (3, 3), &mut rng), corner());
+
+        let inputs = vec![0.5; 1000];
+        let noisy = Augmentation::Noise(0.1).apply(&inputs, (1000, 1), &mut rng);
+
+        assert!(noisy.iter().all(|pixel| (0.0..=1.0).contains(pixel)));
+        assert!(noisy.iter().any(|pixel| *pixel!=0.5));
+
+        let mean = noisy.iter().sum::<f64>() / noisy.len() as f64;
+        assert!((mean - 0.5).abs() < 0.05, "{mean}");
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    io::{self, Read, Write, BufWriter},
+    fs::{self, File},
+    path::Path,
+    mem,
+    vec
+};
+
+use rand::{SeedableRng, seq::SliceRandom, rngs::StdRng};
+
+use super::Dataset;
+
+
+const MAGIC: u32 = 0x64726332;
+const HEADER_SIZE: usize = 7 * mem::size_of::<u32>();
+
+//whole dataset shuffled once and stored contiguously
+//layout: magic, seed (u64), amount, width, height, key length, key bytes
+//then [label, pixels...] per sample
+struct Header
+{
+    seed: u64,
+    amount: usize,
+    width: u32,
+    height: u32,
+    key: String
+}
+
+pub struct CachedDigiter
+{
+    seed: u64,
+    width: u32,
+    height: u32,
+    samples: vec::IntoIter<(u8, Vec<u8>)>
+}
+
+impl CachedDigiter
+{
+    //reads the cache if it was built from the same kind of dataset and the same sources
+    //and is newer than all of them, otherwise rebuilds it
+    pub fn create<D: Dataset>(
+        cache_path: &str,
+        kind: &str,
+        sources: &[&str],
+        dataset: impl FnOnce() -> io::Result<D>
+    ) -> io::Result<Self>
+    {
+        let key = Self::source_key(kind, sources)?;
+
+        if Self::is_fresh(cache_path, &key, sources)?
+        {
+            Self::load(cache_path)
+        } else
+        {
+            let dataset = dataset()?;
+
+            //the cache layout only has room for a width and height
+            if dataset.channels()!=1
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "only single channel images can be cached"
+                ));
+            }
+
+            let cached = Self::shuffled(dataset, rand::random());
+            cached.save(cache_path, &key)?;
+
+            Ok(cached)
+        }
+    }
+
+    pub fn seed(&self) -> u64
+    {
+        self.seed
+    }
+
+    //what the cache is built from, one line for the kind then a line per canonical source
+    fn source_key(kind: &str, sources: &[&str]) -> io::Result<String>
+    {
+        sources.iter().try_fold(kind.to_owned(), |mut key, source|
+        {
+            key.push('\n');
+            key.push_str(&fs::canonicalize(source)?.to_string_lossy());
+
+            Ok(key)
+        })
+    }
+
+    fn is_fresh(cache_path: &str, key: &str, sources: &[&str]) -> io::Result<bool>
+    {
+        if !Path::new(cache_path).try_exists()?
+        {
+            return Ok(false);
+        }
+
+        //an older or broken cache just gets rebuilt
+        let header = match Self::read_header(&mut File::open(cache_path)?)
+        {
+            Ok(header) => header,
+            Err(_) => return Ok(false)
+        };
+
+        if header.key!=key
+        {
+            return Ok(false);
+        }
+
+        let cache_modified = fs::metadata(cache_path)?.modified()?;
+
+        for source in sources
+        {
+            if fs::metadata(source)?.modified()? > cache_modified
+            {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn shuffled(dataset: impl Dataset, seed: u64) -> Self
+    {
+        let (width, height) = (dataset.width(), dataset.height());
+
+        let mut samples = dataset.collect::<Vec<_>>();
+        samples.shuffle(&mut StdRng::seed_from_u64(seed));
+
+        CachedDigiter{seed, width, height, samples: samples.into_iter()}
+    }
+
+    fn save(&self, cache_path: &str, key: &str) -> io::Result<()>
+    {
+        let mut writer = BufWriter::new(File::create(cache_path)?);
+
+        writer.write_all(&MAGIC.to_be_bytes())?;
+        writer.write_all(&self.seed.to_be_bytes())?;
+        writer.write_all(&(self.samples.len() as u32).to_be_bytes())?;
+        writer.write_all(&self.width.to_be_bytes())?;
+        writer.write_all(&self.height.to_be_bytes())?;
+        writer.write_all(&(key.len() as u32).to_be_bytes())?;
+        writer.write_all(key.as_bytes())?;
+
+        for (label, image) in self.samples.as_slice()
+        {
+            writer.write_all(&[*label])?;
+            writer.write_all(image)?;
+        }
+
+        writer.flush()
+    }
+
+    fn read_header(reader: &mut impl Read) -> io::Result<Header>
+    {
+        let invalid = || io::Error::from(io::ErrorKind::InvalidData);
+
+        let mut header = [0; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+
+        let word = |index: usize| -> u32
+        {
+            let start = index * mem::size_of::<u32>();
+            u32::from_be_bytes(header[start..start+mem::size_of::<u32>()].try_into().unwrap())
+        };
+
+        if word(0)!=MAGIC
+        {
+            return Err(invalid());
+        }
+
+        let seed = (word(1) as u64) << 32 | word(2) as u64;
+
+        let mut key = vec![0; word(6) as usize];
+        reader.read_exact(&mut key)?;
+
+        Ok(Header{
+            seed,
+            amount: word(3) as usize,
+            width: word(4),
+            height: word(5),
+            key: String::from_utf8(key).map_err(|_| invalid())?
+        })
+    }
+
+    fn load(cache_path: &str) -> io::Result<Self>
+    {
+        let data = fs::read(cache_path)?;
+
+        let mut body = data.as_slice();
+        let Header{seed, amount, width, height, ..} = Self::read_header(&mut body)?;
+
+        let record_size = 1 + width as usize * height as usize;
+        if amount.checked_mul(record_size)!=Some(body.len())
+        {
+            return Err(io::Error::from(io::ErrorKind::InvalidData));
+        }
+
+        let samples = body.chunks_exact(record_size).map(|record|
+        {
+            (record[0], record[1..].to_vec())
+        }).collect::<Vec<_>>();
+
+        Ok(CachedDigiter{seed, width, height, samples: samples.into_iter()})
+    }
+}
+
+impl Dataset for CachedDigiter
+{
+    fn width(&self) -> u32
+    {
+        self.width
+    }
+
+    fn height(&self) -> u32
+    {
+        self.height
+    }
+}
+
+impl Iterator for CachedDigiter
+{
+    type Item = (u8, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        self.samples.next()
+    }
+}
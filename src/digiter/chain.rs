@@ -0,0 +1,85 @@
+use std::{
+    io,
+    iter,
+    vec
+};
+
+use super::Dataset;
+
+
+//samples of several datasets one after another, every image has to be the same size
+pub struct ChainedDigiter
+{
+    width: u32,
+    height: u32,
+    channels: u32,
+    samples: iter::Flatten<vec::IntoIter<Box<dyn Dataset>>>
+}
+
+impl ChainedDigiter
+{
+    pub fn new(datasets: Vec<Box<dyn Dataset>>) -> io::Result<Self>
+    {
+        let (width, height, channels) = datasets.first()
+            .map(|dataset| (dataset.width(), dataset.height(), dataset.channels()))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no datasets to chain"))?;
+
+        let different = datasets.iter().find(|dataset|
+        {
+            (dataset.width(), dataset.height())!=(width, height)
+        });
+
+        if let Some(dataset) = different
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "datasets have different image sizes ({width}x{height} and {}x{})",
+                    dataset.width(),
+                    dataset.height()
+                )
+            ));
+        }
+
+        if let Some(dataset) = datasets.iter().find(|dataset| dataset.channels()!=channels)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "datasets have different channel counts ({channels} and {})",
+                    dataset.channels()
+                )
+            ));
+        }
+
+        Ok(ChainedDigiter{width, height, channels, samples: datasets.into_iter().flatten()})
+    }
+}
+
+impl Dataset for ChainedDigiter
+{
+    fn width(&self) -> u32
+    {
+        self.width
+    }
+
+    fn height(&self) -> u32
+    {
+        self.height
+    }
+
+    fn channels(&self) -> u32
+    {
+        self.channels
+    }
+}
+
+impl Iterator for ChainedDigiter
+{
+    type Item = (u8, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        self.samples.next()
+    }
+}
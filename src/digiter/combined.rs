@@ -0,0 +1,109 @@
+use std::{
+    io::{self, Read, BufReader},
+    fs::File
+};
+
+use super::Dataset;
+
+
+//headerless records of a label byte followed by the pixel bytes of its image,
+//the image size isnt stored so it has to be given
+pub struct CombinedDigiter
+{
+    width: u32,
+    height: u32,
+    record_size: usize,
+    amount: usize,
+    index: usize,
+    inner: BufReader<File>
+}
+
+impl CombinedDigiter
+{
+    pub fn create(path: &str, (width, height): (u32, u32)) -> io::Result<Self>
+    {
+        if width==0 || height==0
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{path}: {width}x{height} images are empty")
+            ));
+        }
+
+        let file = File::open(path)?;
+
+        //in u64 so big dimensions cant overflow the multiplication
+        let record_size = 1 + u64::from(width) * u64::from(height);
+        let length = file.metadata()?.len();
+
+        if length % record_size!=0
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{path}: {length} bytes isnt a whole number of {width}x{height} records \
+                    ({record_size} bytes each)"
+                )
+            ));
+        }
+
+        let amount = (length / record_size) as usize;
+
+        //a file with at least one record proves a record fits in memory
+        let record_size = usize::try_from(record_size).map_err(|_|
+        {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("{path}: records too big"))
+        })?;
+
+        Ok(CombinedDigiter{
+            width,
+            height,
+            record_size,
+            amount,
+            index: 0,
+            inner: BufReader::new(file)
+        })
+    }
+}
+
+impl Dataset for CombinedDigiter
+{
+    fn width(&self) -> u32
+    {
+        self.width
+    }
+
+    fn height(&self) -> u32
+    {
+        self.height
+    }
+}
+
+impl Iterator for CombinedDigiter
+{
+    type Item = (u8, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        if self.index < self.amount
+        {
+            let mut record = vec![0; self.record_size];
+
+            //the file changed since it was checked, stop early instead of panicking
+            if self.inner.read_exact(&mut record).is_err()
+            {
+                self.index = self.amount;
+                return None;
+            }
+
+            self.index += 1;
+
+            let image = record.split_off(1);
+
+            Some((record[0], image))
+        } else
+        {
+            None
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    io::{self, BufRead, BufReader},
+    fs::File,
+    vec
+};
+
+use super::Dataset;
+
+
+//rows of a label followed by pixel values, an optional header row is skipped
+//a quoted label like "1,3" is a set of labels, the first one is the samples label
+pub struct CsvDigiter
+{
+    width: u32,
+    height: u32,
+    label_sets: Vec<Vec<u8>>,
+    samples: vec::IntoIter<(u8, Vec<u8>)>
+}
+
+//splits the label field (without quotes) from the pixels
+fn split_label(line: &str) -> Option<(&str, &str)>
+{
+    match line.strip_prefix('"')
+    {
+        Some(quoted) =>
+        {
+            let (label, rest) = quoted.split_once('"')?;
+
+            Some((label, rest.trim_start().strip_prefix(',').unwrap_or(rest)))
+        },
+        None => Some(line.split_once(',').unwrap_or((line, "")))
+    }
+}
+
+impl CsvDigiter
+{
+    pub fn create(path: &str, dimensions: Option<(u32, u32)>) -> io::Result<Self>
+    {
+        let invalid = |line: usize, message: &str|
+        {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{path}:{line}: {message}"))
+        };
+
+        let mut samples = Vec::new();
+        let mut label_sets = Vec::new();
+        let mut dimensions = dimensions;
+
+        for (index, line) in BufReader::new(File::open(path)?).lines().enumerate()
+        {
+            let line = line?;
+            let line_number = index + 1;
+
+            if line.trim().is_empty()
+            {
+                continue;
+            }
+
+            let (label, pixels) = split_label(line.trim())
+                .ok_or_else(|| invalid(line_number, "unclosed quote"))?;
+
+            let label_set = label.split(',').map(|label| label.trim().parse::<u8>())
+                .collect::<Result<Vec<u8>, _>>();
+
+            let label_set = match label_set
+            {
+                Ok(label_set) => label_set,
+                Err(_) if index==0 => continue,
+                Err(_) => return Err(invalid(line_number, &format!("invalid label: {label}")))
+            };
+
+            let image = pixels.split(',').map(|field| field.trim()).map(|field|
+            {
+                field.parse::<u8>()
+                    .map_err(|_| invalid(line_number, &format!("invalid pixel: {field}")))
+            }).collect::<io::Result<Vec<u8>>>()?;
+
+            let (width, height) = match dimensions
+            {
+                Some(dimensions) => dimensions,
+                None =>
+                {
+                    let side = (image.len() as f64).sqrt().round() as u32;
+                    if (side * side) as usize!=image.len()
+                    {
+                        return Err(invalid(
+                            line_number,
+                            &format!("{} pixels isnt a square image, use --csv-dims", image.len())
+                        ));
+                    }
+
+                    *dimensions.insert((side, side))
+                }
+            };
+
+            let image_size = width as usize * height as usize;
+            if image_size!=image.len()
+            {
+                return Err(invalid(
+                    line_number,
+                    &format!("expected {image_size} pixels, got {}", image.len())
+                ));
+            }
+
+            samples.push((label_set[0], image));
+            label_sets.push(label_set);
+        }
+
+        let (width, height) = dimensions.ok_or_else(|| invalid(0, "no samples"))?;
+
+        Ok(CsvDigiter{width, height, label_sets, samples: samples.into_iter()})
+    }
+
+    //every label of every row in file order, unaffected by iterating
+    pub fn label_sets(&self) -> &[Vec<u8>]
+    {
+        &self.label_sets
+    }
+}
+
+impl Dataset for CsvDigiter
+{
+    fn width(&self) -> u32
+    {
+        self.width
+    }
+
+    fn height(&self) -> u32
+    {
+        self.height
+    }
+}
+
+impl Iterator for CsvDigiter
+{
+    type Item = (u8, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        self.samples.next()
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    io,
+    iter,
+    vec
+};
+
+use super::Dataset;
+
+
+//labeled images already in memory, for synthetic data and tests
+pub struct MemoryDigiter
+{
+    width: u32,
+    height: u32,
+    samples: iter::Zip<vec::IntoIter<u8>, vec::IntoIter<Vec<u8>>>
+}
+
+impl MemoryDigiter
+{
+    pub fn from_vecs(
+        (width, height): (u32, u32),
+        labels: Vec<u8>,
+        images: Vec<Vec<u8>>
+    ) -> io::Result<Self>
+    {
+        if labels.len()!=images.len()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} labels for {} images", labels.len(), images.len())
+            ));
+        }
+
+        let image_size = width as usize * height as usize;
+        if let Some(image) = images.iter().find(|image| image.len()!=image_size)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("image with {} pixels in a {width}x{height} dataset", image.len())
+            ));
+        }
+
+        Ok(MemoryDigiter{width, height, samples: labels.into_iter().zip(images)})
+    }
+}
+
+impl Dataset for MemoryDigiter
+{
+    fn width(&self) -> u32
+    {
+        self.width
+    }
+
+    fn height(&self) -> u32
+    {
+        self.height
+    }
+}
+
+impl Iterator for MemoryDigiter
+{
+    type Item = (u8, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        self.samples.next()
+    }
+}
@@ -0,0 +1,74 @@
+use super::Dataset;
+
+
+//pads with zeros or center crops every image to the target size
+pub struct ResizedDigiter<D>
+{
+    inner: D,
+    width: u32,
+    height: u32
+}
+
+impl<D: Dataset> ResizedDigiter<D>
+{
+    pub fn new(inner: D, (width, height): (u32, u32)) -> Self
+    {
+        ResizedDigiter{inner, width, height}
+    }
+
+    fn resize(&self, image: &[u8]) -> Vec<u8>
+    {
+        let (source_width, source_height) = (self.inner.width() as i64, self.inner.height() as i64);
+        let channels = self.inner.channels() as usize;
+
+        let offset_x = (self.width as i64 - source_width) / 2;
+        let offset_y = (self.height as i64 - source_height) / 2;
+
+        (0..self.height as i64).flat_map(|y|
+        {
+            (0..self.width as i64).map(move |x| (x - offset_x, y - offset_y))
+        }).flat_map(|(x, y)|
+        {
+            (0..channels).map(move |channel|
+            {
+                if (0..source_width).contains(&x) && (0..source_height).contains(&y)
+                {
+                    image[(y * source_width + x) as usize * channels + channel]
+                } else
+                {
+                    0
+                }
+            })
+        }).collect()
+    }
+}
+
+impl<D: Dataset> Dataset for ResizedDigiter<D>
+{
+    fn width(&self) -> u32
+    {
+        self.width
+    }
+
+    fn height(&self) -> u32
+    {
+        self.height
+    }
+
+    fn channels(&self) -> u32
+    {
+        self.inner.channels()
+    }
+}
+
+impl<D: Dataset> Iterator for ResizedDigiter<D>
+{
+    type Item = (u8, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        let (label, image) = self.inner.next()?;
+
+        Some((label, self.resize(&image)))
+    }
+}
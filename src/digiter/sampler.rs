@@ -0,0 +1,116 @@
+use rand::{Rng, SeedableRng, seq::SliceRandom, rngs::StdRng};
+
+
+//share of the samples with the lowest margins that hard mining draws from
+const HARD_POOL: f64 = 0.25;
+
+
+//endless sample indices, every index comes up once per epoch before any repeats
+//and the order gets reshuffled between epochs
+pub struct EpochSampler
+{
+    rng: StdRng,
+    order: Vec<usize>,
+    position: usize,
+    epoch: usize
+}
+
+impl EpochSampler
+{
+    pub fn new(len: usize, seed: u64) -> Self
+    {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut order = (0..len).collect::<Vec<usize>>();
+        order.shuffle(&mut rng);
+
+        EpochSampler{rng, order, position: 0, epoch: 0}
+    }
+
+    //full passes finished so far
+    #[allow(dead_code)]
+    pub fn epoch(&self) -> usize
+    {
+        self.epoch
+    }
+}
+
+impl Iterator for EpochSampler
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        if self.order.is_empty()
+        {
+            return None;
+        }
+
+        if self.position==self.order.len()
+        {
+            self.order.shuffle(&mut self.rng);
+            self.position = 0;
+            self.epoch += 1;
+        }
+
+        let index = self.order[self.position];
+        self.position += 1;
+
+        Some(index)
+    }
+}
+
+//endless indices of the hardest samples (lowest margins) from the last update,
+//meant to make up a fraction of every batch next to an EpochSampler
+pub struct HardMiner
+{
+    rng: StdRng,
+    fraction: f64,
+    pool: Vec<usize>
+}
+
+impl HardMiner
+{
+    pub fn new(fraction: f64, seed: u64) -> Self
+    {
+        HardMiner{rng: StdRng::seed_from_u64(seed), fraction, pool: Vec::new()}
+    }
+
+    //replaces the pool with the lowest margin samples, margins are indexed like the samples
+    pub fn update(&mut self, margins: &[f64])
+    {
+        let mut order = (0..margins.len()).collect::<Vec<usize>>();
+        order.sort_by(|a, b| margins[*a].total_cmp(&margins[*b]));
+
+        let size = (margins.len() as f64 * HARD_POOL).ceil() as usize;
+        order.truncate(size);
+
+        self.pool = order;
+    }
+
+    //hard samples in a batch of this size, none until the first update
+    pub fn batch_share(&self, batch_size: usize) -> usize
+    {
+        if self.pool.is_empty()
+        {
+            return 0;
+        }
+
+        ((batch_size as f64 * self.fraction).round() as usize).min(batch_size)
+    }
+}
+
+impl Iterator for HardMiner
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        if self.pool.is_empty()
+        {
+            return None;
+        }
+
+        Some(self.pool[self.rng.gen_range(0..self.pool.len())])
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    fmt,
+    collections::BTreeMap
+};
+
+use super::Dataset;
+
+
+//summary of a whole dataset, useful to catch imbalanced classes or wrong sizes
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetStats
+{
+    pub samples: usize,
+    pub width: u32,
+    pub height: u32,
+    pub label_counts: BTreeMap<u8, usize>,
+    //none for an empty dataset
+    pub pixel_range: Option<(u8, u8)>
+}
+
+impl DatasetStats
+{
+    pub fn compute(dataset: impl Dataset) -> Self
+    {
+        let (width, height) = (dataset.width(), dataset.height());
+
+        let mut samples = 0;
+        let mut label_counts = BTreeMap::new();
+        let mut pixel_range: Option<(u8, u8)> = None;
+
+        for (label, image) in dataset
+        {
+            samples += 1;
+            *label_counts.entry(label).or_insert(0) += 1;
+
+            pixel_range = image.iter().fold(pixel_range, |range, pixel|
+            {
+                let (low, high) = range.unwrap_or((*pixel, *pixel));
+
+                Some((low.min(*pixel), high.max(*pixel)))
+            });
+        }
+
+        DatasetStats{samples, width, height, label_counts, pixel_range}
+    }
+}
+
+impl fmt::Display for DatasetStats
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        writeln!(f, "samples: {}, image size: {}x{}", self.samples, self.width, self.height)?;
+
+        if let Some((low, high)) = self.pixel_range
+        {
+            writeln!(f, "pixel values: {low} to {high}")?;
+        }
+
+        //precision of the percentages, {:.3} gives 3 decimals
+        let precision = f.precision().unwrap_or(2);
+
+        write!(f, "labels:")?;
+        for (label, count) in self.label_counts.iter()
+        {
+            let percent = *count as f64 / self.samples as f64 * 100.0;
+            write!(f, "\n    {label}: {count} ({percent:.precision$}%)")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use super::MemoryDigiter;
+
+
+//background noise added to every pixel
+const NOISE: u8 = 32;
+
+
+//a learnable dataset without any files, every class is a bright blob in its own cell
+//of a grid over the image, the blobs jitter around their cell centers and sit on
+//top of noise, the same seed always gives the same samples
+pub fn generate_dataset(
+    seed: u64,
+    count: usize,
+    classes: usize,
+    width: u32,
+    height: u32
+) -> MemoryDigiter
+{
+    assert!((1..=u8::MAX as usize + 1).contains(&classes), "classes must fit in a label byte");
+    assert!(width>0 && height>0, "image cant be empty");
+
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let columns = (classes as f64).sqrt().ceil() as usize;
+    let rows = classes.div_ceil(columns);
+
+    let cell_width = width as f64 / columns as f64;
+    let cell_height = height as f64 / rows as f64;
+
+    //small enough that neighbouring blobs barely overlap
+    let spread = cell_width.min(cell_height) / 4.0;
+
+    let labels = (0..count).map(|index| (index % classes) as u8).collect::<Vec<u8>>();
+    let images = labels.iter().map(|label|
+    {
+        let label = *label as usize;
+
+        let center_x = ((label % columns) as f64 + 0.5) * cell_width
+            + rng.gen_range(-0.5..=0.5) * spread;
+        let center_y = ((label / columns) as f64 + 0.5) * cell_height
+            + rng.gen_range(-0.5..=0.5) * spread;
+
+        (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).map(|(x, y)|
+        {
+            let distance = (x as f64 + 0.5 - center_x).powi(2)
+                + (y as f64 + 0.5 - center_y).powi(2);
+
+            let blob = (-distance / (2.0 * spread * spread)).exp() * (u8::MAX - NOISE) as f64;
+
+            blob as u8 + rng.gen_range(0..=NOISE)
+        }).collect::<Vec<u8>>()
+    }).collect::<Vec<Vec<u8>>>();
+
+    MemoryDigiter::from_vecs((width, height), labels, images)
+        .expect("generated images always match the dimensions")
+}
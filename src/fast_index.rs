@@ -0,0 +1,73 @@
+//indexing for the hot loops, unchecked in release builds and bounds checked in debug builds
+//(or with the checked feature) so an out of range index panics instead of being undefined behavior
+pub trait FastIndex<T>
+{
+    //safety: index has to be in bounds
+    unsafe fn fast_get(&self, index: usize) -> &T;
+
+    //only training writes through it, which needs std
+    //safety: index has to be in bounds
+    #[cfg(feature = "std")]
+    unsafe fn fast_get_mut(&mut self, index: usize) -> &mut T;
+}
+
+impl<T> FastIndex<T> for [T]
+{
+    #[inline(always)]
+    unsafe fn fast_get(&self, index: usize) -> &T
+    {
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        {
+            &self[index]
+        }
+
+        #[cfg(not(any(debug_assertions, feature = "checked")))]
+        {
+            unsafe{ self.get_unchecked(index) }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[inline(always)]
+    unsafe fn fast_get_mut(&mut self, index: usize) -> &mut T
+    {
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        {
+            &mut self[index]
+        }
+
+        #[cfg(not(any(debug_assertions, feature = "checked")))]
+        {
+            unsafe{ self.get_unchecked_mut(index) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn in_bounds()
+    {
+        let mut values = [1, 2, 3];
+
+        unsafe
+        {
+            *values.fast_get_mut(1) += 10;
+            assert_eq!(*values.fast_get(1), 12);
+            assert_eq!(*values.fast_get(2), 3);
+        }
+    }
+
+    #[test]
+    #[cfg(any(debug_assertions, feature = "checked"))]
+    #[should_panic(expected = "index out of bounds")]
+    fn out_of_bounds()
+    {
+        let values = [1, 2, 3];
+
+        unsafe{ values.fast_get(3); }
+    }
+}
@@ -0,0 +1,637 @@
+use std::{
+    slice,
+    ptr,
+    panic::{self, AssertUnwindSafe},
+    ffi::{CStr, c_char}
+};
+
+use crate::neural_net::*;
+
+
+const IMAGE_SIZE: usize = 28*28;
+
+//one value per digit, networks with any other amount of outputs (like from --classes)
+//only give zeroes here and need network_outputs with network_recognize_into instead
+#[repr(C)]
+pub struct Guesses
+{
+    guesses: [f64;10]
+}
+
+impl Guesses
+{
+    fn zeroed() -> Self
+    {
+        Guesses{guesses: [0.0; 10]}
+    }
+
+    fn from_outputs(outputs: Vec<f64>) -> Option<Self>
+    {
+        Some(Guesses{guesses: outputs.try_into().ok()?})
+    }
+}
+
+//any failure (including a panic) turns into all zero guesses instead of
+//unwinding into the callers stack
+fn guarded(f: impl FnOnce() -> Option<Guesses>) -> Guesses
+{
+    panic::catch_unwind(AssertUnwindSafe(f)).ok().flatten().unwrap_or_else(Guesses::zeroed)
+}
+
+fn load_network(network_path: *const c_char) -> Option<NeuralNet>
+{
+    load_network_with(network_path, false)
+}
+
+fn load_network_with(network_path: *const c_char, allow_unchecksummed: bool) -> Option<NeuralNet>
+{
+    if network_path.is_null()
+    {
+        return None;
+    }
+
+    let network_path = unsafe{ CStr::from_ptr(network_path) }.to_str().ok()?;
+
+    if allow_unchecksummed
+    {
+        NeuralNet::load_unchecksummed(network_path).ok()
+    } else
+    {
+        NeuralNet::load(network_path).ok()
+    }
+}
+
+//none on any failure, recognize turns that into zeroes
+fn recognize_guesses(network_path: *const c_char, image: *const u8) -> Option<Guesses>
+{
+    if image.is_null()
+    {
+        return None;
+    }
+
+    let mut network = load_network(network_path)?;
+
+    if network.inputs_amount()!=IMAGE_SIZE
+    {
+        return None;
+    }
+
+    let image = unsafe{ slice::from_raw_parts(image, IMAGE_SIZE) };
+
+    let guesses = network.predict_raw(image);
+
+    Guesses::from_outputs(guesses)
+}
+
+/// # Safety
+/// network_path has to be null or a nul terminated string and image null or 784 readable bytes
+#[no_mangle]
+pub unsafe extern "C" fn recognize(network_path: *const c_char, image: *const u8) -> Guesses
+{
+    guarded(|| recognize_guesses(network_path, image))
+}
+
+//same as recognize but the guesses are a softmax over the output layer before its
+//transfer function so they sum to 1, recognize gives the independent sigmoid outputs
+//which dont, the best guess is the same for both
+/// # Safety
+/// same as recognize
+#[no_mangle]
+pub unsafe extern "C" fn recognize_probs(network_path: *const c_char, image: *const u8) -> Guesses
+{
+    guarded(||
+    {
+        if image.is_null()
+        {
+            return None;
+        }
+
+        let mut network = load_network(network_path)?;
+
+        if network.inputs_amount()!=IMAGE_SIZE
+        {
+            return None;
+        }
+
+        let image = unsafe{ slice::from_raw_parts(image, IMAGE_SIZE) };
+
+        Guesses::from_outputs(softmax(&network.logits(&network.image_inputs(image))))
+    })
+}
+
+//confidence is the highest output of the network, the sigmoid outputs are
+//independent so they arent normalized into probabilities, returns the digit
+//if its confidence is above min_confidence and -1 otherwise (or on failure)
+/// # Safety
+/// same as recognize
+#[no_mangle]
+pub unsafe extern "C" fn recognize_threshold(
+    network_path: *const c_char,
+    image: *const u8,
+    min_confidence: f64
+) -> i32
+{
+    //zeroed guesses would pass a negative min_confidence so failures are checked first
+    let guesses = match panic::catch_unwind(|| recognize_guesses(network_path, image))
+    {
+        Ok(Some(guesses)) => guesses.guesses,
+        _ => return -1
+    };
+
+    let digit = argmax(&guesses);
+
+    if guesses[digit] > min_confidence
+    {
+        digit as i32
+    } else
+    {
+        -1
+    }
+}
+
+//pixels go into the network as is, so they have to be already preprocessed the way the
+//network was trained (scaled, binarized and standardized if it was), recognize does that
+//for raw bytes
+/// # Safety
+/// network_path has to be null or a nul terminated string and pixels null or len readable values
+#[no_mangle]
+pub unsafe extern "C" fn recognize_f64(
+    network_path: *const c_char,
+    pixels: *const f64,
+    len: usize
+) -> Guesses
+{
+    guarded(||
+    {
+        if pixels.is_null()
+        {
+            return None;
+        }
+
+        let mut network = load_network(network_path)?;
+
+        if network.inputs_amount()!=len
+        {
+            return None;
+        }
+
+        let pixels = unsafe{ slice::from_raw_parts(pixels, len) };
+
+        Guesses::from_outputs(network.feedforward(pixels))
+    })
+}
+
+//post activation values of a layer (the last layer gives the same values as recognize),
+//returns null on failure or if theres no such layer, the length is given by
+//recognize_features_len and the values have to be freed with features_free
+/// # Safety
+/// network_path has to be null or a nul terminated string and image null or as many
+/// readable bytes as the network has inputs
+#[no_mangle]
+pub unsafe extern "C" fn recognize_features(
+    network_path: *const c_char,
+    image: *const u8,
+    layer_index: usize
+) -> *mut f64
+{
+    panic::catch_unwind(||
+    {
+        if image.is_null()
+        {
+            return None;
+        }
+
+        let mut network = load_network(network_path)?;
+
+        if layer_index>=network.layers_amount()
+        {
+            return None;
+        }
+
+        let image = unsafe{ slice::from_raw_parts(image, network.inputs_amount()) };
+
+        let mut activations = network.activations(&network.image_inputs(image));
+
+        Some(activations.swap_remove(layer_index).into_boxed_slice())
+    }).ok().flatten()
+        .map(|features| Box::into_raw(features) as *mut f64)
+        .unwrap_or(ptr::null_mut())
+}
+
+//amount of values recognize_features returns for that layer, 0 on failure
+/// # Safety
+/// network_path has to be null or a nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn recognize_features_len(network_path: *const c_char, layer_index: usize) -> usize
+{
+    panic::catch_unwind(||
+    {
+        let network = load_network(network_path)?;
+
+        (layer_index<network.layers_amount())
+            .then(|| network.layer_size(layer_index))
+    }).ok().flatten().unwrap_or(0)
+}
+
+/// # Safety
+/// features has to be null or come from recognize_features with its len, and only get freed once
+#[no_mangle]
+pub unsafe extern "C" fn features_free(features: *mut f64, len: usize)
+{
+    if !features.is_null()
+    {
+        drop(unsafe{ Box::from_raw(ptr::slice_from_raw_parts_mut(features, len)) });
+    }
+}
+
+//loads a network once for repeated recognize calls, returns null on failure
+//the handle is never mutated after loading so network_recognize can be called
+//from multiple threads at the same time with the same handle
+/// # Safety
+/// network_path has to be null or a nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_load(network_path: *const c_char) -> *mut NeuralNet
+{
+    panic::catch_unwind(|| load_network(network_path)).ok().flatten()
+        .map(|network| Box::into_raw(Box::new(network)))
+        .unwrap_or(ptr::null_mut())
+}
+
+//network_load which also accepts files saved before checksums were added
+/// # Safety
+/// network_path has to be null or a nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn network_load_unchecksummed(network_path: *const c_char) -> *mut NeuralNet
+{
+    panic::catch_unwind(|| load_network_with(network_path, true)).ok().flatten()
+        .map(|network| Box::into_raw(Box::new(network)))
+        .unwrap_or(ptr::null_mut())
+}
+
+/// # Safety
+/// network has to be null or a handle from network_load which wasnt freed, image null or
+/// as many readable bytes as the network has inputs
+#[no_mangle]
+pub unsafe extern "C" fn network_recognize(network: *const NeuralNet, image: *const u8) -> Guesses
+{
+    guarded(||
+    {
+        if network.is_null() || image.is_null()
+        {
+            return None;
+        }
+
+        let network = unsafe{ &*network };
+
+        let image = unsafe{ slice::from_raw_parts(image, network.inputs_amount()) };
+
+        //each call gets its own scratch so the network can be shared
+        let mut scratch = network.scratch();
+        let guesses = network.feedforward_pure(&network.image_inputs(image), &mut scratch);
+
+        Guesses::from_outputs(guesses)
+    })
+}
+
+//amount of values network_recognize_into writes, 0 on failure
+/// # Safety
+/// network has to be null or a handle from network_load which wasnt freed
+#[no_mangle]
+pub unsafe extern "C" fn network_outputs(network: *const NeuralNet) -> usize
+{
+    if network.is_null()
+    {
+        return 0;
+    }
+
+    let network = unsafe{ &*network };
+
+    network.layer_size(network.layers_amount() - 1)
+}
+
+//network_recognize for any amount of classes, the outputs go into the callers array
+//which needs room for network_outputs values, returns how many got written, 0 on failure
+/// # Safety
+/// network has to be null or a handle from network_load which wasnt freed, image null or
+/// as many readable bytes as the network has inputs and out null or room for len values
+#[no_mangle]
+pub unsafe extern "C" fn network_recognize_into(
+    network: *const NeuralNet,
+    image: *const u8,
+    out: *mut f64,
+    len: usize
+) -> usize
+{
+    //out is only written to after everything that can panic
+    panic::catch_unwind(AssertUnwindSafe(||
+    {
+        if network.is_null() || image.is_null() || out.is_null()
+        {
+            return None;
+        }
+
+        let network = unsafe{ &*network };
+
+        let image = unsafe{ slice::from_raw_parts(image, network.inputs_amount()) };
+
+        let mut scratch = network.scratch();
+        let outputs = network.feedforward_pure(&network.image_inputs(image), &mut scratch);
+
+        if outputs.len()>len
+        {
+            return None;
+        }
+
+        unsafe{ slice::from_raw_parts_mut(out, outputs.len()) }.copy_from_slice(&outputs);
+
+        Some(outputs.len())
+    })).ok().flatten().unwrap_or(0)
+}
+
+//recognizes count images laid out back to back with a loaded handle, the best digit
+//and its confidence (the highest output, like recognize_threshold) of every image go
+//into the callers arrays which need room for count values each, returns how many
+//images got recognized, 0 on failure
+/// # Safety
+/// network has to be null or a handle from network_load which wasnt freed, images null
+/// or count images worth of readable bytes and both outputs null or room for count values
+#[no_mangle]
+pub unsafe extern "C" fn network_predict_stream(
+    network: *const NeuralNet,
+    images: *const u8,
+    count: usize,
+    out_digits: *mut i32,
+    out_confidences: *mut f64
+) -> usize
+{
+    //the output arrays are only written to so a panic cant leave them broken
+    panic::catch_unwind(AssertUnwindSafe(||
+    {
+        if network.is_null() || images.is_null() || out_digits.is_null()
+            || out_confidences.is_null()
+        {
+            return None;
+        }
+
+        let network = unsafe{ &*network };
+
+        let image_size = network.inputs_amount();
+        let images = unsafe{ slice::from_raw_parts(images, count.checked_mul(image_size)?) };
+
+        let digits = unsafe{ slice::from_raw_parts_mut(out_digits, count) };
+        let confidences = unsafe{ slice::from_raw_parts_mut(out_confidences, count) };
+
+        //one scratch for the whole stream, its still per call so the handle can be shared
+        let mut scratch = network.scratch();
+        images.chunks_exact(image_size).zip(digits.iter_mut().zip(confidences.iter_mut()))
+            .for_each(|(image, (digit, confidence))|
+            {
+                let outputs = network.feedforward_pure(&network.image_inputs(image), &mut scratch);
+                let best = argmax(&outputs);
+
+                *digit = best as i32;
+                *confidence = outputs[best];
+            });
+
+        Some(count)
+    })).ok().flatten().unwrap_or(0)
+}
+
+/// # Safety
+/// network has to be null or a handle from network_load, its unusable after this
+#[no_mangle]
+pub unsafe extern "C" fn network_free(network: *mut NeuralNet)
+{
+    if !network.is_null()
+    {
+        drop(unsafe{ Box::from_raw(network) });
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::{env, fs, ffi::CString};
+
+    #[test]
+    fn threshold()
+    {
+        let layers = [DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}];
+        let path = env::temp_dir().join("digitsrecog_ffi_threshold.nn");
+        NeuralNet::create(IMAGE_SIZE, &layers).save(path.to_str().unwrap()).unwrap();
+
+        let image = [0_u8; IMAGE_SIZE];
+        let network_path = CString::new(path.to_str().unwrap()).unwrap();
+
+        let guesses = unsafe{ recognize(network_path.as_ptr(), image.as_ptr()) }.guesses;
+        let accepted = unsafe{ recognize_threshold(network_path.as_ptr(), image.as_ptr(), 0.0) };
+        let rejected = unsafe{ recognize_threshold(network_path.as_ptr(), image.as_ptr(), 1.0) };
+        let no_image = unsafe{ recognize_threshold(network_path.as_ptr(), ptr::null(), -1.0) };
+
+        fs::remove_file(path).unwrap();
+
+        let best = guesses.iter().cloned().fold(f64::MIN, f64::max);
+        assert_eq!(guesses[accepted as usize], best);
+        assert_eq!(rejected, -1);
+        assert_eq!(no_image, -1);
+    }
+
+    #[test]
+    fn probabilities()
+    {
+        let layers = [DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}];
+        let path = env::temp_dir().join("digitsrecog_ffi_probabilities.nn");
+        NeuralNet::create(IMAGE_SIZE, &layers).save(path.to_str().unwrap()).unwrap();
+
+        let image = [200_u8; IMAGE_SIZE];
+        let network_path = CString::new(path.to_str().unwrap()).unwrap();
+
+        let guesses = unsafe{ recognize(network_path.as_ptr(), image.as_ptr()) }.guesses;
+        let probabilities = unsafe{ recognize_probs(network_path.as_ptr(), image.as_ptr()) }.guesses;
+
+        fs::remove_file(path).unwrap();
+
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(argmax(&probabilities), argmax(&guesses));
+    }
+
+    #[test]
+    fn features()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 5, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(IMAGE_SIZE, &layers);
+
+        let path = env::temp_dir().join("digitsrecog_ffi_features.nn");
+        network.save(path.to_str().unwrap()).unwrap();
+
+        let image = [100_u8; IMAGE_SIZE];
+        let network_path = CString::new(path.to_str().unwrap()).unwrap();
+
+        let len = unsafe{ recognize_features_len(network_path.as_ptr(), 0) };
+        let features = unsafe{ recognize_features(network_path.as_ptr(), image.as_ptr(), 0) };
+
+        let missing_len = unsafe{ recognize_features_len(network_path.as_ptr(), 2) };
+        let missing = unsafe{ recognize_features(network_path.as_ptr(), image.as_ptr(), 2) };
+
+        fs::remove_file(path).unwrap();
+
+        let expected = network.activations(&[100.0 / 255.0; IMAGE_SIZE]).remove(0);
+
+        assert_eq!(len, 5);
+        assert_eq!(unsafe{ slice::from_raw_parts(features, len) }, expected.as_slice());
+        unsafe{ features_free(features, len) };
+
+        assert_eq!(missing_len, 0);
+        assert!(missing.is_null());
+    }
+
+    #[test]
+    fn predict_stream()
+    {
+        let layers = [DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}];
+        let path = env::temp_dir().join("digitsrecog_ffi_predict_stream.nn");
+        NeuralNet::create(IMAGE_SIZE, &layers).save(path.to_str().unwrap()).unwrap();
+
+        let network_path = CString::new(path.to_str().unwrap()).unwrap();
+        let network = unsafe{ network_load(network_path.as_ptr()) };
+
+        fs::remove_file(path).unwrap();
+
+        let images = [0_u8, 120, 255].iter().flat_map(|value| [*value; IMAGE_SIZE])
+            .collect::<Vec<u8>>();
+
+        let mut digits = [-1; 3];
+        let mut confidences = [0.0; 3];
+        let predicted = unsafe{ network_predict_stream(
+            network,
+            images.as_ptr(),
+            3,
+            digits.as_mut_ptr(),
+            confidences.as_mut_ptr()
+        ) };
+
+        assert_eq!(predicted, 3);
+
+        for (index, image) in images.chunks(IMAGE_SIZE).enumerate()
+        {
+            let guesses = unsafe{ network_recognize(network, image.as_ptr()) }.guesses;
+            let best = argmax(&guesses);
+
+            assert_eq!(digits[index], best as i32);
+            assert_eq!(confidences[index], guesses[best]);
+        }
+
+        let failed = unsafe{ network_predict_stream(
+            network,
+            ptr::null(),
+            3,
+            digits.as_mut_ptr(),
+            confidences.as_mut_ptr()
+        ) };
+        assert_eq!(failed, 0);
+
+        unsafe{ network_free(network) };
+    }
+
+    #[test]
+    fn any_class_count()
+    {
+        let layers = [DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}];
+        let path = env::temp_dir().join("digitsrecog_ffi_any_class_count.nn");
+        let mut created = NeuralNet::create(IMAGE_SIZE, &layers);
+        created.save(path.to_str().unwrap()).unwrap();
+
+        let network_path = CString::new(path.to_str().unwrap()).unwrap();
+        let network = unsafe{ network_load(network_path.as_ptr()) };
+
+        fs::remove_file(path).unwrap();
+
+        let image = [80_u8; IMAGE_SIZE];
+
+        let len = unsafe{ network_outputs(network) };
+        assert_eq!(len, 3);
+
+        let mut outputs = [0.0; 3];
+        let written = unsafe{ network_recognize_into(
+            network,
+            image.as_ptr(),
+            outputs.as_mut_ptr(),
+            outputs.len()
+        ) };
+
+        assert_eq!(written, 3);
+        assert_eq!(outputs.as_slice(), created.predict_raw(&image).as_slice());
+
+        let too_short = unsafe{ network_recognize_into(
+            network,
+            image.as_ptr(),
+            outputs.as_mut_ptr(),
+            2
+        ) };
+        assert_eq!(too_short, 0);
+
+        assert_eq!(unsafe{ network_outputs(ptr::null()) }, 0);
+
+        unsafe{ network_free(network) };
+    }
+
+    #[test]
+    fn unchecksummed_handle()
+    {
+        let layers = [DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}];
+        let path = env::temp_dir().join("digitsrecog_ffi_unchecksummed.nn");
+
+        //the old format was the cbor without anything around it
+        let mut old = Vec::new();
+        ciborium::ser::into_writer(&NeuralNet::create(IMAGE_SIZE, &layers), &mut old).unwrap();
+        fs::write(&path, old).unwrap();
+
+        let network_path = CString::new(path.to_str().unwrap()).unwrap();
+        let strict = unsafe{ network_load(network_path.as_ptr()) };
+        let network = unsafe{ network_load_unchecksummed(network_path.as_ptr()) };
+
+        fs::remove_file(path).unwrap();
+
+        assert!(strict.is_null());
+        assert!(!network.is_null());
+
+        unsafe{ network_free(network) };
+    }
+
+    #[test]
+    fn failures_return_zeroes()
+    {
+        let image = [0_u8; IMAGE_SIZE];
+        let pixels = [0.0; IMAGE_SIZE];
+
+        let missing = CString::new("this/network/doesnt/exist.nn").unwrap();
+
+        assert_eq!(unsafe{ recognize(missing.as_ptr(), image.as_ptr()) }.guesses, [0.0; 10]);
+        assert_eq!(unsafe{ recognize(ptr::null(), image.as_ptr()) }.guesses, [0.0; 10]);
+        assert_eq!(unsafe{ recognize_probs(missing.as_ptr(), image.as_ptr()) }.guesses, [0.0; 10]);
+        assert_eq!(
+            unsafe{ recognize_f64(missing.as_ptr(), pixels.as_ptr(), pixels.len()) }.guesses,
+            [0.0; 10]
+        );
+        assert_eq!(unsafe{ recognize_threshold(missing.as_ptr(), image.as_ptr(), 0.0) }, -1);
+        assert_eq!(unsafe{ recognize_threshold(missing.as_ptr(), image.as_ptr(), -1.0) }, -1);
+        assert!(unsafe{ network_load(missing.as_ptr()) }.is_null());
+        assert_eq!(unsafe{ network_recognize(ptr::null(), image.as_ptr()) }.guesses, [0.0; 10]);
+
+        //wrong amount of outputs
+        let layers = [DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}];
+        let path = env::temp_dir().join("digitsrecog_ffi_wrong_outputs.nn");
+        NeuralNet::create(IMAGE_SIZE, &layers).save(path.to_str().unwrap()).unwrap();
+
+        let network_path = CString::new(path.to_str().unwrap()).unwrap();
+        let guesses = unsafe{ recognize(network_path.as_ptr(), image.as_ptr()) }.guesses;
+
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(guesses, [0.0; 10]);
+    }
+}
@@ -1,38 +1,21 @@
-use std::{
-    slice,
-    ffi::{CStr, c_char}
-};
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::suspicious_else_formatting)]
 
-use neural_net::*;
+extern crate alloc;
 
-mod neural_net;
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("building without std needs the libm feature for the float math");
 
+#[cfg(feature = "std")]
+pub use ffi::*;
 
-#[repr(C)]
-pub struct Guesses
-{
-    guesses: [f64;10]
-}
+pub mod neural_net_core;
+mod fast_index;
 
-#[no_mangle]
-pub extern "C" fn recognize(network_path: *const c_char, image: *const u8) -> Guesses
-{
-    if network_path.is_null() || image.is_null()
-    {
-        Guesses{guesses: [0.0; 10]}
-    } else
-    {
-        let network_path = unsafe{ CStr::from_ptr(network_path) };
-        let network_path = network_path.to_str().unwrap();
+#[cfg(feature = "std")]
+pub mod neural_net;
+#[cfg(feature = "std")]
+pub mod digiter;
 
-        let image = unsafe{ slice::from_raw_parts(image, 28*28) };
-
-        let mut network = NeuralNet::load(network_path)
-            .map_err(|err| format!("{err} (filepath: {network_path})")).unwrap();
-
-        let guesses = network.feedforward(&image.iter().map(|v| *v as f64 / 255.0)
-            .collect::<Vec<f64>>());
-
-        Guesses{guesses: guesses.clone().try_into().unwrap()}
-    }
-}
\ No newline at end of file
+#[cfg(feature = "std")]
+mod ffi;
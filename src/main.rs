@@ -1,350 +1,1439 @@
-use std::{fmt, env, thread, process, str, num::NonZeroUsize};
+#![allow(clippy::suspicious_else_formatting)]
+
+extern crate alloc;
+
+use std::{
+    env,
+    io,
+    fs,
+    iter,
+    process,
+    time::Instant,
+    sync::{Arc, atomic::{AtomicBool, Ordering}}
+};
+
+use signal_hook::consts::SIGINT;
+use rayon::prelude::*;
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
 
 use digiter::*;
 use neural_net::*;
+use metrics::*;
+use config::*;
 
 mod digiter;
 mod neural_net;
+mod neural_net_core;
+mod fast_index;
+mod metrics;
+mod config;
+
+
+const TEST_SAMPLES: usize = 1000;
+
+//bins of every --weight-hist histogram
+const WEIGHT_HISTOGRAM_BINS: usize = 20;
+
+//networks with more parameters than this make --dump-weights unwieldy
+const DUMP_WEIGHTS_WARN_PARAMETERS: usize = 10_000;
+
+//fraction of floored learning rates after which training is likely stuck
+const STAGNATION_FRACTION: f64 = 0.9;
+
+//every label of every sample, for --multilabel
+type LabelSets = Vec<Vec<u8>>;
+
+//labels and raw pixels of a test image, theyre kept raw so every network can
+//preprocess them the way it was trained
+type TestImage = (Vec<u8>, Vec<u8>);
+
+//label smoothing moves smoothing of the target away from the correct classes
+fn train_sample(labels: &[u8], inputs: Vec<f64>, classes: usize, smoothing: f64) -> TrainSample
+{
+    let (correct, incorrect) = (1.0 - smoothing, smoothing / (classes - 1) as f64);
+
+    TrainSample::new(
+        inputs,
+        (0..classes).map(|i| if labels.contains(&(i as u8)) {correct} else {incorrect})
+            .collect::<Vec<f64>>()
+    )
+}
+
+//a label past the outputs would give the sample an all zero target
+fn labels_fit(labels: &[u8], classes: usize) -> bool
+{
+    labels.iter().all(|label| (*label as usize)<classes)
+}
 
+fn warn_skipped(skipped: usize, set: &str, classes: usize)
+{
+    if skipped>0
+    {
+        println!(
+            "warning: skipped {skipped} {set} samples with labels outside of the {classes} classes"
+        );
+    }
+}
 
-fn test_network(filename: &str, digit_reader: Digiter)
+//label sets (from --multilabel) replace the single labels of the dataset when given,
+//reading the dataset is sequential but turning the images into samples runs in parallel
+//a chunk at a time so the raw images dont all have to be in memory at once
+fn dataset_samples(
+    digit_reader: impl Iterator<Item=(u8, Vec<u8>)>,
+    label_sets: Option<LabelSets>,
+    classes: usize,
+    smoothing: f64,
+    preprocessing: Preprocessing
+) -> Vec<TrainSample>
 {
-    let mut network = NeuralNet::load(filename).unwrap();
+    const CHUNK_SIZE: usize = 4096;
 
-    let samples = 1000;
+    let mut digit_reader = digit_reader.enumerate();
 
-    let mut correct = 0;
-    let mut combined_error = 0.0;
-    for (index, (label, inputs)) in digit_reader.take(samples).enumerate()
+    let labels = |index: usize, label: &u8| -> Vec<u8>
     {
-        let inputs = inputs.into_iter().map(|b| b as f64 / 255.0).collect::<Vec<f64>>();
+        label_sets.as_ref().and_then(|label_sets| label_sets.get(index)).cloned()
+            .unwrap_or_else(|| vec![*label])
+    };
 
-        let out = network.feedforward(&inputs);
-        
-        if index==0
+    let mut skipped = 0;
+
+    let mut samples = Vec::new();
+    loop
+    {
+        let chunk = digit_reader.by_ref().take(CHUNK_SIZE).collect::<Vec<_>>();
+        if chunk.is_empty()
         {
-            println!("sample output: {out:?} (correct {label})");
+            warn_skipped(skipped, "training", classes);
+
+            return samples;
         }
 
-        let guess = out.iter().enumerate()
-            .reduce(|highest, current|
-            {
-                if current.1>highest.1 {current} else {highest}
-            }).unwrap().0;
+        let total = chunk.len();
+        let chunk = chunk.into_iter().filter(|(index, (label, _))|
+        {
+            labels_fit(&labels(*index, label), classes)
+        }).collect::<Vec<_>>();
+
+        skipped += total - chunk.len();
 
-        combined_error += out.into_iter().enumerate().map(|(index, prediction)|
+        samples.par_extend(chunk.into_par_iter().map(|(index, (label, image))|
         {
-            (if (index as u8)==label {1.0} else {0.0} - prediction).powi(2) * 0.5
-        }).sum::<f64>();
+            let inputs = preprocessing.apply(&image);
+
+            train_sample(&labels(index, &label), inputs, classes, smoothing)
+        }));
+    }
+}
+
+//the correct classes output minus the highest other output for every sample,
+//negative for wrong guesses so the confidently wrong ones are the hardest
+fn sample_margins(network: &NeuralNet, samples: &[TrainSample]) -> Vec<f64>
+{
+    samples.par_iter().map_init(|| network.scratch(), |scratch, sample|
+    {
+        let outputs = network.feedforward_pure(&sample.inputs, scratch);
+        let correct = argmax(&sample.outputs);
+
+        let runner_up = outputs.iter().enumerate().filter(|(class, _)| *class!=correct)
+            .fold(f64::NEG_INFINITY, |highest, (_, output)| highest.max(*output));
+
+        outputs[correct] - runner_up
+    }).collect()
+}
+
+//classes with at least one sample, a sample has the classes with its highest target
+//(several with multiple labels)
+fn present_classes(samples: &[TrainSample], classes: usize) -> usize
+{
+    let mut present = vec![false; classes];
+    for sample in samples
+    {
+        let highest = sample.outputs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
 
-        if label==guess as u8
+        //a sample without any labels has all targets at 0
+        if highest<=0.0
         {
-            correct += 1;
+            continue;
         }
+
+        sample.outputs.iter().zip(present.iter_mut())
+            .filter(|(target, _)| **target==highest)
+            .for_each(|(_, present)| *present = true);
     }
 
-    println!("combined error: {combined_error}, percent correct: {:.2}%",
-        (correct as f64 / samples as f64) * 100.0);
+    present.into_iter().filter(|present| *present).count()
 }
 
-fn xorshift(mut x: u32) -> u32
+fn test_images(
+    digit_reader: impl Dataset,
+    label_sets: Option<LabelSets>,
+    classes: usize
+) -> Vec<TestImage>
 {
-    x ^= x << 13;
-    x ^= x >> 17;
-    x ^ (x << 5)
+    let mut skipped = 0;
+
+    let images = digit_reader.take(TEST_SAMPLES).enumerate().map(|(index, (label, image))|
+    {
+        let labels = label_sets.as_ref().and_then(|label_sets| label_sets.get(index)).cloned()
+            .unwrap_or_else(|| vec![label]);
+
+        (labels, image)
+    }).filter(|(labels, _)|
+    {
+        let fits = labels_fit(labels, classes);
+        if !fits
+        {
+            skipped += 1;
+        }
+
+        fits
+    }).collect();
+
+    warn_skipped(skipped, "test", classes);
+
+    images
 }
 
-enum ProgramMode
+//moves a random fraction of the samples (at least one, leaving at least one) out of them
+fn hold_out(samples: &mut Vec<TrainSample>, fraction: f64, seed: u64) -> Vec<TrainSample>
 {
-    Train,
-    Restart
+    let amount = ((samples.len() as f64 * fraction).round() as usize).clamp(1, samples.len() - 1);
+
+    samples.shuffle(&mut StdRng::seed_from_u64(seed));
+    samples.split_off(samples.len() - amount)
 }
 
-fn train(filename: &str, digit_reader: Digiter, config: &Config)
+//the test images as inputs for a network with that preprocessing
+fn test_samples(
+    images: &[TestImage],
+    classes: usize,
+    preprocessing: Preprocessing
+) -> Vec<TrainSample>
 {
-    let image_size = (digit_reader.width() * digit_reader.height()) as usize;
+    images.par_iter().map(|(labels, image)|
+    {
+        train_sample(labels, preprocessing.apply(image), classes, 0.0)
+    }).collect()
+}
 
-    let layers = [
-        DefaultLayerSettings{size: 50, transfer_function: TransferFunction::Tanh},
-        DefaultLayerSettings{size: 50, transfer_function: TransferFunction::Tanh},
-        DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}
-        ];
+struct TestResults
+{
+    metrics: Metrics,
+    calibration: Calibration,
+    //mean over the samples
+    brier_score: f64,
+    //best guess for every sample in order
+    guesses: Vec<usize>
+}
 
-    let mut network = match config.mode
+fn evaluate_network(
+    network: &mut NeuralNet,
+    samples: &[TrainSample],
+    config: &Config
+) -> TestResults
+{
+    let mut metrics = Metrics::new(config.loss);
+    let mut calibration = Calibration::new();
+    let mut brier_sum = 0.0;
+    let mut guesses = Vec::with_capacity(samples.len());
+    for sample in samples
     {
-        ProgramMode::Restart => NeuralNet::create(image_size, &layers),
-        ProgramMode::Train => NeuralNet::load(filename).unwrap()
-    };
+        let (logits, out) = network.probe(&sample.inputs);
 
-    let iterations_progress = config.iterations/100;
-    let mut progress = 1;
-    while iterations_progress>progress
+        metrics.add(&out, &sample.outputs);
+        calibration.add(&out, &sample.outputs);
+        guesses.push(argmax(&out));
+
+        //multiple labels can be right at once so the outputs already are independent probabilities
+        let probabilities = if config.multilabel {out} else {softmax(&logits)};
+        brier_sum += brier_score(&probabilities, &sample.outputs);
+    }
+
+    let brier_score = brier_sum / samples.len().max(1) as f64;
+
+    TestResults{metrics, calibration, brier_score, guesses}
+}
+
+//every saved network gets read through here so --load-unchecksummed applies to all of them
+fn load_network(filename: &str, config: &Config) -> Result<NeuralNet, LoadError>
+{
+    if config.load_unchecksummed
     {
-        progress <<= 1;
+        NeuralNet::load_unchecksummed(filename)
+    } else
+    {
+        NeuralNet::load(filename)
     }
-    let progress_mask = progress-1;
-    let progress = progress as f64;
+}
+
+//size is the width and height of the test images
+fn test_network(filename: &str, images: &[TestImage], size: (u32, u32), config: &Config)
+{
+    let mut network = load_network(filename, config).unwrap();
+
+    let samples = &test_samples(images, config.classes_amount(), network.preprocessing());
+
+    if let Some(sample) = samples.first()
+    {
+        let out = network.feedforward(&sample.inputs);
+        println!(
+            "sample output: {out:?} (guessed {}, correct {})",
+            config.label_name(argmax(&out)),
+            config.label_name(argmax(&sample.outputs))
+        );
+    }
+
+    let TestResults{metrics, calibration, brier_score, ..} =
+        evaluate_network(&mut network, samples, config);
+
+    println!(
+        "combined error: {}, percent correct: {}, brier score: {}",
+        format_number(metrics.combined_error, config.precision),
+        format_percent(metrics.accuracy(), config.precision),
+        format_number(brier_score, config.precision)
+    );
+
+    if config.multilabel
+    {
+        println!(
+            "exact label set matches: {}",
+            format_percent(metrics.exact_match_rate(), config.precision)
+        );
+    }
+
+    if config.calibration
+    {
+        calibration.print(config.precision);
+    }
+
+    if config.embedding_analysis
+    {
+        embedding_analysis(&mut network, samples, config);
+    }
+
+    if config.robustness
+    {
+        robustness(&mut network, samples, size, config);
+    }
+
+    for (index, sample) in samples.iter().take(config.show_samples).enumerate()
+    {
+        show_sample(&mut network, index, sample, size.0 as usize, config);
+    }
+}
+
+//accuracy of both networks overall and per class, and the samples they disagree on
+fn compare_networks(
+    filename: &str,
+    other_filename: &str,
+    images: &[TestImage],
+    config: &Config
+) -> Result<(), String>
+{
+    let load = |filename: &str|
+    {
+        load_network(filename, config).map_err(|err| format!("cant load {filename}: {err}"))
+    };
+
+    let mut networks = [load(filename)?, load(other_filename)?];
+
+    let inputs = images.first().map(|(_, image)| image.len()).unwrap_or_default();
+    let outputs = config.classes_amount();
+
+    for (network, filename) in networks.iter().zip([filename, other_filename])
+    {
+        if network.inputs_amount()!=inputs
+        {
+            return Err(format!(
+                "{filename} takes {} inputs but the test images have {inputs} pixels",
+                network.inputs_amount()
+            ));
+        }
 
-    let digit_reader = digit_reader.into_iter()
-        .map(|(label, img)|
+        let network_outputs = network.layer_size(network.layers_amount() - 1);
+        if network_outputs!=outputs
         {
-            TrainSample
-            {
-                inputs: img.iter().map(|b| *b as f64 / 255.0).collect::<Vec<f64>>(),
-                outputs: (0..10).map(|i| if i==label {1.0} else {0.0}).collect::<Vec<f64>>()
-            }
-        }).collect::<Vec<TrainSample>>();
+            return Err(format!(
+                "{filename} has {network_outputs} outputs but the test set has {outputs} classes"
+            ));
+        }
+    }
 
-    let seed = rand::random::<u32>();
-    let mut progress_counter = 1.0;
+    //every network sees the images preprocessed the way it was trained
+    let [(samples, first_guesses), (_, second_guesses)] = networks.each_mut().map(|network|
+    {
+        let samples = test_samples(images, outputs, network.preprocessing());
+        let guesses = evaluate_network(network, &samples, config).guesses;
+
+        (samples, guesses)
+    });
+
+    let mut class_totals = vec![0_usize; outputs];
+    let mut class_correct = vec![[0_usize; 2]; outputs];
 
-    let random = xorshift(seed);
-    let batch_begin = random as usize;
-    for i in 0..config.iterations
+    //samples the networks guess differently, by which one (if any) was right
+    let (mut first_right, mut second_right, mut neither) = (0, 0, 0);
+
+    for (sample, guesses) in samples.iter().zip(first_guesses.into_iter().zip(second_guesses))
     {
-        let batch = (0..config.batch_size).map(|b|
+        let correct = argmax(&sample.outputs);
+        let guesses = [guesses.0, guesses.1];
+
+        class_totals[correct] += 1;
+        for (index, guess) in guesses.iter().enumerate()
         {
-            unsafe
+            if *guess==correct
             {
-            digit_reader.get_unchecked((i+b+batch_begin)%digit_reader.len()).clone()
+                class_correct[correct][index] += 1;
             }
-        }).collect::<Vec<TrainSample>>();
-        network.backpropagate_multithreaded(&batch, config.threads);
+        }
 
-        if (i & progress_mask)==0
+        if guesses[0]!=guesses[1]
         {
-            let percent = progress_counter / (config.iterations as f64 / progress);
-
-            print!("[");
-            let length = 30;
-            for i in 0..length
+            match (guesses[0]==correct, guesses[1]==correct)
             {
-                let part = i as f64 / length as f64;
-                if part < percent
-                {
-                    print!("🌸");
-                } else
-                {
-                    print!("__");
-                }
+                (true, _) => first_right += 1,
+                (_, true) => second_right += 1,
+                _ => neither += 1
             }
+        }
+    }
 
-            println!("] {:.2}%", percent * 100.0);
+    let percents = |correct: [usize; 2], total: usize|
+    {
+        let accuracy = |correct: usize| correct as f64 / total.max(1) as f64;
+        let (first, second) = (accuracy(correct[0]), accuracy(correct[1]));
 
-            progress_counter += 1.0;
+        format!(
+            "{} vs {} ({:+.*}%)",
+            format_percent(first, config.precision),
+            format_percent(second, config.precision),
+            config.precision,
+            (second - first) * 100.0
+        )
+    };
+
+    let overall = class_correct.iter().fold([0, 0], |total, correct|
+    {
+        [total[0] + correct[0], total[1] + correct[1]]
+    });
+
+    println!("{filename} vs {other_filename} on {} test samples", samples.len());
+    println!("percent correct: {}", percents(overall, samples.len()));
+
+    println!("per class percent correct:");
+    for (class, (correct, total)) in class_correct.iter().zip(class_totals.iter()).enumerate()
+    {
+        if *total==0
+        {
+            continue;
         }
+
+        println!("    {}: {}", config.label_name(class), percents(*correct, *total));
     }
 
-    network.save(filename).unwrap();
+    println!(
+        "disagreements: {} ({filename} right {first_right}, {other_filename} right {second_right}, \
+        neither right {neither})",
+        first_right + second_right + neither
+    );
+
+    Ok(())
 }
 
-enum ConfigError
+//the image as text with the correct label and the best guesses next to it
+fn show_sample(
+    network: &mut NeuralNet,
+    index: usize,
+    sample: &TrainSample,
+    width: usize,
+    config: &Config
+)
 {
-    InvalidArg(String),
-    MissingValue,
-    MissingRequired(String),
-    InvalidValue(String)
+    const GUESSES: usize = 3;
+
+    let outputs = network.feedforward(&sample.inputs);
+
+    let mut ranked = outputs.iter().copied().enumerate().collect::<Vec<(usize, f64)>>();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let correct = argmax(&sample.outputs);
+    let mark = if ranked[0].0==correct {"right"} else {"wrong"};
+
+    let info = iter::once(format!("correct: {} ({mark})", config.label_name(correct)))
+        .chain(ranked.iter().take(GUESSES).enumerate().map(|(rank, (label, output))|
+        {
+            format!(
+                "{}. {} ({})",
+                rank + 1,
+                config.label_name(*label),
+                format_number(*output, config.precision)
+            )
+        }));
+
+    println!("sample {index}:");
+
+    //how much every pixel sways the guess, drawn next to the image
+    let saliency = config.saliency.then(||
+    {
+        let gradient = network.input_gradient(&sample.inputs, ranked[0].0);
+        let magnitudes = gradient.iter().map(|value| value.abs()).collect::<Vec<f64>>();
+
+        ascii_image(&magnitudes, width)
+    });
+
+    let image = ascii_image(&sample.inputs, width);
+    let info = info.chain(iter::repeat(String::new()));
+    for (index, (row, info)) in image.iter().zip(info).enumerate()
+    {
+        let saliency = saliency.as_ref().map(|saliency| format!("|{}|  ", saliency[index]))
+            .unwrap_or_default();
+
+        println!("{}", format!("    |{row}|  {saliency}{info}").trim_end());
+    }
 }
 
-impl fmt::Display for ConfigError
+//accuracy on transformed copies of the samples, only for evaluation
+fn robustness(network: &mut NeuralNet, samples: &[TrainSample], size: (u32, u32), config: &Config)
 {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    const AUGMENTATIONS: [Augmentation; 7] = [
+        Augmentation::Rotate(-10.0),
+        Augmentation::Rotate(10.0),
+        Augmentation::Rotate(20.0),
+        Augmentation::Shift(2, 0),
+        Augmentation::Shift(0, 2),
+        Augmentation::Noise(0.1),
+        Augmentation::Noise(0.3)
+    ];
+
+    //same noise every run so reports of different networks compare
+    let mut rng = StdRng::seed_from_u64(0);
+
+    println!("robustness (percent correct on transformed test samples):");
+    for augmentation in AUGMENTATIONS
     {
-        let mut val = None;
-        
-        let stringified = match self
+        let mut metrics = Metrics::new(config.loss);
+        for sample in samples
         {
-            ConfigError::InvalidArg(x) =>
-            {
-                val = Some(x);
-                "invalid argument"
-            },
-            ConfigError::MissingValue => "argument value missing",
-            ConfigError::MissingRequired(x) =>
-            {
-                val = Some(x);
-                "missing required argument"
-            },
-            ConfigError::InvalidValue(x) =>
-            {
-                val = Some(x);
-                "invalid value"
-            }
-        };
+            let inputs = augmentation.apply(&sample.inputs, size, &mut rng);
 
-        let optional = if let Some(val) = val {format!(" ({val})")} else {"".to_owned()};
-        write!(f, "{}{}", stringified, &optional)
+            metrics.add(&network.feedforward(&inputs), &sample.outputs);
+        }
+
+        println!(
+            "    {augmentation}: {}",
+            format_percent(metrics.accuracy(), config.precision)
+        );
     }
 }
 
-struct Config
+//penultimate layer activations (the inputs for a single layer network) and the guess
+fn embedding(network: &mut NeuralNet, inputs: &[f64]) -> (Vec<f64>, usize)
 {
-    mode: ProgramMode,
-    filename: String,
-    threads: usize,
-    iterations: usize,
-    batch_size: usize,
-    train_images: String,
-    train_labels: String,
-    test_images: String,
-    test_labels: String
+    let mut activations = network.activations(inputs);
+
+    let guess = argmax(&activations.pop().unwrap());
+    let embedding = activations.pop().unwrap_or_else(|| inputs.to_vec());
+
+    (embedding, guess)
 }
 
-impl Config
+fn embedding_analysis(network: &mut NeuralNet, samples: &[TrainSample], config: &Config)
 {
-    pub fn create(mut args: impl Iterator<Item=String>) -> Result<Self, ConfigError>
-    {
-        let mut mode = ProgramMode::Restart;
-        let mut filename = "network.nn".to_owned();
-
-        let mut threads = None;
+    let embeddings = samples.iter().map(|sample| embedding(network, &sample.inputs))
+        .collect::<Vec<_>>();
 
-        let mut iterations = 10;
-        let mut batch_size = 10000;
+    let classes = samples.first().map(|sample| sample.outputs.len()).unwrap_or(0);
+    let embedding_size = embeddings.first().map(|(embedding, _)| embedding.len()).unwrap_or(0);
 
-        let mut train_labels = None;
-        let mut train_images = None;
-        
-        let mut test_labels = None;
-        let mut test_images = None;
+    let mut analysis = EmbeddingAnalysis::new(classes, embedding_size);
+    samples.iter().zip(embeddings.iter()).for_each(|(sample, (embedding, _))|
+    {
+        analysis.add(argmax(&sample.outputs), embedding);
+    });
 
+    println!("embedding analysis (cosine similarity to the class means):");
 
-        while let Some(arg) = args.next()
+    let (mut errors, mut guessed_total, mut correct_total, mut closer_correct) = (0, 0.0, 0.0, 0);
+    for (index, (sample, (embedding, guess))) in samples.iter().zip(embeddings.iter()).enumerate()
+    {
+        let correct = argmax(&sample.outputs);
+        if *guess==correct
         {
-            match arg.as_str()
-            {
-                "-M" | "--mode" =>
-                {
-                    mode = match args.next().ok_or(ConfigError::MissingValue)?.as_str()
-                    {
-                        "restart" => ProgramMode::Restart,
-                        "train" => ProgramMode::Train,
-                        x => return Err(ConfigError::InvalidValue(x.to_owned()))
-                    };
-                },
-                "-o" | "--output" =>
-                {
-                    filename = args.next().ok_or(ConfigError::MissingValue)?;
-                },
-                "--threads" =>
-                {
-                    threads = Some(Self::number_arg::<usize>(&mut args)?);
-                },
-                "-I" | "--iter" =>
-                {
-                    iterations = Self::number_arg(&mut args)?;
-                },
-                "-b" | "--batch" =>
-                {
-                    batch_size = Self::number_arg(&mut args)?;
-                },
-                "-i" | "--images" =>
-                {
-                    train_images = Some(args.next().ok_or(ConfigError::MissingValue)?);
-                },
-                "-l" | "--labels" =>
-                {
-                    train_labels = Some(args.next().ok_or(ConfigError::MissingValue)?);
-                },
-                "-t" | "--test-images" =>
-                {
-                    test_images = Some(args.next().ok_or(ConfigError::MissingValue)?);
-                },
-                "-T" | "--test-labels" =>
-                {
-                    test_labels = Some(args.next().ok_or(ConfigError::MissingValue)?);
-                },
-                "-h" | "--help" =>
-                {
-                    Self::help_message();
-                },
-                x => return Err(ConfigError::InvalidArg(x.to_owned()))
-            }
+            continue;
         }
 
-        let train_labels: String =
-            train_labels.ok_or(ConfigError::MissingRequired("--labels".to_owned()))?;
+        let (guessed_similarity, correct_similarity) =
+            analysis.similarities(embedding, *guess, correct);
 
-        let train_images: String =
-            train_images.ok_or(ConfigError::MissingRequired("--images".to_owned()))?;
+        println!(
+            "    sample {index}: guessed {} ({}), correct {} ({})",
+            config.label_name(*guess),
+            format_number(guessed_similarity, config.precision),
+            config.label_name(correct),
+            format_number(correct_similarity, config.precision)
+        );
 
-        let test_images: String = if test_images.is_none()
-        {
-            train_images.clone()
-        } else
-        {
-            test_images.unwrap()
-        };
+        errors += 1;
+        guessed_total += guessed_similarity;
+        correct_total += correct_similarity;
 
-        let test_labels: String = if test_labels.is_none()
-        {
-            train_labels.clone()
-        } else
+        if correct_similarity>=guessed_similarity
         {
-            test_labels.unwrap()
-        };
+            closer_correct += 1;
+        }
+    }
 
-        let threads = threads.unwrap_or_else(||
-        {
-            thread::available_parallelism().unwrap_or_else(|_| NonZeroUsize::new(1).unwrap()).get()
-        });
+    if errors>0
+    {
+        println!(
+            "    {errors} errors, mean similarity to the guessed class {}, to the correct class {}",
+            format_number(guessed_total / errors as f64, config.precision),
+            format_number(correct_total / errors as f64, config.precision)
+        );
 
-        Ok(Config{
-            mode, filename,
-            threads,
-            iterations, batch_size,
-            train_images, train_labels,
-            test_images, test_labels
-        })
+        println!("    {closer_correct} of them are at least as close to the correct class");
+    } else
+    {
+        println!("    no misclassified samples");
     }
+}
 
-    fn number_arg<T>(mut args: impl Iterator<Item=String>) -> Result<T, ConfigError>
-    where
-        T: str::FromStr,
-        <T as str::FromStr>::Err: fmt::Display
+//without test samples the copy is only saved, not compared
+fn quantize_network(
+    filename: &str,
+    quantized_filename: &str,
+    images: Option<&[TestImage]>,
+    config: &Config
+)
+{
+    let mut network = load_network(filename, config).unwrap();
+    let quantized = match network.quantize()
     {
-        args.next().ok_or(ConfigError::MissingValue)?.trim().parse()
-            .map_err(|err| ConfigError::InvalidValue(format!("{err}")))
-    }
+        Some(quantized) => quantized,
+        None =>
+        {
+            println!("only networks of dense layers can be quantized");
+            process::exit(1)
+        }
+    };
+
+    quantized.save(quantized_filename).unwrap();
 
-    pub fn help_message() -> !
+    let samples = match images
     {
-        println!("usage: {} [args]", env::args().next().unwrap());
-        println!("args:");
-        println!("    -h, --help         display this help messsage");
-        println!("    -M, --mode         program mode (default restart)");
-        println!("    -o, --output       output filename (default network.nn)");
-        println!("    --threads          override the amount of threads used");
-        println!("    -I, --iter         iterations to train for (default 10)");
-        println!("    -b, --batch        batch size (default 10000)");
-        println!("    -i, --images       mnist training images");
-        println!("    -l, --labels       mnist training labels");
-        println!("    -t, --test-images  optional test images (uses training otherwise)");
-        println!("    -T, --test-labels  optional test labels (uses training otherwise)");
-        println!("program modes:");
-        println!("    restart, train");
+        Some(images) => &test_samples(images, config.classes_amount(), network.preprocessing()),
+        None => return
+    };
 
-        process::exit(1)
+    let metrics = Metrics::evaluate(&mut network, samples);
+
+    let mut quantized_metrics = Metrics::default();
+    for sample in samples
+    {
+        quantized_metrics.add(&quantized.feedforward(&sample.inputs), &sample.outputs);
     }
+
+    let size = |filename| fs::metadata(filename).map(|metadata| metadata.len()).unwrap_or(0);
+
+    println!(
+        "quantized percent correct: {} ({:+.*}%), size: {} bytes (from {} bytes)",
+        format_percent(quantized_metrics.accuracy(), config.precision),
+        config.precision,
+        (quantized_metrics.accuracy() - metrics.accuracy()) * 100.0,
+        size(quantized_filename),
+        size(filename)
+    );
 }
 
-fn main()
+fn prune_network(
+    filename: &str,
+    pruned_filename: &str,
+    images: Option<&[TestImage]>,
+    config: &Config
+)
 {
-    let config = Config::create(env::args().skip(1)).unwrap_or_else(|err|
+    let mut network = load_network(filename, config).unwrap();
+    network.set_loss(config.loss);
+
+    let samples = images.map(|images|
     {
-        println!("parse error: {err}");
-        Config::help_message()
+        test_samples(images, config.classes_amount(), network.preprocessing())
     });
+    let samples = samples.as_deref();
+
+    let metrics = samples.map(|samples| Metrics::evaluate(&mut network, samples));
+
+    let sparsity = match config.pruning
+    {
+        Pruning::Threshold(threshold) => network.prune(threshold),
+        Pruning::Fraction(fraction) => network.prune_fraction(fraction)
+    };
+
+    network.save(pruned_filename).unwrap();
+
+    let (samples, metrics) = match (samples, metrics)
+    {
+        (Some(samples), Some(metrics)) => (samples, metrics),
+        _ =>
+        {
+            println!("{} of weights are zero", format_percent(sparsity, config.precision));
+            return;
+        }
+    };
+
+    let pruned_metrics = Metrics::evaluate(&mut network, samples);
+
+    println!(
+        "pruned percent correct: {} ({:+.*}%), {} of weights are zero",
+        format_percent(pruned_metrics.accuracy(), config.precision),
+        config.precision,
+        (pruned_metrics.accuracy() - metrics.accuracy()) * 100.0,
+        format_percent(sparsity, config.precision)
+    );
+}
+
+fn print_progress(percent: f64, config: &Config)
+{
+    let (filled, empty) = match config.progress_style
+    {
+        ProgressStyle::Emoji => ("🌸", "__"),
+        ProgressStyle::Ascii => ("#", "-")
+    };
+
+    let length = config.progress_width;
+    let bar = (0..length).map(|i|
+    {
+        let part = i as f64 / length as f64;
+        if part < percent {filled} else {empty}
+    }).collect::<String>();
+
+    println!("[{bar}] {}", format_percent(percent, config.precision));
+}
+
+//the image shape of the dataset, what conv and pooling layers go over
+fn input_shape(dataset: &impl Dataset) -> Shape
+{
+    Shape::new(dataset.width() as usize, dataset.height() as usize, dataset.channels() as usize)
+}
+
+fn prepare_network(filename: &str, input: Shape, config: &Config) -> NeuralNet
+{
+    let mut network = match config.mode
+    {
+        ProgramMode::Restart =>
+        {
+            let mut network = match NeuralNet::create_shaped(input, &config.layers)
+            {
+                Ok(network) => network,
+                Err(err) =>
+                {
+                    println!("invalid --arch for {input} images: {err}");
+                    process::exit(1)
+                }
+            };
+
+            if config.zero_head
+            {
+                network.zero_head();
+            }
+
+            network
+        },
+        ProgramMode::Train => load_network(filename, config).unwrap()
+    };
+
+    if let Err(err) = config.check_layers(network.layers_amount())
+    {
+        println!("{err}");
+        process::exit(1)
+    }
+
+    if config.reset_learning_rates
+    {
+        network.reset_adaptive();
+    }
 
-    let train_digiter = Digiter::create(
-        &config.train_labels,
-        &config.train_images
-    ).unwrap();
-    train(&config.filename, train_digiter, &config);
-
-    let test_digiter = Digiter::create(
-        &config.test_labels,
-        &config.test_images
-    ).unwrap();
-    test_network(&config.filename, test_digiter);
+    //a loaded network keeps the learning rates it adapted unless theyre reset
+    if matches!(config.mode, ProgramMode::Restart) || config.reset_learning_rates
+    {
+        for (layer, rate) in config.layer_learning_rates.iter().enumerate()
+        {
+            if let Some(rate) = rate
+            {
+                network.set_learning_rates(layer, *rate);
+            }
+        }
+    }
+
+    network.set_optimizer(config.optimizer);
+    network.set_grad_reduce(config.grad_reduce);
+    network.set_loss(config.loss);
+    if let Some(preprocessing) = config.preprocessing
+    {
+        network.set_preprocessing(preprocessing);
+    }
+
+    if config.activation_stats
+    {
+        network.track_activations();
+    }
+
+    config.frozen_layers.iter().for_each(|layer| network.set_frozen(*layer, true));
+    config.unfrozen_layers.iter().for_each(|layer| network.set_frozen(*layer, false));
+
+    network
+}
+
+fn train(
+    filename: &str,
+    digit_reader: impl Dataset,
+    label_sets: Option<LabelSets>,
+    interrupted: &AtomicBool,
+    config: &Config
+)
+{
+    let mut network = prepare_network(filename, input_shape(&digit_reader), config);
+
+    if network.training_info().iterations>0 && !config.quiet
+    {
+        println!("resuming a network {}", network.training_info());
+    }
+
+    let mut digit_reader = dataset_samples(
+        digit_reader,
+        label_sets,
+        config.classes_amount(),
+        config.label_smoothing,
+        network.preprocessing()
+    );
+
+    if digit_reader.is_empty()
+    {
+        println!("the training set is empty");
+        process::exit(1)
+    }
+
+    let seed = rand::random::<u64>();
+
+    //the target accuracy is checked on samples the network never trains on
+    let validation = config.target_accuracy.map(|_|
+    {
+        if digit_reader.len()<2
+        {
+            println!("the training set is too small to hold out samples for --target-accuracy");
+            process::exit(1)
+        }
+
+        let validation = hold_out(&mut digit_reader, config.validation_split, seed);
+
+        if !config.quiet
+        {
+            println!(
+                "holding out {} training samples to check the target accuracy",
+                validation.len()
+            );
+        }
+
+        validation
+    });
+
+    //a single class trains the network to always guess it and tests misleadingly well
+    let classes = present_classes(&digit_reader, config.classes_amount());
+    if classes<2
+    {
+        println!(
+            "warning: the training set only has samples of {classes} class, \
+            the network will learn to always guess it"
+        );
+    }
+
+    //samples used by a single weight update
+    let update_size = config.batch_size * config.accumulate;
+    let epoch_iterations = digit_reader.len().div_ceil(update_size);
+
+    let iterations = config.epochs.map(|epochs| epochs * epoch_iterations)
+        .unwrap_or(config.iterations);
+
+    let iterations_progress = iterations/100;
+    let mut progress = 1;
+    while iterations_progress>progress
+    {
+        progress <<= 1;
+    }
+    let progress_mask = progress-1;
+    let progress = progress as f64;
+
+    let mut progress_counter = 1.0;
+
+    let mut sampler = EpochSampler::new(digit_reader.len(), seed);
+    let mut hard_miner = config.hard_mining.map(|fraction| HardMiner::new(fraction, seed));
+
+    let mut log = config.log.as_ref().map(|path|
+    {
+        MetricsLog::create(path, config.append_log, seed, config.precision).unwrap()
+    });
+    let mut last_batch = Vec::new();
+    let mut trained_iterations = 0;
+
+    let start = Instant::now();
+    for i in 0..iterations
+    {
+        if let Some(epochs) = config.epochs.filter(|_| !config.quiet)
+        {
+            if i % epoch_iterations == 0
+            {
+                println!("epoch {}/{epochs}", i / epoch_iterations + 1);
+            }
+        }
+
+        //the first epoch is a warm up before the margins mean anything
+        if let Some(miner) = hard_miner.as_mut().filter(|_| i>0 && i % epoch_iterations == 0)
+        {
+            miner.update(&sample_margins(&network, &digit_reader));
+        }
+
+        for _ in 0..config.accumulate
+        {
+            let hard = hard_miner.as_ref().map(|miner| miner.batch_share(config.batch_size))
+                .unwrap_or(0);
+
+            //the last batch of an epoch continues into the next shuffled epoch
+            let batch = sampler.by_ref().take(config.batch_size - hard)
+                .chain(hard_miner.iter_mut().flat_map(|miner| miner.take(hard)))
+                .map(|index| &digit_reader[index])
+                .collect::<Vec<&TrainSample>>();
+            network.backpropagate_multithreaded_nonapply(&batch, config.threads);
+
+            last_batch = batch;
+        }
+
+        if let Some(warm_restarts) = config.warm_restarts
+        {
+            let (scale, restarted) = warm_restarts.scale(i);
+
+            if restarted && !config.quiet
+            {
+                println!("warm restart at iteration {}", i + 1);
+            }
+
+            network.set_optimizer(config.optimizer.scaled(scale));
+        }
+
+        network.apply_gradients();
+        trained_iterations += 1;
+
+        if interrupted.load(Ordering::Relaxed)
+        {
+            println!("interrupted after {trained_iterations} iterations, saving the network");
+            break;
+        }
+
+        //checked every iteration since checkpoints can be far apart with big batches
+        if config.max_time.is_some_and(|max_time| start.elapsed()>=max_time)
+        {
+            println!("time limit reached after {} iterations", i + 1);
+            break;
+        }
+
+        //the log follows the progress steps when nothing is reported
+        let report = match config.report_every
+        {
+            Some(report_every) => (i + 1) % report_every == 0,
+            None => log.is_some() && (i & progress_mask)==0
+        };
+
+        if report
+        {
+            let metrics = Metrics::evaluate(&mut network, last_batch.iter().copied());
+
+            if config.report_every.is_some() && !config.quiet
+            {
+                println!(
+                    "iteration {}: batch loss {}, batch accuracy {}",
+                    i + 1,
+                    format_number(metrics.combined_error / metrics.total as f64, config.precision),
+                    format_percent(metrics.accuracy(), config.precision)
+                );
+            }
+
+            if let Some(log) = log.as_mut()
+            {
+                log.write(i + 1, &metrics).unwrap();
+            }
+        }
+
+        if (i & progress_mask)==0
+        {
+            let percent = progress_counter / (iterations as f64 / progress);
+
+            if !config.quiet
+            {
+                print_progress(percent, config);
+            }
+
+            progress_counter += 1.0;
+
+            if config.stagnation_warn
+            {
+                let floored = network.floored_fraction();
+                println!(
+                    "{} of learning rates at the minimum",
+                    format_percent(floored, config.precision)
+                );
+
+                if floored>STAGNATION_FRACTION
+                {
+                    println!(
+                        "warning: training has likely stalled, try a bigger batch, \
+                        --reset-lr or a different --optimizer"
+                    );
+                }
+            }
+        }
+
+        if let (Some(target), Some(validation)) = (config.target_accuracy, &validation)
+        {
+            if (i + 1) % config.validation_every == 0
+            {
+                let accuracy = Metrics::evaluate(&mut network, validation).accuracy();
+                if accuracy * 100.0>=target
+                {
+                    println!(
+                        "reached target accuracy ({} on held out samples) after {} iterations",
+                        format_percent(accuracy, config.precision),
+                        i + 1
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Some(stats) = network.activation_stats()
+    {
+        //the output layer isnt hidden
+        for layer in 0..stats.layers_amount()-1
+        {
+            println!(
+                "layer {layer} ({:?}): {} dead neurons, {} inactive activations",
+                stats.transfer_function(layer),
+                format_percent(stats.dead_fraction(layer), config.precision),
+                format_percent(stats.inactive_fraction(layer), config.precision)
+            );
+        }
+    }
+
+    network.record_training(trained_iterations, config.batch_size);
+    network.save(filename).unwrap();
+}
+
+fn open_source(config: &Config, labels: &str, images: &str) -> io::Result<Box<dyn Dataset>>
+{
+    Ok(match config.dataset
+    {
+        DatasetKind::Idx => Box::new(Digiter::create(labels, images)?),
+        DatasetKind::Csv => Box::new(CsvDigiter::create(images, config.csv_dimensions)?),
+        DatasetKind::Combined =>
+        {
+            //checked to be set when parsing the config
+            let dimensions = config.combined_dimensions.unwrap();
+            Box::new(CombinedDigiter::create(images, dimensions)?)
+        }
+    })
+}
+
+//chains the sources if theres more than one, sources of different sizes only mix
+//when --input-size brings them all to the same size
+fn chained(config: &Config, sources: Vec<Box<dyn Dataset>>) -> io::Result<Box<dyn Dataset>>
+{
+    if sources.len()==1
+    {
+        return Ok(sources.into_iter().next().unwrap());
+    }
+
+    let sources = sources.into_iter().map(|source| resized(config, source)).collect();
+
+    Ok(Box::new(ChainedDigiter::new(sources)?))
+}
+
+fn open_dataset(
+    config: &Config,
+    labels: &[String],
+    images: &[String]
+) -> io::Result<Box<dyn Dataset>>
+{
+    let sources = images.iter().enumerate().map(|(index, images)|
+    {
+        let labels = labels.get(index).map(|labels| labels.as_str()).unwrap_or_default();
+
+        open_source(config, labels, images)
+    }).collect::<io::Result<Vec<_>>>()?;
+
+    chained(config, sources)
+}
+
+//with --multilabel also returns the label set of every sample
+fn open_labeled(
+    config: &Config,
+    labels: &[String],
+    images: &[String]
+) -> io::Result<(Box<dyn Dataset>, Option<LabelSets>)>
+{
+    if config.multilabel
+    {
+        let mut label_sets = Vec::new();
+
+        let sources = images.iter().map(|images|
+        {
+            let digiter = CsvDigiter::create(images, config.csv_dimensions)?;
+            label_sets.extend_from_slice(digiter.label_sets());
+
+            Ok(Box::new(digiter) as Box<dyn Dataset>)
+        }).collect::<io::Result<Vec<_>>>()?;
+
+        return Ok((chained(config, sources)?, Some(label_sets)));
+    }
+
+    open_dataset(config, labels, images).map(|dataset| (dataset, None))
+}
+
+//trains a tiny network on synthetic images, then checks that it learned and that
+//saving and loading it keeps the outputs the same
+fn selftest(threads: usize) -> Result<(), String>
+{
+    const SIZE: u32 = 6;
+    const CLASSES: usize = 3;
+
+    let dataset = generate_dataset(1, CLASSES * 50, CLASSES, SIZE, SIZE);
+    let samples = dataset_samples(dataset, None, CLASSES, 0.0, Preprocessing::default());
+
+    let layers = [
+        DefaultLayerSettings{size: 8, transfer_function: TransferFunction::Tanh},
+        DefaultLayerSettings{size: CLASSES, transfer_function: TransferFunction::Sigmoid}
+    ];
+    let mut network = NeuralNet::create_seeded((SIZE * SIZE) as usize, &layers, 1);
+
+    let before = Metrics::evaluate(&mut network, &samples).combined_error;
+    for _ in 0..20
+    {
+        network.backpropagate_multithreaded(&samples, threads);
+    }
+    let after = Metrics::evaluate(&mut network, &samples).combined_error;
+
+    if after>=before
+    {
+        return Err(format!("the loss didnt decrease ({before} to {after})"));
+    }
+
+    let path = env::temp_dir().join(format!("digitsrecog_selftest_{}.nn", process::id()));
+    let path = path.to_str().ok_or("the temporary directory isnt valid utf-8")?;
+
+    network.save(path).map_err(|err| format!("cant save to {path}: {err}"))?;
+    let loaded = NeuralNet::load(path);
+    fs::remove_file(path).map_err(|err| format!("cant remove {path}: {err}"))?;
+
+    let mut loaded = loaded.map_err(|err| format!("cant load {path}: {err}"))?;
+
+    let same = samples.iter().all(|sample|
+    {
+        loaded.feedforward(&sample.inputs)==network.feedforward(&sample.inputs)
+    });
+
+    if !same
+    {
+        return Err("the reloaded network gives different outputs".to_owned());
+    }
+
+    Ok(())
+}
+
+//largest training label plus one for --classes auto, warns about labels without samples
+//since they usually mean the labels arent numbered from 0
+fn infer_classes(config: &Config) -> usize
+{
+    let (dataset, label_sets) = open_labeled(config, &config.train_labels, &config.train_images)
+        .unwrap();
+
+    let mut counts = [0_usize; u8::MAX as usize + 1];
+    match label_sets
+    {
+        Some(label_sets) =>
+        {
+            label_sets.iter().flatten().for_each(|label| counts[*label as usize] += 1);
+        },
+        None => dataset.for_each(|(label, _)| counts[label as usize] += 1)
+    }
+
+    let classes = counts.iter().rposition(|count| *count>0).map(|label| label + 1).unwrap_or(0);
+
+    let missing = counts[..classes].iter().enumerate().filter(|(_, count)| **count==0)
+        .map(|(label, _)| label.to_string())
+        .collect::<Vec<String>>();
+
+    if !config.quiet
+    {
+        println!("inferred {classes} classes from the training labels");
+    }
+
+    if !missing.is_empty()
+    {
+        println!(
+            "warning: no training samples with the labels {}, the labels might have gaps",
+            missing.join(", ")
+        );
+    }
+
+    classes
+}
+
+//applies --input-size, after the cache so the cache stays size independent
+//(unless the cached sources needed resizing to be chained)
+fn resized(config: &Config, dataset: Box<dyn Dataset>) -> Box<dyn Dataset>
+{
+    match config.input_size
+    {
+        Some(size) if (dataset.width(), dataset.height())!=size =>
+        {
+            Box::new(ResizedDigiter::new(dataset, size))
+        },
+        _ => dataset
+    }
+}
+
+fn main()
+{
+    let mut config = Config::create(env::args().skip(1)).unwrap_or_else(|err|
+    {
+        println!("parse error: {err}");
+        Config::help_message()
+    });
+
+    if config.verbose
+    {
+        config.decisions.iter().for_each(|decision| println!("{decision}"));
+    }
+
+    if let Some(path) = &config.list_model
+    {
+        match NeuralNet::inspect(path)
+        {
+            Ok(info) => println!("{info}"),
+            Err(err) => println!("cant read {path}: {err}")
+        }
+
+        return;
+    }
+
+    rayon::ThreadPoolBuilder::new().num_threads(config.threads).build_global().unwrap();
+
+    if config.selftest
+    {
+        match selftest(config.threads)
+        {
+            Ok(()) => println!("OK"),
+            Err(err) =>
+            {
+                println!("self test failed: {err}");
+                process::exit(1)
+            }
+        }
+
+        return;
+    }
+
+    if config.classes==Classes::Auto
+    {
+        let classes = infer_classes(&config);
+        if let Err(err) = config.set_classes(classes)
+        {
+            println!("parse error: {err}");
+            Config::help_message()
+        }
+    }
+
+    if config.standardize
+    {
+        let dataset = open_dataset(&config, &config.train_labels, &config.train_images).unwrap();
+        let images = resized(&config, dataset).map(|(_, image)| image);
+
+        let mut preprocessing = config.preprocessing.unwrap_or_default();
+        let standardization = preprocessing.standardization(images);
+
+        preprocessing.standardize = Some(standardization);
+        config.preprocessing = Some(preprocessing);
+
+        if !config.quiet
+        {
+            println!(
+                "standardizing inputs with mean {} and deviation {}",
+                format_number(standardization.mean, config.precision),
+                format_number(standardization.deviation, config.precision)
+            );
+        }
+    }
+
+    if config.stats
+    {
+        let dataset = open_dataset(&config, &config.train_labels, &config.train_images).unwrap();
+        println!("{:.*}", config.precision, DatasetStats::compute(resized(&config, dataset)));
+    }
+
+    if config.dry_run
+    {
+        let dataset = open_dataset(&config, &config.train_labels, &config.train_images).unwrap();
+        let dataset = resized(&config, dataset);
+
+        println!("{}", prepare_network(&config.filename, input_shape(&dataset), &config));
+        return;
+    }
+
+    let test_set = (!config.no_test).then(||
+    {
+        let (test_dataset, test_label_sets) =
+            open_labeled(&config, &config.test_labels, &config.test_images).unwrap();
+
+        let test_dataset = resized(&config, test_dataset);
+        let size = (test_dataset.width(), test_dataset.height());
+
+        //these treat every input as a pixel of a width by height image
+        if test_dataset.channels()!=1 && (config.robustness || config.show_samples>0)
+        {
+            println!("--robustness and --show-samples only work with single channel images");
+            process::exit(1)
+        }
+
+        (test_images(test_dataset, test_label_sets, config.classes_amount()), size)
+    });
+
+    let test_images = test_set.as_ref().map(|(images, _)| images.as_slice());
+
+    if let (Some(other), Some(images)) = (&config.compare, test_images)
+    {
+        if let Err(err) = compare_networks(&config.filename, other, images, &config)
+        {
+            println!("cant compare the networks: {err}");
+            process::exit(1)
+        }
+
+        return;
+    }
+
+    let open_train = || open_dataset(&config, &config.train_labels, &config.train_images);
+
+    let (train_digiter, train_label_sets): (Box<dyn Dataset>, _) = if let Some(cache) = &config.cache
+    {
+        let sources = config.train_labels.iter().chain(config.train_images.iter())
+            .map(|source| source.as_str())
+            .collect::<Vec<&str>>();
+
+        let cached = CachedDigiter::create(cache, config.dataset.name(), &sources, open_train).unwrap();
+        println!("using dataset cache {cache} (shuffle seed {})", cached.seed());
+
+        (Box::new(cached), None)
+    } else
+    {
+        open_labeled(&config, &config.train_labels, &config.train_images).unwrap()
+    };
+    let interrupted = Arc::new(AtomicBool::new(false));
+
+    //a second ctrl-c exits right away in case saving hangs
+    signal_hook::flag::register_conditional_shutdown(SIGINT, 130, Arc::clone(&interrupted))
+        .and_then(|_| signal_hook::flag::register(SIGINT, Arc::clone(&interrupted)))
+        .unwrap();
+
+    train(
+        &config.filename,
+        resized(&config, train_digiter),
+        train_label_sets,
+        &interrupted,
+        &config
+    );
+
+    if interrupted.load(Ordering::Relaxed)
+    {
+        println!("saved to {}", config.filename);
+        return;
+    }
+
+    if let Some((test_images, size)) = &test_set
+    {
+        test_network(&config.filename, test_images, *size, &config);
+    }
+
+    if let Some(quantized_filename) = &config.quantize
+    {
+        quantize_network(&config.filename, quantized_filename, test_images, &config);
+    }
+
+    if let Some(pruned_filename) = &config.prune
+    {
+        prune_network(&config.filename, pruned_filename, test_images, &config);
+    }
+
+    if let Some(dot_filename) = &config.dot
+    {
+        let network = load_network(&config.filename, &config).unwrap();
+
+        match network.to_dot(config.dot_threshold)
+        {
+            Some(dot) => fs::write(dot_filename, dot).unwrap(),
+            None =>
+            {
+                println!("only networks of dense layers can be drawn");
+                process::exit(1)
+            }
+        }
+    }
+
+    if let Some(dir) = &config.export_npy
+    {
+        load_network(&config.filename, &config).unwrap().export_npy(dir).unwrap();
+    }
+
+    if config.weight_histogram
+    {
+        let network = load_network(&config.filename, &config).unwrap();
+
+        for (index, histogram) in network.weight_histogram(WEIGHT_HISTOGRAM_BINS).iter().enumerate()
+        {
+            //pooling layers have no weights
+            if histogram.weights.total()==0
+            {
+                continue;
+            }
+
+            println!("layer {index} weights:\n{:.*}", config.precision, histogram.weights);
+            println!("layer {index} biases:\n{:.*}", config.precision, histogram.biases);
+        }
+    }
+
+    if let Some(path) = &config.dump_weights
+    {
+        let network = load_network(&config.filename, &config).unwrap();
+
+        let parameters = network.parameters_amount();
+        if parameters>DUMP_WEIGHTS_WARN_PARAMETERS
+        {
+            println!("warning: dumping {parameters} parameters as text, the dump will be very long");
+        }
+
+        let text = match network.dump_weights_text()
+        {
+            Some(text) => text,
+            None =>
+            {
+                println!("only networks of dense layers can be dumped, try --export-npy");
+                process::exit(1)
+            }
+        };
+        if path=="-"
+        {
+            print!("{text}");
+        } else
+        {
+            fs::write(path, text).unwrap();
+        }
+    }
 }
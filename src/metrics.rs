@@ -0,0 +1,386 @@
+use std::{
+    io::{self, Write},
+    fs::{self, File, OpenOptions},
+    time::{SystemTime, UNIX_EPOCH}
+};
+
+use crate::neural_net::*;
+
+
+//fixed decimals, values too small to show a digit are written in scientific notation
+pub fn format_number(value: f64, precision: usize) -> String
+{
+    if value!=0.0 && value.abs() < 0.1_f64.powi(precision as i32)
+    {
+        format!("{value:.precision$e}")
+    } else
+    {
+        format!("{value:.precision$}")
+    }
+}
+
+pub fn format_percent(fraction: f64, precision: usize) -> String
+{
+    format!("{:.precision$}%", fraction * 100.0)
+}
+
+//darkest to brightest
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+//one line per image row, each pixel is two characters wide so the image keeps its shape
+//in a terminal, brightness is relative to the darkest and brightest input of the image
+//so it works however the inputs were preprocessed
+pub fn ascii_image(inputs: &[f64], width: usize) -> Vec<String>
+{
+    let (low, high) = inputs.iter().fold((f64::INFINITY, f64::NEG_INFINITY),
+        |(low, high), input| (low.min(*input), high.max(*input)));
+
+    let range = if high>low {high - low} else {1.0};
+
+    inputs.chunks(width.max(1)).map(|row|
+    {
+        row.iter().flat_map(|input|
+        {
+            let brightness = (input - low) / range;
+            let index = (brightness * (ASCII_RAMP.len() - 1) as f64).round() as usize;
+
+            [ASCII_RAMP[index] as char; 2]
+        }).collect()
+    }).collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Metrics
+{
+    pub correct: usize,
+    //every output on the same side of 0.5 as its target, for multi label targets
+    pub exact_matches: usize,
+    pub total: usize,
+    pub combined_error: f64,
+    loss: Loss
+}
+
+impl Metrics
+{
+    pub fn new(loss: Loss) -> Self
+    {
+        Metrics{loss, ..Default::default()}
+    }
+
+    pub fn evaluate<'a>(
+        network: &mut NeuralNet,
+        samples: impl IntoIterator<Item=&'a TrainSample>
+    ) -> Self
+    {
+        let mut metrics = Metrics::new(network.loss());
+
+        for sample in samples
+        {
+            let outputs = network.feedforward(&sample.inputs);
+            metrics.add(&outputs, &sample.outputs);
+        }
+
+        metrics
+    }
+
+    pub fn add(&mut self, outputs: &[f64], correct: &[f64])
+    {
+        self.combined_error += outputs.iter().zip(correct.iter()).map(|(prediction, correct)|
+        {
+            self.loss.error(*prediction, *correct)
+        }).sum::<f64>();
+
+        if argmax(outputs)==argmax(correct)
+        {
+            self.correct += 1;
+        }
+
+        let matches = outputs.iter().zip(correct.iter())
+            .all(|(output, correct)| (*output>=0.5)==(*correct>=0.5));
+
+        if matches
+        {
+            self.exact_matches += 1;
+        }
+
+        self.total += 1;
+    }
+
+    pub fn accuracy(&self) -> f64
+    {
+        if self.total==0
+        {
+            0.0
+        } else
+        {
+            self.correct as f64 / self.total as f64
+        }
+    }
+
+    pub fn exact_match_rate(&self) -> f64
+    {
+        if self.total==0
+        {
+            0.0
+        } else
+        {
+            self.exact_matches as f64 / self.total as f64
+        }
+    }
+}
+
+pub struct Calibration
+{
+    counts: [usize; Self::BINS],
+    correct: [usize; Self::BINS]
+}
+
+impl Calibration
+{
+    pub const BINS: usize = 10;
+
+    pub fn new() -> Self
+    {
+        Calibration{counts: [0; Self::BINS], correct: [0; Self::BINS]}
+    }
+
+    pub fn add(&mut self, outputs: &[f64], correct: &[f64])
+    {
+        let guess = argmax(outputs);
+        let confidence = outputs[guess].clamp(0.0, 1.0);
+
+        let bin = ((confidence * Self::BINS as f64) as usize).min(Self::BINS - 1);
+
+        self.counts[bin] += 1;
+        if guess==argmax(correct)
+        {
+            self.correct[bin] += 1;
+        }
+    }
+
+    pub fn print(&self, precision: usize)
+    {
+        println!("confidence calibration:");
+        for bin in 0..Self::BINS
+        {
+            let low = bin as f64 / Self::BINS as f64;
+            let high = (bin + 1) as f64 / Self::BINS as f64;
+
+            let count = self.counts[bin];
+            let accuracy = if count==0
+            {
+                "-".to_owned()
+            } else
+            {
+                format_percent(self.correct[bin] as f64 / count as f64, precision)
+            };
+
+            println!("    {low:.1}-{high:.1}: {count:>6} samples, accuracy {accuracy}");
+        }
+    }
+}
+
+//mean squared difference between predicted probabilities and the target over the classes,
+//0 for a fully confident right guess
+pub fn brier_score(probabilities: &[f64], correct: &[f64]) -> f64
+{
+    let squared_sum = probabilities.iter().zip(correct.iter())
+        .map(|(probability, correct)| (probability - correct).powi(2))
+        .sum::<f64>();
+
+    squared_sum / correct.len().max(1) as f64
+}
+
+//0 when either vector is all zeroes
+pub fn cosine_similarity(a: &[f64], b: &[f64]) -> f64
+{
+    let dot = a.iter().zip(b.iter()).map(|(a, b)| a * b).sum::<f64>();
+    let length = |values: &[f64]| values.iter().map(|value| value * value).sum::<f64>().sqrt();
+
+    let lengths = length(a) * length(b);
+    if lengths==0.0
+    {
+        0.0
+    } else
+    {
+        dot / lengths
+    }
+}
+
+//mean embedding (penultimate layer activations) of every class, a misclassified
+//sample close to both its guessed and its correct class sits near the boundary
+pub struct EmbeddingAnalysis
+{
+    sums: Vec<Vec<f64>>,
+    counts: Vec<usize>
+}
+
+impl EmbeddingAnalysis
+{
+    pub fn new(classes: usize, embedding_size: usize) -> Self
+    {
+        EmbeddingAnalysis{sums: vec![vec![0.0; embedding_size]; classes], counts: vec![0; classes]}
+    }
+
+    pub fn add(&mut self, class: usize, embedding: &[f64])
+    {
+        self.sums[class].iter_mut().zip(embedding.iter()).for_each(|(sum, value)| *sum += value);
+        self.counts[class] += 1;
+    }
+
+    pub fn mean(&self, class: usize) -> Option<Vec<f64>>
+    {
+        let count = self.counts[class];
+
+        (count>0).then(|| self.sums[class].iter().map(|sum| sum / count as f64).collect())
+    }
+
+    //similarity to the mean of the guessed class and to the mean of the correct class
+    pub fn similarities(&self, embedding: &[f64], guessed: usize, correct: usize) -> (f64, f64)
+    {
+        let similarity = |class| self.mean(class)
+            .map(|mean| cosine_similarity(embedding, &mean))
+            .unwrap_or(0.0);
+
+        (similarity(guessed), similarity(correct))
+    }
+}
+
+//csv of the batch metrics during training, appended runs start with a comment line
+pub struct MetricsLog
+{
+    file: File,
+    precision: usize
+}
+
+impl MetricsLog
+{
+    const HEADER: &'static str = "iteration,batch_loss,batch_accuracy";
+
+    //the header is only written into empty files so appended runs share it
+    pub fn create(path: &str, append: bool, seed: u64, precision: usize) -> io::Result<Self>
+    {
+        let mut file = if append
+        {
+            OpenOptions::new().create(true).append(true).open(path)?
+        } else
+        {
+            File::create(path)?
+        };
+
+        if fs::metadata(path)?.len()==0
+        {
+            writeln!(file, "{}", Self::HEADER)?;
+        }
+
+        if append
+        {
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+
+            writeln!(file, "# run {timestamp} seed={seed}")?;
+        }
+
+        Ok(MetricsLog{file, precision})
+    }
+
+    pub fn write(&mut self, iteration: usize, metrics: &Metrics) -> io::Result<()>
+    {
+        writeln!(
+            self.file,
+            "{iteration},{},{}",
+            format_number(metrics.combined_error / metrics.total as f64, self.precision),
+            format_number(metrics.accuracy(), self.precision)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn number_formatting()
+    {
+        assert_eq!(format_number(12.34567, 2), "12.35");
+        assert_eq!(format_number(0.0, 3), "0.000");
+        assert_eq!(format_number(0.0004, 3), "4.000e-4");
+        assert_eq!(format_number(-0.00001234, 2), "-1.23e-5");
+        assert_eq!(format_percent(0.98765, 1), "98.8%");
+    }
+
+    #[test]
+    fn brier()
+    {
+        assert_eq!(brier_score(&[0.0, 1.0, 0.0], &[0.0, 1.0, 0.0]), 0.0);
+        assert_eq!(brier_score(&[1.0, 0.0], &[0.0, 1.0]), 1.0);
+        assert!((brier_score(&[0.5, 0.25, 0.25], &[1.0, 0.0, 0.0]) - 0.125).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ascii()
+    {
+        let image = ascii_image(&[0.0, 0.5, 1.0, 1.0, -3.0, 2.0], 3);
+        assert_eq!(image, vec!["++**##".to_owned(), "##  @@".to_owned()]);
+
+        assert_eq!(ascii_image(&[0.3; 2], 1), vec!["  ".to_owned(); 2]);
+    }
+
+    #[test]
+    fn metrics_log()
+    {
+        let path = std::env::temp_dir().join("digitsrecog_metrics_log.csv");
+        let path = path.to_str().unwrap();
+
+        let metrics = Metrics{correct: 3, total: 4, combined_error: 2.0, ..Default::default()};
+
+        let run = |append, seed|
+        {
+            let mut log = MetricsLog::create(path, append, seed, 3).unwrap();
+            log.write(10, &metrics).unwrap();
+        };
+
+        let rows = || fs::read_to_string(path).unwrap().lines()
+            .filter(|line| !line.starts_with("# run"))
+            .map(|line| line.to_owned())
+            .collect::<Vec<String>>();
+
+        run(false, 1);
+        run(false, 2);
+        assert_eq!(rows(), ["iteration,batch_loss,batch_accuracy", "10,0.500,0.750"]);
+
+        run(true, 3);
+        run(true, 4);
+
+        let contents = fs::read_to_string(path).unwrap();
+        assert_eq!(contents.matches("iteration,").count(), 1);
+        assert!(contents.contains("seed=3\n10,0.500,0.750\n") && contents.contains("seed=4\n"));
+        assert_eq!(rows().len(), 4);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn embeddings()
+    {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-12);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-12);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+
+        let mut analysis = EmbeddingAnalysis::new(3, 2);
+        analysis.add(0, &[1.0, 0.0]);
+        analysis.add(0, &[3.0, 0.0]);
+        analysis.add(1, &[0.0, 1.0]);
+
+        assert_eq!(analysis.mean(0), Some(vec![2.0, 0.0]));
+        assert_eq!(analysis.mean(2), None);
+
+        let (guessed, correct) = analysis.similarities(&[1.0, 1.0], 0, 1);
+        assert!((guessed - correct).abs() < 1e-12);
+        assert!((guessed - 0.5_f64.sqrt()).abs() < 1e-12);
+
+        assert_eq!(analysis.similarities(&[1.0, 0.0], 2, 0), (0.0, 1.0));
+    }
+}
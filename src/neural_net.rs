@@ -1,356 +1,2866 @@
 use std::{
-    fs::File,
+    borrow::Borrow,
+    fmt,
+    mem,
+    iter,
+    slice,
+    fs,
     io,
-    thread
+    path::Path
 };
 
 pub use layer::*;
+pub use quantized::*;
+pub use activation_stats::*;
+pub use training_info::*;
+pub use net_info::*;
+pub use preprocessing::*;
+pub use histogram::*;
+pub use schedule::*;
+pub use conv::*;
 
 use serde::{Serialize, Deserialize};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use rayon::prelude::*;
+
+use crate::neural_net_core::{InferenceNet, InferenceLayer};
+
+
+mod layer;
+mod quantized;
+mod activation_stats;
+mod checksum;
+mod training_info;
+mod net_info;
+mod preprocessing;
+mod histogram;
+mod schedule;
+mod conv;
+mod npy;
+
+
+#[derive(Debug, Clone)]
+pub struct TrainSample
+{
+    pub inputs: Vec<f64>,
+    pub outputs: Vec<f64>,
+    pub weight: f64
+}
+
+#[allow(dead_code)]
+impl TrainSample
+{
+    pub fn new(inputs: Vec<f64>, outputs: Vec<f64>) -> Self
+    {
+        TrainSample{inputs, outputs, weight: 1.0}
+    }
+}
+
+//weights given to set_layer_weights with a row length that doesnt match the layer
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeError
+{
+    pub expected: Vec<usize>,
+    pub found: Vec<usize>
+}
+
+impl fmt::Display for ShapeError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "expected weights shaped {:?}, found {:?}", self.expected, self.found)
+    }
+}
+
+//a conv or pooling layer whose window is bigger than the image coming into it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchError
+{
+    pub layer: usize,
+    pub window: usize,
+    pub input: Shape
+}
+
+impl fmt::Display for ArchError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(
+            f,
+            "layer {} has a {}x{} window but its inputs are only {}x{}",
+            self.layer,
+            self.window,
+            self.window,
+            self.input.width,
+            self.input.height
+        )
+    }
+}
+
+impl std::error::Error for ArchError {}
+
+#[derive(Debug)]
+pub enum LoadError
+{
+    Io(io::Error),
+    Decode(ciborium::de::Error<io::Error>),
+    //the checksum doesnt match, the file was truncated or damaged
+    Corrupt,
+    //saved before files had checksums, see NeuralNet::load_unchecksummed
+    Unchecksummed
+}
+
+impl fmt::Display for LoadError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            LoadError::Io(err) => write!(f, "{err}"),
+            LoadError::Decode(err) => write!(f, "invalid network: {err}"),
+            LoadError::Corrupt => write!(f, "network file is corrupt (checksum mismatch)"),
+            LoadError::Unchecksummed => write!(f, "network file has no checksum (old format)")
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+//worst mismatch between the backpropagated and the numerical gradients
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientCheck
+{
+    pub max_error: f64,
+    pub layer: usize,
+    pub neuron: usize,
+    //index into the neurons weights, the last one is the bias
+    pub weight: usize
+}
+
+//one layers weights, the biases are separate since they usually spread differently
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightHistogram
+{
+    pub weights: Histogram,
+    pub biases: Histogram
+}
+
+//spread of a single layers rprop learning rates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LearningRateStats
+{
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64
+}
+
+//per layer buffers for feedforward_pure, one per thread
+#[derive(Debug, Clone)]
+pub struct Scratch
+{
+    layers: Vec<Vec<f64>>
+}
+
+//gradients of some samples kept outside of the network, so threads can each fill
+//their own while sharing it
+#[derive(Debug, Clone)]
+struct Accumulator
+{
+    //none for layers without parameters
+    gradients: Vec<Option<Gradients>>,
+    samples: usize,
+    activation_stats: Option<ActivationStats>,
+    //error derivatives of every layers neurons from the last accumulated sample
+    deltas: Vec<Vec<f64>>
+}
+
+impl Accumulator
+{
+    fn combine(&mut self, other: &Accumulator)
+    {
+        self.samples += other.samples;
+
+        self.gradients.iter_mut().zip(other.gradients.iter()).for_each(|gradients|
+        {
+            if let (Some(gradients), Some(other)) = gradients
+            {
+                gradients.combine(other);
+            }
+        });
+
+        if let (Some(stats), Some(other_stats)) =
+            (self.activation_stats.as_mut(), other.activation_stats.as_ref())
+        {
+            stats.combine(other_stats);
+        }
+    }
+
+    //reduces the gradients of the others into these, mean and median leave
+    //the amount of samples of an average accumulator
+    fn reduce(&mut self, others: &[Accumulator], reduction: GradReduce)
+    {
+        let samples = self.samples + others.iter().map(|other| other.samples).sum::<usize>();
+
+        self.samples = if reduction==GradReduce::Sum
+        {
+            samples
+        } else
+        {
+            (samples as f64 / (others.len() + 1) as f64).round() as usize
+        };
+
+        self.gradients.iter_mut().enumerate().for_each(|(layer, gradients)|
+        {
+            if let Some(gradients) = gradients
+            {
+                let others = others.iter().filter_map(|other| other.gradients[layer].as_ref())
+                    .collect::<Vec<&Gradients>>();
+
+                gradients.reduce(&others, reduction);
+            }
+        });
+
+        if let Some(stats) = self.activation_stats.as_mut()
+        {
+            others.iter().filter_map(|other| other.activation_stats.as_ref())
+                .for_each(|other_stats| stats.combine(other_stats));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NeuralNet
+{
+    inputs_amount: usize,
+    layers: Vec<Box<dyn Layer>>,
+    //flattened so its fields sit next to the others like they did before it was a struct
+    #[serde(flatten)]
+    preprocessing: Preprocessing,
+    #[serde(default)]
+    training_info: TrainingInfo,
+    #[serde(skip)]
+    optimizer: Optimizer,
+    #[serde(skip)]
+    loss: Loss,
+    #[serde(skip)]
+    grad_reduce: GradReduce,
+    //samples in the accumulated gradients
+    #[serde(skip)]
+    gradient_samples: usize,
+    //error derivatives of every layers neurons from the last backpropagated sample
+    #[serde(skip)]
+    deltas: Vec<Vec<f64>>,
+    //whether deltas belong to the last sample, a feedforward makes them stale
+    #[serde(skip)]
+    holds_deltas: bool,
+    #[serde(skip)]
+    activation_stats: Option<ActivationStats>
+}
+
+#[allow(dead_code)]
+impl NeuralNet
+{
+    pub fn create(
+        inputs_amount: usize,
+        layers: &[DefaultLayerSettings],
+    ) -> Self
+    {
+        Self::create_with_rng(inputs_amount, layers, &mut rand::thread_rng())
+    }
+
+    //same seed always gives the same starting weights
+    pub fn create_seeded(
+        inputs_amount: usize,
+        layers: &[DefaultLayerSettings],
+        seed: u64
+    ) -> Self
+    {
+        Self::create_with_rng(inputs_amount, layers, &mut StdRng::seed_from_u64(seed))
+    }
+
+    fn create_with_rng(
+        inputs_amount: usize,
+        layers: &[DefaultLayerSettings],
+        rng: &mut impl Rng
+    ) -> Self
+    {
+        let layers = layers.iter().cloned().map(LayerSettings::Dense).collect::<Vec<_>>();
+
+        Self::create_shaped_with_rng(Shape::flat(inputs_amount), &layers, rng)
+            .expect("dense layers fit any inputs")
+    }
+
+    //a network which can have conv and pooling layers, those see the inputs as an image
+    //of the given shape
+    pub fn create_shaped(input: Shape, layers: &[LayerSettings]) -> Result<Self, ArchError>
+    {
+        Self::create_shaped_with_rng(input, layers, &mut rand::thread_rng())
+    }
+
+    pub fn create_shaped_seeded(
+        input: Shape,
+        layers: &[LayerSettings],
+        seed: u64
+    ) -> Result<Self, ArchError>
+    {
+        Self::create_shaped_with_rng(input, layers, &mut StdRng::seed_from_u64(seed))
+    }
+
+    fn create_shaped_with_rng(
+        input: Shape,
+        layers: &[LayerSettings],
+        rng: &mut impl Rng
+    ) -> Result<Self, ArchError>
+    {
+        assert!(!layers.is_empty(), "network needs at least one layer");
+        assert!(input.size()>0, "network needs at least one input");
+
+        if let Some(index) = layers.iter().position(|layer|
+        {
+            matches!(layer, LayerSettings::Dense(DefaultLayerSettings{size: 0, ..}))
+        })
+        {
+            panic!("layer {index} has no neurons");
+        }
+
+        let mut shape = input;
+        let layers = layers.iter().enumerate().map(|(index, layer)|
+            {
+                let window = match layer
+                {
+                    LayerSettings::Dense(_) => 1,
+                    LayerSettings::Conv(settings) => settings.kernel_size,
+                    LayerSettings::MaxPool{size} => *size
+                };
+
+                if !shape.fits(window)
+                {
+                    return Err(ArchError{layer: index, window, input: shape});
+                }
+
+                let (layer, output) = match layer
+                {
+                    LayerSettings::Dense(DefaultLayerSettings{size, transfer_function}) =>
+                    {
+                        let layer = DefaultLayer::new_with_rng(
+                            *size,
+                            shape.size(),
+                            *transfer_function,
+                            rng
+                        );
+
+                        (Box::new(layer) as Box<dyn Layer>, Shape::flat(*size))
+                    },
+                    LayerSettings::Conv(settings) =>
+                    {
+                        let layer = ConvLayer::new_with_rng(shape, *settings, rng);
+                        let output = layer.output_shape();
+
+                        (Box::new(layer) as Box<dyn Layer>, output)
+                    },
+                    LayerSettings::MaxPool{size} =>
+                    {
+                        let layer = MaxPool::new(shape, *size);
+                        let output = layer.output_shape();
+
+                        (Box::new(layer) as Box<dyn Layer>, output)
+                    }
+                };
+
+                shape = output;
+
+                Ok(layer)
+            }).collect::<Result<Vec<Box<dyn Layer>>, ArchError>>()?;
+
+        Ok(NeuralNet{
+            inputs_amount: input.size(),
+            layers,
+            preprocessing: Preprocessing::default(),
+            training_info: TrainingInfo::default(),
+            optimizer: Optimizer::default(),
+            loss: Loss::default(),
+            grad_reduce: GradReduce::default(),
+            gradient_samples: 0,
+            deltas: Vec::new(),
+            holds_deltas: false,
+            activation_stats: None
+        })
+    }
+
+    pub fn load(filename: &str) -> Result<Self, LoadError>
+    {
+        Self::load_with(filename, false)
+    }
+
+    //also accepts files saved before checksums were added
+    pub fn load_unchecksummed(filename: &str) -> Result<Self, LoadError>
+    {
+        Self::load_with(filename, true)
+    }
+
+    fn load_with(filename: &str, allow_unchecksummed: bool) -> Result<Self, LoadError>
+    {
+        let data = fs::read(filename).map_err(LoadError::Io)?;
+        let payload = checksum::unseal(&data, allow_unchecksummed)?;
+
+        let mut net = ciborium::de::from_reader::<Self, _>(payload).map_err(LoadError::Decode)?;
+
+        net.layers.iter_mut().for_each(|layer| layer.reset_temporary());
+
+        Ok(net)
+    }
+
+    //shape of a saved network without loading its weights or training state
+    pub fn inspect(filename: &str) -> Result<NetInfo, LoadError>
+    {
+        NetInfo::read(filename)
+    }
+
+    //loads a network and replaces its output layer with a freshly initialized one
+    pub fn load_backbone(
+        filename: &str,
+        new_head: DefaultLayerSettings
+    ) -> Result<Self, LoadError>
+    {
+        let mut net = Self::load(filename)?;
+
+        net.layers.pop();
+
+        let previous_size = net.layers.last().map(|layer| layer.size())
+            .unwrap_or(net.inputs_amount);
+
+        let DefaultLayerSettings{size, transfer_function} = new_head;
+        assert!(size>0, "output layer has no neurons");
+
+        net.layers.push(Box::new(DefaultLayer::new(size, previous_size, transfer_function)));
+
+        Ok(net)
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), ciborium::ser::Error<io::Error>>
+    {
+        let mut payload = Vec::new();
+        ciborium::ser::into_writer(&self, &mut payload)?;
+
+        //written next to the file and renamed over it, so a save that gets interrupted
+        //leaves the previous file intact
+        let temporary = format!("{filename}.tmp");
+
+        fs::write(&temporary, checksum::seal(&payload))
+            .and_then(|_| fs::rename(&temporary, filename))
+            .map_err(ciborium::ser::Error::Io)
+    }
+
+    //whether every layer is a dense one, only those can be quantized, drawn or dumped
+    pub fn is_dense(&self) -> bool
+    {
+        self.layers.iter().all(|layer| matches!(layer.saved(), SavedLayer::Default(_)))
+    }
+
+    //none for networks with conv or pooling layers
+    pub fn quantize(&self) -> Option<QuantizedNet>
+    {
+        self.is_dense().then(|| QuantizedNet::new(self))
+    }
+
+    //copy of the weights which can feedforward without std, none for networks with
+    //conv or pooling layers
+    pub fn inference(&self) -> Option<InferenceNet>
+    {
+        if !self.is_dense()
+        {
+            return None;
+        }
+
+        let layers = self.layers.iter().map(|layer|
+        {
+            InferenceLayer::new(layer.weights().to_vec(), layer.transfer_function())
+        }).collect::<Vec<InferenceLayer>>();
+
+        Some(InferenceNet::new(self.inputs_amount, layers).unwrap())
+    }
+
+    pub fn inputs_amount(&self) -> usize
+    {
+        self.inputs_amount
+    }
+
+    pub fn set_preprocessing(&mut self, preprocessing: Preprocessing)
+    {
+        self.preprocessing = preprocessing;
+    }
+
+    pub fn preprocessing(&self) -> Preprocessing
+    {
+        self.preprocessing
+    }
+
+    //inputs for an image preprocessed the way this network was trained
+    pub fn image_inputs(&self, image: &[u8]) -> Vec<f64>
+    {
+        self.preprocessing.apply(image)
+    }
+
+    //outputs for raw pixels, preprocessed the way this network was trained
+    pub fn predict_raw(&mut self, raw_pixels: &[u8]) -> Vec<f64>
+    {
+        let inputs = self.image_inputs(raw_pixels);
+
+        self.feedforward(&inputs)
+    }
+
+    pub fn training_info(&self) -> &TrainingInfo
+    {
+        &self.training_info
+    }
+
+    //adds a finished training run to the training info
+    pub fn record_training(&mut self, iterations: usize, batch_size: usize)
+    {
+        let bounds = self.layers.iter().filter_map(|layer| layer.parameters())
+            .map(|parameters| parameters.learning_rate_bounds())
+            .reduce(|(lowest, highest), (layer_lowest, layer_highest)|
+            {
+                (lowest.min(layer_lowest), highest.max(layer_highest))
+            });
+
+        self.training_info.iterations += iterations;
+        self.training_info.last_batch_size = Some(batch_size);
+        self.training_info.learning_rate_bounds = bounds;
+    }
+
+    pub fn set_optimizer(&mut self, optimizer: Optimizer)
+    {
+        self.optimizer = optimizer;
+    }
+
+    pub fn set_grad_reduce(&mut self, grad_reduce: GradReduce)
+    {
+        self.grad_reduce = grad_reduce;
+    }
+
+    pub fn set_loss(&mut self, loss: Loss)
+    {
+        self.loss = loss;
+    }
+
+    pub fn loss(&self) -> Loss
+    {
+        self.loss
+    }
+
+    //starts recording how often neurons are dead or saturated while training
+    pub fn track_activations(&mut self)
+    {
+        self.activation_stats = Some(ActivationStats::new(&self.layers));
+    }
+
+    pub fn activation_stats(&self) -> Option<&ActivationStats>
+    {
+        self.activation_stats.as_ref()
+    }
+
+    pub fn reset_adaptive(&mut self)
+    {
+        self.layers.iter_mut().filter_map(|layer| layer.parameters_mut())
+            .for_each(|parameters| parameters.reset_adaptive());
+    }
+
+    pub fn parameters_amount(&self) -> usize
+    {
+        self.layers.iter().map(|layer| Self::layer_parameters(layer.as_ref())).sum()
+    }
+
+    //fraction of weights whose rprop learning rate sits at the minimum, if most
+    //of them do the weights barely move anymore
+    pub fn floored_fraction(&self) -> f64
+    {
+        let floored = self.layers.iter().filter_map(|layer| layer.parameters())
+            .map(|parameters| parameters.floored_learning_rates())
+            .sum::<usize>();
+
+        floored as f64 / self.parameters_amount() as f64
+    }
+
+    //weight and bias histograms of every layer, each with bins equal width bins,
+    //layers without weights get empty ones
+    pub fn weight_histogram(&self, bins: usize) -> Vec<WeightHistogram>
+    {
+        (0..self.layers.len()).map(|index|
+        {
+            let (weights, biases) = self.weight_matrix(index);
+
+            WeightHistogram{
+                weights: Histogram::new(weights.iter().flatten().copied(), bins),
+                biases: Histogram::new(biases.iter().copied(), bins)
+            }
+        }).collect()
+    }
+
+    //rprop learning rates of every layer with weights in order
+    pub fn learning_rate_stats(&self) -> Vec<LearningRateStats>
+    {
+        self.layers.iter().filter_map(|layer| layer.parameters()).map(|parameters|
+        {
+            let (min, max) = parameters.learning_rate_bounds();
+
+            let rates = parameters.learning_rates();
+            let amount = rates.iter().map(|rates| rates.len()).sum::<usize>();
+            let mean = rates.iter().flatten().sum::<f64>() / amount as f64;
+
+            LearningRateStats{min, max, mean}
+        }).collect()
+    }
+
+    //zeroes every weight (biases are kept) below the threshold in magnitude,
+    //training afterwards can grow them back, returns the sparsity
+    pub fn prune(&mut self, threshold: f64) -> f64
+    {
+        self.layers.iter_mut().filter_map(|layer| layer.parameters_mut()).for_each(|parameters|
+        {
+            parameters.prune(threshold);
+        });
+
+        self.sparsity()
+    }
+
+    //prunes the smallest fraction of the weights
+    pub fn prune_fraction(&mut self, fraction: f64) -> f64
+    {
+        let mut magnitudes = self.layers.iter().filter_map(|layer| layer.parameters())
+            .flat_map(|parameters| parameters.connections())
+            .map(|weight| weight.abs())
+            .collect::<Vec<f64>>();
+
+        magnitudes.sort_by(|a, b| a.total_cmp(b));
+
+        let amount = (magnitudes.len() as f64 * fraction.clamp(0.0, 1.0)).round() as usize;
+
+        //everything below the first kept weight
+        let threshold = magnitudes.get(amount).copied().unwrap_or(f64::INFINITY);
+
+        self.prune(threshold)
+    }
+
+    //fraction of zero weights, biases not included
+    pub fn sparsity(&self) -> f64
+    {
+        let (zeroes, total) = self.layers.iter().filter_map(|layer| layer.parameters())
+            .flat_map(|parameters| parameters.connections())
+            .fold((0, 0), |(zeroes, total), weight|
+            {
+                (zeroes + (*weight==0.0) as usize, total + 1)
+            });
+
+        zeroes as f64 / total as f64
+    }
+
+    fn layer_parameters(layer: &dyn Layer) -> usize
+    {
+        layer.parameters().map(|parameters| parameters.amount()).unwrap_or(0)
+    }
+
+    pub fn layers_amount(&self) -> usize
+    {
+        self.layers.len()
+    }
+
+    //frozen layers still propagate errors but never update their weights
+    pub fn set_frozen(&mut self, layer: usize, frozen: bool)
+    {
+        assert!(
+            layer<self.layers.len(),
+            "layer index {layer} out of range (network has {} layers)",
+            self.layers.len()
+        );
+
+        if let Some(parameters) = self.layers[layer].parameters_mut()
+        {
+            parameters.set_frozen(frozen);
+        }
+    }
+
+    //starts a layer at its own rprop learning rate instead of the default one,
+    //like smaller rates for the earlier layers when fine tuning
+    pub fn set_learning_rates(&mut self, layer: usize, rate: f64)
+    {
+        assert!(
+            layer<self.layers.len(),
+            "layer index {layer} out of range (network has {} layers)",
+            self.layers.len()
+        );
+
+        if let Some(parameters) = self.layers[layer].parameters_mut()
+        {
+            parameters.fill_learning_rates(rate);
+        }
+    }
+
+    //amount of outputs of a layer, conv and pooling layers have one per pixel of every channel
+    pub fn layer_size(&self, layer: usize) -> usize
+    {
+        self.layers[layer].size()
+    }
+
+    pub fn layer_weights(&self, layer: usize) -> &[Vec<f64>]
+    {
+        self.layers[layer].weights()
+    }
+
+    //zeroes the output layer so every class starts out equally likely, the hidden
+    //layers stay random so training still breaks the symmetry
+    pub fn zero_head(&mut self)
+    {
+        if let Some(parameters) = self.layers.last_mut().unwrap().parameters_mut()
+        {
+            parameters.zero();
+        }
+    }
+
+    //weighted sums of the last feedforward, none once a sample was backpropagated
+    //since then they belong to that sample
+    pub fn layer_pre_activations(&self, layer: usize) -> Option<&[f64]>
+    {
+        (!self.holds_deltas).then(|| self.layers[layer].neurons())
+    }
+
+    //error derivatives of the last backpropagated sample, none after a feedforward
+    pub fn layer_deltas(&self, layer: usize) -> Option<&[f64]>
+    {
+        self.holds_deltas.then(|| self.deltas[layer].as_slice())
+    }
+
+    //a neuron by previous neuron matrix and the biases, empty for layers without weights
+    pub fn weight_matrix(&self, layer: usize) -> (Vec<Vec<f64>>, Vec<f64>)
+    {
+        self.layers[layer].parameters().map(|parameters| parameters.weight_matrix())
+            .unwrap_or_default()
+    }
+
+    //replaces a layers weights (the last weight of each neuron is its bias),
+    //the rprop state of that layer starts over since it belonged to the old weights
+    pub fn set_layer_weights(
+        &mut self,
+        layer: usize,
+        weights: Vec<Vec<f64>>
+    ) -> Result<(), ShapeError>
+    {
+        assert!(
+            layer<self.layers.len(),
+            "layer index {layer} out of range (network has {} layers)",
+            self.layers.len()
+        );
+
+        let shape = |weights: &[Vec<f64>]|
+        {
+            weights.iter().map(|weights| weights.len()).collect::<Vec<usize>>()
+        };
+
+        let expected = shape(self.layers[layer].weights());
+        let found = shape(&weights);
+
+        if expected!=found
+        {
+            return Err(ShapeError{expected, found});
+        }
+
+        if let Some(parameters) = self.layers[layer].parameters_mut()
+        {
+            parameters.weights_mut().iter_mut().zip(weights).for_each(|(current, new)|
+            {
+                *current = new;
+            });
+
+            parameters.reset_adaptive();
+        }
+
+        Ok(())
+    }
+
+    pub fn feedforward(&mut self, inputs: &[f64]) -> Vec<f64>
+    {
+        self.feedforward_inner(inputs);
+
+        self.outputs()
+    }
+
+    //the guessed class, see argmax for how ties are broken
+    pub fn predict(&mut self, inputs: &[f64]) -> usize
+    {
+        argmax(&self.feedforward(inputs))
+    }
+
+    //inputs given as indices of the nonzero values, cheaper for mostly empty images
+    pub fn feedforward_sparse(&mut self, indices: &[usize], values: &[f64]) -> Vec<f64>
+    {
+        assert_eq!(indices.len(), values.len());
+        assert!(indices.iter().all(|index| *index<self.inputs_amount));
+
+        //dense layers can skip the zero inputs, others get the full inputs
+        match self.layers[0].as_any_mut().downcast_mut::<DefaultLayer>()
+        {
+            Some(layer) => layer.feedforward_sparse(indices, values),
+            None =>
+            {
+                let mut inputs = vec![0.0; self.inputs_amount];
+                indices.iter().zip(values.iter()).for_each(|(index, value)| inputs[*index] = *value);
+
+                self.layers[0].feedforward(&inputs, TransferFunction::Nothing);
+            }
+        }
+
+        self.holds_deltas = false;
+        self.feedforward_hidden();
+
+        self.outputs()
+    }
+
+    //compares the backpropagated gradients for the sample to central differences
+    //of the loss, the error is relative to the bigger gradient of the two
+    pub fn gradient_check(&mut self, sample: &TrainSample, epsilon: f64) -> GradientCheck
+    {
+        let mut analytic = self.accumulator();
+
+        let mut scratch = self.scratch();
+        self.feedforward_scratch(&sample.inputs, &mut scratch);
+
+        let neurons = scratch.layers.iter().map(Vec::as_slice).collect::<Vec<&[f64]>>();
+        self.accumulate(&mut analytic, &neurons, sample);
+
+        let loss = self.loss;
+        let error = |network: &mut Self|
+        {
+            network.feedforward(&sample.inputs).iter().zip(sample.outputs.iter())
+                .map(|(output, correct)| loss.error(*output, *correct))
+                .sum::<f64>() * sample.weight
+        };
+
+        let set_weight = |network: &mut Self, layer: usize, neuron: usize, weight: usize, value|
+        {
+            network.layers[layer].parameters_mut().unwrap().weights_mut()[neuron][weight] = value;
+        };
+
+        let mut worst = GradientCheck{max_error: 0.0, layer: 0, neuron: 0, weight: 0};
+        for layer in 0..self.layers.len()
+        {
+            for neuron in 0..self.layers[layer].weights().len()
+            {
+                for weight in 0..self.layers[layer].weights()[neuron].len()
+                {
+                    let normal_weight = self.layers[layer].weights()[neuron][weight];
+
+                    set_weight(self, layer, neuron, weight, normal_weight + epsilon);
+                    let left = error(self);
+
+                    set_weight(self, layer, neuron, weight, normal_weight - epsilon);
+                    let right = error(self);
+
+                    set_weight(self, layer, neuron, weight, normal_weight);
+
+                    let numeric = (left - right) / (2.0 * epsilon);
+                    let gradient = analytic.gradients[layer].as_ref().unwrap()
+                        .rows()[neuron][weight];
+
+                    let scale = gradient.abs().max(numeric.abs()).max(epsilon);
+                    let relative_error = (gradient - numeric).abs() / scale;
+
+                    if relative_error>worst.max_error
+                    {
+                        worst = GradientCheck{max_error: relative_error, layer, neuron, weight};
+                    }
+                }
+            }
+        }
+
+        worst
+    }
+
+    //graphviz graph of the network, blue edges are positive weights and red ones negative,
+    //edges with a weight magnitude below min_weight are left out (the biases arent drawn),
+    //none for networks with conv or pooling layers
+    pub fn to_dot(&self, min_weight: f64) -> Option<String>
+    {
+        if !self.is_dense()
+        {
+            return None;
+        }
+
+        let mut dot = "digraph network\n{\n    rankdir=LR;\n    node [shape=circle, label=\"\"];\n"
+            .to_owned();
+
+        let strongest = self.layers.iter().flat_map(|layer| layer.weights().iter().flatten())
+            .fold(0.0_f64, |strongest, weight| strongest.max(weight.abs()));
+
+        let node = |layer: usize, neuron: usize| format!("n{layer}_{neuron}");
+
+        let layer_names = iter::once("inputs".to_owned()).chain(self.layers.iter()
+            .enumerate()
+            .map(|(index, layer)| format!("layer {index} ({:?})", layer.transfer_function())));
+
+        let sizes = iter::once(self.inputs_amount).chain(self.layers.iter().map(|layer| layer.size()));
+
+        for (layer, (name, size)) in layer_names.zip(sizes).enumerate()
+        {
+            dot += &format!("    subgraph cluster_{layer}\n    {{\n        label=\"{name}\";\n");
+
+            for neuron in 0..size
+            {
+                dot += &format!("        {};\n", node(layer, neuron));
+            }
+
+            dot += "    }\n";
+        }
+
+        for (index, layer) in self.layers.iter().enumerate()
+        {
+            for (neuron, weights) in layer.weights().iter().enumerate()
+            {
+                //the last weight is the bias
+                for (previous, weight) in weights[..weights.len()-1].iter().enumerate()
+                {
+                    if weight.abs()<min_weight
+                    {
+                        continue;
+                    }
+
+                    let color = if *weight<0.0 {"red"} else {"blue"};
+                    let width = weight.abs() / strongest * 3.0 + 0.1;
+
+                    dot += &format!(
+                        "    {} -> {} [color={color}, penwidth={width:.2}];\n",
+                        node(index, previous),
+                        node(index + 1, neuron)
+                    );
+                }
+            }
+        }
+
+        dot += "}\n";
+
+        Some(dot)
+    }
+
+    //every weight and bias as a grid, a row per neuron with a column per input,
+    //only readable for small networks, none for networks with conv or pooling layers
+    pub fn dump_weights_text(&self) -> Option<String>
+    {
+        if !self.is_dense()
+        {
+            return None;
+        }
+
+        let mut text = String::new();
+
+        let mut previous_size = self.inputs_amount;
+        for (index, layer) in self.layers.iter().enumerate()
+        {
+            text += &format!(
+                "layer {index} ({:?}, {previous_size} inputs, {} neurons)\n",
+                layer.transfer_function(),
+                layer.size()
+            );
+
+            let label_width = (layer.size().max(1) - 1).to_string().len();
+            for (neuron, weights) in layer.weights().iter().enumerate()
+            {
+                //the last weight is the bias
+                let (bias, weights) = weights.split_last().unwrap();
+
+                let row = weights.iter().map(|weight| format!("{weight:>9.4}"))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+
+                text += &format!("    neuron {neuron:>label_width$}: {row} | bias {bias:>9.4}\n");
+            }
+
+            previous_size = layer.size();
+        }
+
+        Some(text)
+    }
+
+    //writes layer{i}_weights.npy shaped (size, previous_size) and layer{i}_bias.npy shaped (size,)
+    //for every layer with weights into dir, which gets created if it doesnt exist, conv layers
+    //are shaped (channels, kernel weights) with the kernels flattened
+    pub fn export_npy(&self, dir: &str) -> io::Result<()>
+    {
+        let dir = Path::new(dir);
+        fs::create_dir_all(dir)?;
+
+        let mut weighted = (0..self.layers.len())
+            .filter(|index| self.layers[*index].parameters().is_some());
+
+        weighted.try_for_each(|index|
+        {
+            let (weights, biases) = self.weight_matrix(index);
+            let previous_size = weights.first().map(|weights| weights.len()).unwrap_or(0);
+
+            let weights = npy::encode(
+                &[weights.len(), previous_size],
+                &weights.into_iter().flatten().collect::<Vec<f64>>()
+            );
+
+            fs::write(dir.join(format!("layer{index}_weights.npy")), weights)?;
+            fs::write(dir.join(format!("layer{index}_bias.npy")), npy::encode(&[biases.len()], &biases))
+        })
+    }
+
+    //output layer values before its transfer function
+    pub fn logits(&mut self, inputs: &[f64]) -> Vec<f64>
+    {
+        self.feedforward_inner(inputs);
+
+        self.layers.last().unwrap().neurons().to_vec()
+    }
+
+    //logits and outputs of the same feedforward, the outputs are the logits after the
+    //output layers transfer function
+    pub fn probe(&mut self, inputs: &[f64]) -> (Vec<f64>, Vec<f64>)
+    {
+        self.feedforward_inner(inputs);
+
+        (self.layers.last().unwrap().neurons().to_vec(), self.outputs())
+    }
+
+    fn outputs(&self) -> Vec<f64>
+    {
+        let last_layer = self.layers.last().unwrap();
+        
+        let transfer_function = last_layer.transfer_function();
+        last_layer.neurons().iter().map(|n| transfer_function.t_f(*n)).collect::<Vec<f64>>()
+    }
+
+    pub fn scratch(&self) -> Scratch
+    {
+        Scratch{
+            layers: self.layers.iter().map(|layer| vec![0.0; layer.size()]).collect()
+        }
+    }
+
+    //doesnt touch the network so it can be shared between threads
+    pub fn feedforward_pure(&self, inputs: &[f64], scratch: &mut Scratch) -> Vec<f64>
+    {
+        self.feedforward_scratch(inputs, scratch);
+
+        let transfer_function = self.layers.last().unwrap().transfer_function();
+        scratch.layers.last().unwrap().iter().map(|n| transfer_function.t_f(*n))
+            .collect::<Vec<f64>>()
+    }
+
+    //leaves the pre-activations of every layer in the scratch
+    fn feedforward_scratch(&self, inputs: &[f64], scratch: &mut Scratch)
+    {
+        for (index, layer) in self.layers.iter().enumerate()
+        {
+            if index==0
+            {
+                layer.feedforward_into(inputs, TransferFunction::Nothing, &mut scratch.layers[0]);
+            } else
+            {
+                let (previous, current) = scratch.layers.split_at_mut(index);
+
+                layer.feedforward_into(
+                    &previous[index-1],
+                    self.layers[index-1].transfer_function(),
+                    &mut current[0]
+                );
+            }
+        }
+    }
+
+    pub fn activations(&mut self, inputs: &[f64]) -> Vec<Vec<f64>>
+    {
+        self.feedforward_inner(inputs);
+
+        self.layers.iter().map(|layer|
+        {
+            let transfer_function = layer.transfer_function();
+            layer.neurons().iter().map(|n| transfer_function.t_f(*n)).collect::<Vec<f64>>()
+        }).collect::<Vec<Vec<f64>>>()
+    }
+
+    //derivative of the target_class output with respect to every input, a saliency map
+    //of which inputs the prediction depends on the most, the weights and gradients stay as is
+    pub fn input_gradient(&mut self, inputs: &[f64], target_class: usize) -> Vec<f64>
+    {
+        self.feedforward_inner(inputs);
+
+        let output_layer = self.layers.last().unwrap();
+        let transfer_function = output_layer.transfer_function();
+
+        //derivatives of the output with respect to every neuron before its transfer function
+        let mut deltas = output_layer.neurons().iter().enumerate().map(|(neuron, value)|
+        {
+            if neuron==target_class {transfer_function.dt_f(*value)} else {0.0}
+        }).collect::<Vec<f64>>();
+
+        for layer in (0..self.layers.len()).rev()
+        {
+            let (previous_values, previous_transfer) = if layer==0
+            {
+                (inputs, TransferFunction::Nothing)
+            } else
+            {
+                let previous = &self.layers[layer - 1];
+                (previous.neurons(), previous.transfer_function())
+            };
+
+            let previous_activations = previous_values.iter()
+                .map(|value| previous_transfer.t_f(*value))
+                .collect::<Vec<f64>>();
+
+            let mut errors = vec![0.0; previous_values.len()];
+            self.layers[layer].input_errors(&previous_activations, &deltas, &mut errors);
+
+            deltas = errors.iter().zip(previous_values.iter()).map(|(error, value)|
+            {
+                previous_transfer.dt_f(*value) * error
+            }).collect();
+        }
+
+        deltas
+    }
+
+    fn feedforward_inner(&mut self, inputs: &[f64])
+    {
+        self.layers[0].feedforward(inputs, TransferFunction::Nothing);
+        self.holds_deltas = false;
+
+        self.feedforward_hidden();
+    }
+
+    //every layer after the first one
+    fn feedforward_hidden(&mut self)
+    {
+        for layer in 1..self.layers.len()
+        {
+            let (previous, current) = self.layers.split_at_mut(layer);
+            let previous_layer = &previous[layer-1];
+
+            current[0].feedforward(previous_layer.neurons(), previous_layer.transfer_function());
+        }
+    }
+
+    //samples can be owned or borrowed so batches dont have to clone them
+    pub fn backpropagate_multithreaded<S: Borrow<TrainSample> + Sync>(
+        &mut self,
+        samples: &[S],
+        threads: usize
+    )
+    {
+        self.backpropagate_multithreaded_nonapply(samples, threads);
+        self.apply_gradients();
+    }
+
+    //accumulates gradients without applying them, call apply_gradients after,
+    //only a single thread keeps the deltas of the last sample for layer_deltas
+    pub fn backpropagate_multithreaded_nonapply<S: Borrow<TrainSample> + Sync>(
+        &mut self,
+        samples: &[S],
+        threads: usize
+    )
+    {
+        if threads<=1
+        {
+            self.backpropagate_nonapply(samples);
+            return;
+        }
+
+        //the deltas of the threads stay in their accumulators
+        self.holds_deltas = false;
+
+        if samples.is_empty()
+        {
+            return;
+        }
+
+        //one chunk per thread, the network is shared and only the accumulators are per thread
+        let chunk_size = samples.len().div_ceil(threads);
+
+        let this = &*self;
+        let accumulate_chunk = |(mut accumulator, mut scratch): (Accumulator, Scratch), chunk: &[S]|
+        {
+            for sample in chunk
+            {
+                let sample = sample.borrow();
+
+                this.feedforward_scratch(&sample.inputs, &mut scratch);
+
+                let neurons = scratch.layers.iter().map(Vec::as_slice).collect::<Vec<&[f64]>>();
+                this.accumulate(&mut accumulator, &neurons, sample);
+            }
+
+            (accumulator, scratch)
+        };
+
+        let chunks = samples.par_chunks(chunk_size);
+
+        //sums can be combined pairwise as the threads finish, the other reductions need
+        //every threads gradients at once
+        let combined = if self.grad_reduce==GradReduce::Sum
+        {
+            chunks.fold(|| (this.accumulator(), this.scratch()), accumulate_chunk)
+                .map(|(accumulator, _)| accumulator)
+                .reduce_with(|mut accumulator, other|
+                {
+                    accumulator.combine(&other);
+
+                    accumulator
+                })
+        } else
+        {
+            let mut accumulators = chunks.map(|chunk|
+            {
+                accumulate_chunk((this.accumulator(), this.scratch()), chunk).0
+            }).collect::<Vec<Accumulator>>();
+
+            let mut reduced = accumulators.pop();
+
+            if let Some(reduced) = reduced.as_mut()
+            {
+                reduced.reduce(&accumulators, self.grad_reduce);
+            }
+
+            reduced
+        };
+
+        if let Some(combined) = combined
+        {
+            self.add_accumulated(&combined);
+        }
+    }
+
+    //empty gradients for every layer to backpropagate into without touching the network
+    fn accumulator(&self) -> Accumulator
+    {
+        Accumulator{
+            gradients: self.layers.iter().map(|layer|
+            {
+                layer.parameters().map(|parameters| parameters.empty_gradients())
+            }).collect(),
+            samples: 0,
+            activation_stats: self.activation_stats.is_some()
+                .then(|| ActivationStats::new(&self.layers)),
+            deltas: self.layers.iter().map(|layer| vec![0.0; layer.size()]).collect()
+        }
+    }
+
+    fn add_accumulated(&mut self, accumulator: &Accumulator)
+    {
+        self.gradient_samples += accumulator.samples;
+
+        self.layers.iter_mut().zip(accumulator.gradients.iter()).for_each(|(layer, gradients)|
+        {
+            if let Some(gradients) = gradients
+            {
+                layer.combine(gradients);
+            }
+        });
+
+        if let (Some(stats), Some(other_stats)) =
+            (self.activation_stats.as_mut(), accumulator.activation_stats.as_ref())
+        {
+            stats.combine(other_stats);
+        }
+    }
+
+    pub fn backpropagate<S: Borrow<TrainSample>>(&mut self, samples: &[S])
+    {
+        self.backpropagate_nonapply(samples);
+        self.apply_gradients();
+    }
+
+    //online learning, updates the weights right after every sample
+    //rprop only looks at gradient signs so single noisy samples keep flipping them,
+    //use the sgd optimizer for this
+    pub fn train_one(&mut self, sample: &TrainSample)
+    {
+        self.backpropagate(slice::from_ref(sample));
+    }
+
+    fn backpropagate_nonapply<S: Borrow<TrainSample>>(&mut self, samples: &[S])
+    {
+        if samples.is_empty()
+        {
+            return;
+        }
+
+        let mut accumulator = self.take_accumulator();
+        for sample in samples
+        {
+            let sample = sample.borrow();
+
+            self.feedforward_inner(&sample.inputs);
+
+            let neurons = self.layers.iter().map(|layer| layer.neurons()).collect::<Vec<&[f64]>>();
+            self.accumulate(&mut accumulator, &neurons, sample);
+        }
+
+        self.restore_accumulator(accumulator);
+        self.holds_deltas = true;
+    }
+
+    //accumulator out of the networks own buffers, so the gradients keep adding up in place
+    fn take_accumulator(&mut self) -> Accumulator
+    {
+        let shaped = self.deltas.len()==self.layers.len()
+            && self.deltas.iter().zip(self.layers.iter()).all(|(deltas, layer)| deltas.len()==layer.size());
+
+        if !shaped
+        {
+            self.deltas = self.layers.iter().map(|layer| vec![0.0; layer.size()]).collect();
+        }
+
+        Accumulator{
+            gradients: self.layers.iter_mut().map(|layer|
+            {
+                layer.parameters_mut().map(|parameters| parameters.take_gradients())
+            }).collect(),
+            samples: 0,
+            activation_stats: self.activation_stats.take(),
+            deltas: mem::take(&mut self.deltas)
+        }
+    }
+
+    fn restore_accumulator(&mut self, accumulator: Accumulator)
+    {
+        self.gradient_samples += accumulator.samples;
+
+        self.layers.iter_mut().zip(accumulator.gradients).for_each(|(layer, gradients)|
+        {
+            if let (Some(parameters), Some(gradients)) = (layer.parameters_mut(), gradients)
+            {
+                parameters.set_gradients(gradients);
+            }
+        });
+
+        self.activation_stats = accumulator.activation_stats;
+        self.deltas = accumulator.deltas;
+    }
+
+    pub fn apply_gradients(&mut self)
+    {
+        let (optimizer, samples_amount) = (self.optimizer, self.gradient_samples);
+        self.layers.iter_mut().for_each(|layer|
+        {
+            layer.apply_gradients(optimizer, samples_amount);
+        });
+
+        self.gradient_samples = 0;
+    }
+
+    //adds the gradients of a sample to the accumulator, neurons are the pre-activations
+    //of every layer from feedforwarding its inputs
+    fn accumulate(&self, accumulator: &mut Accumulator, neurons: &[&[f64]], sample: &TrainSample)
+    {
+        accumulator.samples += 1;
+
+        if let Some(stats) = accumulator.activation_stats.as_mut()
+        {
+            stats.record(neurons);
+        }
+
+        let loss = self.loss;
+        let last_layer = self.layers.len()-1;
+
+        let transfer_function = self.layers[last_layer].transfer_function();
+        accumulator.deltas[last_layer].iter_mut().zip(neurons[last_layer].iter())
+            .zip(sample.outputs.iter())
+            .for_each(|((delta, neuron), correct)|
+            {
+                let error = loss.derivative(transfer_function.t_f(*neuron), *correct)
+                    * sample.weight;
+
+                *delta = transfer_function.dt_f(*neuron) * error;
+            });
+
+        for layer in (0..self.layers.len()).rev()
+        {
+            let previous_activations;
+            let (inputs, tf) = if layer==0
+            {
+                (sample.inputs.as_slice(), TransferFunction::Nothing)
+            } else
+            {
+                let tf = self.layers[layer-1].transfer_function();
+
+                previous_activations = neurons[layer-1].iter().map(|neuron| tf.t_f(*neuron))
+                    .collect::<Vec<f64>>();
+
+                (previous_activations.as_slice(), tf)
+            };
+
+            //split so the current deltas can be read while the previous ones get written
+            let (previous_deltas, deltas) = accumulator.deltas.split_at_mut(layer);
+            let deltas = &deltas[0];
+
+            if let Some(gradients) = accumulator.gradients[layer].as_mut()
+            {
+                self.layers[layer].backpropagate(inputs, deltas, gradients);
+            }
+
+            if let Some(previous_deltas) = previous_deltas.last_mut()
+            {
+                self.layers[layer].input_errors(inputs, deltas, previous_deltas);
+
+                previous_deltas.iter_mut().zip(neurons[layer-1].iter())
+                    .for_each(|(delta, neuron)|
+                    {
+                        *delta *= tf.dt_f(*neuron);
+                    });
+            }
+        }
+    }
+}
+
+impl fmt::Display for NeuralNet
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        writeln!(f, "inputs: {}", self.inputs_amount)?;
+
+        if self.preprocessing.scale!=DEFAULT_INPUT_SCALE
+        {
+            writeln!(f, "input scale: {}", self.preprocessing.scale)?;
+        }
+
+        if let Some(threshold) = self.preprocessing.binarize
+        {
+            writeln!(f, "binarize threshold: {threshold}")?;
+        }
+
+        if let Some(Standardization{mean, deviation}) = self.preprocessing.standardize
+        {
+            writeln!(f, "standardized with mean {mean} and deviation {deviation}")?;
+        }
+
+        if self.training_info.iterations>0
+        {
+            writeln!(f, "{}", self.training_info)?;
+        }
+
+        for (index, layer) in self.layers.iter().enumerate()
+        {
+            let frozen = layer.parameters().is_some_and(|parameters| parameters.frozen());
+            let frozen = if frozen {" (frozen)"} else {""};
+
+            let parameters = Self::layer_parameters(layer.as_ref());
+            let transfer_function = layer.transfer_function();
+
+            let kind = match layer.saved()
+            {
+                SavedLayer::Default(_) => format!("{} neurons", layer.size()),
+                SavedLayer::Conv(conv) =>
+                {
+                    let kernel = conv.kernel_size();
+                    let (stride, output) = (conv.stride(), conv.output_shape());
+
+                    format!("conv {kernel}x{kernel} stride {stride}, {output} outputs")
+                },
+                SavedLayer::MaxPool(pool) =>
+                {
+                    let (size, output) = (pool.pool_size(), pool.output_shape());
+
+                    writeln!(f, "layer {index}: max pool {size}x{size}, {output} outputs")?;
+                    continue;
+                }
+            };
+
+            writeln!(
+                f,
+                "layer {index}: {kind}, {transfer_function:?}, {parameters} parameters{frozen}"
+            )?;
+        }
+
+        write!(f, "total parameters: {}", self.parameters_amount())
+    }
+}
+
 #[cfg(test)]
-use rand::Rng;
+mod tests
+{
+    use super::*;
+    use crate::neural_net_core::{InferenceNet, InferenceLayer};
+    use layer::tests::{get_weight, get_gradient, get_learning_rate};
+
+    #[test]
+    fn backprop()
+    {
+        for _ in 0..10
+        {
+            backprop_single();
+        }
+    }
+
+    fn backprop_single()
+    {
+        let mut rng = rand::thread_rng();
+
+        let layers_amount = rng.gen_range(1..10);
+        let layers = (0..layers_amount).map(|_|
+            {
+                let transfer_functions = [TransferFunction::Sigmoid];
+                let t_index = rng.gen_range(0..transfer_functions.len());
+
+                let transfer_function = transfer_functions[t_index];
+
+                let size = rng.gen_range(1..10);
+                DefaultLayerSettings{size, transfer_function}
+            }).collect::<Vec<DefaultLayerSettings>>();
+    
+        let inputs_amount = rng.gen_range(1..10);
+        let mut network = NeuralNet::create(inputs_amount, &layers);
+
+        let outputs_amount = layers.last().unwrap().size;
+        let sample = random_samples(&mut rng, 1, inputs_amount, outputs_amount).remove(0);
+
+        let check = network.gradient_check(&sample, 0.0001);
+
+        println!("{check:?}");
+        assert!(check.max_error<0.001);
+    }
+
+    #[test]
+    fn activations()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 6, transfer_function: TransferFunction::Relu},
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+
+        let mut rng = rand::thread_rng();
+        let inputs = (0..5).map(|_| rng.gen()).collect::<Vec<f64>>();
+
+        let activations = network.activations(&inputs);
+
+        assert_eq!(activations.len(), layers.len());
+        for (activation, layer) in activations.iter().zip(layers.iter())
+        {
+            assert_eq!(activation.len(), layer.size);
+        }
+
+        assert_eq!(activations.last().unwrap(), &network.feedforward(&inputs));
+    }
+
+    #[test]
+    fn loss_gradients()
+    {
+        let losses = [Loss::Mse, Loss::Huber{delta: 0.1}, Loss::CrossEntropy];
+
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+
+        let mut rng = rand::thread_rng();
+        let sample = TrainSample::new(
+            (0..5).map(|_| rng.gen()).collect::<Vec<f64>>(),
+            (0..3).map(|_| rng.gen_range(0..2) as f64).collect::<Vec<f64>>()
+        );
+
+        for loss in losses
+        {
+            let mut network = NeuralNet::create(5, &layers);
+            network.set_loss(loss);
+
+            let check = network.gradient_check(&sample, 0.00001);
+            assert!(check.max_error<0.001, "{loss:?} {check:?}");
+        }
+    }
+
+    //loss over the samples divided by how many there are
+    fn mean_loss(network: &mut NeuralNet, samples: &[TrainSample]) -> f64
+    {
+        let loss = network.loss();
+        samples.iter().map(|sample|
+        {
+            network.feedforward(&sample.inputs).iter().zip(sample.outputs.iter())
+                .map(|(output, correct)| loss.error(*output, *correct))
+                .sum::<f64>()
+        }).sum::<f64>() / samples.len() as f64
+    }
+
+    //inputs and targets uniform in 0..1
+    fn random_samples(
+        rng: &mut impl Rng,
+        amount: usize,
+        inputs: usize,
+        outputs: usize
+    ) -> Vec<TrainSample>
+    {
+        (0..amount).map(|_|
+        {
+            TrainSample::new(
+                (0..inputs).map(|_| rng.gen()).collect::<Vec<f64>>(),
+                (0..outputs).map(|_| rng.gen()).collect::<Vec<f64>>()
+            )
+        }).collect::<Vec<TrainSample>>()
+    }
+
+    fn all_gradients(network: &mut NeuralNet) -> Vec<f64>
+    {
+        network.layers.iter_mut().flat_map(|layer|
+        {
+            let shape = layer.weights().iter().map(|weights| weights.len())
+                .collect::<Vec<usize>>();
+
+            shape.into_iter().enumerate().flat_map(|(neuron, previous_amount)|
+            {
+                (0..previous_amount).map(|previous| *get_gradient(layer, neuron, previous))
+                    .collect::<Vec<f64>>()
+            }).collect::<Vec<f64>>()
+        }).collect::<Vec<f64>>()
+    }
+
+    #[test]
+    fn accumulated_gradients()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let samples = random_samples(&mut rng, 100, 5, 3);
+
+        let mut accumulated = network.clone();
+        let mut accumulated_threaded = network.clone();
+        for batch in samples.chunks(25)
+        {
+            accumulated.backpropagate_multithreaded_nonapply(batch, 1);
+            accumulated_threaded.backpropagate_multithreaded_nonapply(batch, 3);
+        }
+
+        network.backpropagate_multithreaded_nonapply(&samples, 1);
+
+        let gradients = all_gradients(&mut network);
+        assert_eq!(all_gradients(&mut accumulated), gradients);
+
+        for (threaded, gradient) in all_gradients(&mut accumulated_threaded).iter()
+            .zip(gradients.iter())
+        {
+            assert!((threaded - gradient).abs() < 0.000001);
+        }
+    }
+
+    #[test]
+    fn borrowed_samples()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut owned = NeuralNet::create(5, &layers);
+        let mut borrowed = owned.clone();
+
+        let mut rng = StdRng::seed_from_u64(2);
+        let samples = random_samples(&mut rng, 20, 5, 3);
+
+        //a shuffled batch of references into the samples
+        let indices = [3, 17, 0, 3, 9, 12];
+        let batch = indices.iter().map(|index| samples[*index].clone())
+            .collect::<Vec<TrainSample>>();
+        let batch_refs = indices.iter().map(|index| &samples[*index])
+            .collect::<Vec<&TrainSample>>();
+
+        owned.backpropagate_multithreaded_nonapply(&batch, 1);
+        borrowed.backpropagate_multithreaded_nonapply(&batch_refs, 1);
+
+        assert_eq!(all_gradients(&mut borrowed), all_gradients(&mut owned));
+    }
+
+    #[test]
+    fn single_thread_path()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let network = NeuralNet::create_seeded(5, &layers, 11);
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let samples = random_samples(&mut rng, 30, 5, 3);
+
+        let mut direct = network.clone();
+        direct.backpropagate_nonapply(&samples);
+
+        //a single thread sums into the networks own gradients and keeps the deltas of
+        //the last sample, the threaded path only adds its accumulators at the end
+        let mut single = network.clone();
+        single.backpropagate_multithreaded_nonapply(&samples, 1);
+
+        assert_eq!(all_gradients(&mut single), all_gradients(&mut direct));
+        assert!(single.layer_deltas(1).is_some());
+        assert_eq!(single.layer_deltas(1), direct.layer_deltas(1));
+
+        let mut threaded = network.clone();
+        threaded.backpropagate_multithreaded_nonapply(&samples, 3);
+
+        assert!(threaded.layer_deltas(1).is_none());
+        assert_eq!(threaded.gradient_samples, single.gradient_samples);
+    }
+
+    #[test]
+    fn threaded_path_drops_deltas()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create_seeded(5, &layers, 12);
+
+        let mut rng = StdRng::seed_from_u64(12);
+        let samples = random_samples(&mut rng, 10, 5, 3);
+
+        network.backpropagate_multithreaded_nonapply(&samples, 1);
+        assert!(network.layer_deltas(0).is_some());
+
+        //the deltas from the single threaded batch would be stale now
+        network.backpropagate_multithreaded_nonapply(&samples, 2);
+        assert!(network.layer_deltas(0).is_none());
+    }
+
+    #[test]
+    fn reduced_gradients()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let network = NeuralNet::create(5, &layers);
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let samples = random_samples(&mut rng, 30, 5, 3);
+
+        //gradients of each of the 3 thread chunks
+        let chunks = samples.chunks(10).map(|chunk|
+        {
+            let mut network = network.clone();
+            network.backpropagate_nonapply(chunk);
+
+            all_gradients(&mut network)
+        }).collect::<Vec<Vec<f64>>>();
+
+        for reduction in [GradReduce::Mean, GradReduce::Median]
+        {
+            let mut reduced = network.clone();
+            reduced.set_grad_reduce(reduction);
+            reduced.backpropagate_multithreaded_nonapply(&samples, 3);
+
+            assert_eq!(reduced.gradient_samples, 10);
+
+            for (index, gradient) in all_gradients(&mut reduced).into_iter().enumerate()
+            {
+                let mut values = chunks.iter().map(|chunk| chunk[index]).collect::<Vec<f64>>();
+                assert!((gradient - reduction.reduce(&mut values)).abs() < 0.000001);
+            }
+        }
+    }
+
+    #[test]
+    fn mean_gradients()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+        network.set_optimizer(Optimizer::Sgd{learning_rate: 1.0, momentum: 0.0});
+
+        let mut rng = StdRng::seed_from_u64(4);
+        let samples = random_samples(&mut rng, 100, 5, 3);
+
+        let weights = |network: &NeuralNet|
+        {
+            (0..network.layers_amount()).flat_map(|layer|
+            {
+                network.layer_weights(layer).iter().flatten().copied().collect::<Vec<f64>>()
+            }).collect::<Vec<f64>>()
+        };
+
+        let initial = weights(&network);
+
+        let mut summed = network.clone();
+        summed.backpropagate_nonapply(&samples);
+        let gradients = all_gradients(&mut summed);
+
+        for threads in [1, 3]
+        {
+            let mut network = network.clone();
+            network.backpropagate_multithreaded(&samples, threads);
+
+            let changes = weights(&network).into_iter().zip(initial.iter())
+                .map(|(weight, initial)| initial - weight);
+
+            for (change, gradient) in changes.zip(gradients.iter())
+            {
+                assert!((change - gradient / samples.len() as f64).abs() < 0.000001);
+            }
+        }
+    }
+
+    #[test]
+    fn load_backbone()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 6, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let network = NeuralNet::create(5, &layers);
+
+        let path = std::env::temp_dir().join("digitsrecog_load_backbone.nn");
+        let path = path.to_str().unwrap();
+
+        network.save(path).unwrap();
+
+        let head = DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Relu};
+        let mut loaded = NeuralNet::load_backbone(path, head).unwrap();
+
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.layers.len(), layers.len());
+        for (layer, loaded_layer) in network.layers.iter().zip(loaded.layers.iter()).take(2)
+        {
+            assert_eq!(layer.weights(), loaded_layer.weights());
+        }
+
+        let head = loaded.layers.last().unwrap();
+        assert_eq!(head.size(), 3);
+        assert!(head.weights().iter().all(|weights| weights.len()==4+1));
+
+        assert_eq!(loaded.feedforward(&[0.0; 5]).len(), 3);
+    }
+
+    #[test]
+    fn binarize()
+    {
+        let layers = [DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}];
+        let mut network = NeuralNet::create(4, &layers);
+
+        let image = [0, 127, 128, 255];
+        assert_eq!(network.image_inputs(&image), Preprocessing::default().apply(&image));
+
+        let binarized = Preprocessing{binarize: Some(0.5), ..Default::default()};
+        network.set_preprocessing(binarized);
+
+        let path = std::env::temp_dir().join("digitsrecog_binarize.nn");
+        let path = path.to_str().unwrap();
+
+        network.save(path).unwrap();
+        let mut loaded = NeuralNet::load(path).unwrap();
+
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.preprocessing(), binarized);
+        assert_eq!(loaded.image_inputs(&image), vec![0.0, 0.0, 1.0, 1.0]);
+
+        let inputs = binarized.apply(&image);
+        assert_eq!(loaded.predict_raw(&image), network.feedforward(&inputs));
+    }
+
+    #[test]
+    fn preprocessing()
+    {
+        let layers = [DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}];
+        let mut network = NeuralNet::create(4, &layers);
+
+        let image = [0, 25, 50, 100];
+
+        let preprocessing = Preprocessing{
+            scale: 100.0,
+            binarize: None,
+            standardize: Some(Standardization{mean: 0.5, deviation: 0.25})
+        };
+        network.set_preprocessing(preprocessing);
+
+        let path = std::env::temp_dir().join("digitsrecog_preprocessing.nn");
+        let path = path.to_str().unwrap();
+
+        network.save(path).unwrap();
+        let mut loaded = NeuralNet::load(path).unwrap();
+
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.preprocessing(), preprocessing);
+        assert_eq!(loaded.image_inputs(&image), vec![-2.0, -1.0, 0.0, 2.0]);
+        assert_eq!(loaded.predict_raw(&image), network.feedforward(&[-2.0, -1.0, 0.0, 2.0]));
+
+        let binarized = Preprocessing{scale: 100.0, binarize: Some(0.5), standardize: None};
+        assert_eq!(binarized.apply(&image), vec![0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn export_npy()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Relu},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let network = NeuralNet::create(5, &layers);
+
+        let dir = std::env::temp_dir().join("digitsrecog_export_npy");
+        network.export_npy(dir.to_str().unwrap()).unwrap();
+
+        let read = |name: &str|
+        {
+            let file = npyz::NpyFile::new(fs::File::open(dir.join(name)).unwrap()).unwrap();
+            let shape = file.shape().to_vec();
+
+            (shape, file.into_vec::<f64>().unwrap())
+        };
+
+        for (index, (size, previous_size)) in [(4, 5), (3, 4)].into_iter().enumerate()
+        {
+            let (weights, biases) = network.weight_matrix(index);
+
+            let (shape, loaded) = read(&format!("layer{index}_weights.npy"));
+            assert_eq!(shape, vec![size, previous_size]);
+            assert_eq!(loaded, weights.into_iter().flatten().collect::<Vec<f64>>());
+
+            let (shape, loaded) = read(&format!("layer{index}_bias.npy"));
+            assert_eq!(shape, vec![size]);
+            assert_eq!(loaded, biases);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn checksummed_files()
+    {
+        let layers = [DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}];
+        let network = NeuralNet::create(4, &layers);
+
+        let path = std::env::temp_dir().join("digitsrecog_checksummed.nn");
+        let path = path.to_str().unwrap();
+
+        network.save(path).unwrap();
+
+        let mut data = fs::read(path).unwrap();
+        let middle = data.len() / 2;
+        data[middle] ^= 1;
+        fs::write(path, &data).unwrap();
+
+        assert!(matches!(NeuralNet::load(path), Err(LoadError::Corrupt)));
+
+        //the old format was the cbor without anything around it
+        let mut old = Vec::new();
+        ciborium::ser::into_writer(&network, &mut old).unwrap();
+        fs::write(path, old).unwrap();
+
+        assert!(matches!(NeuralNet::load(path), Err(LoadError::Unchecksummed)));
+        let loaded = NeuralNet::load_unchecksummed(path).unwrap();
+
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.layer_weights(0), network.layer_weights(0));
+    }
+
+    #[test]
+    fn training_info()
+    {
+        let layers = [DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}];
+        let mut network = NeuralNet::create(4, &layers);
+
+        assert_eq!(*network.training_info(), TrainingInfo::default());
+
+        let sample = TrainSample::new(vec![0.5; 4], vec![1.0, 0.0, 0.0]);
+        network.backpropagate(&[sample]);
+        network.record_training(1, 1);
+        network.record_training(5, 32);
+
+        let path = std::env::temp_dir().join("digitsrecog_training_info.nn");
+        let path = path.to_str().unwrap();
+
+        network.save(path).unwrap();
+        let loaded = NeuralNet::load(path).unwrap();
+
+        std::fs::remove_file(path).unwrap();
+        assert!(!std::path::Path::new(&format!("{path}.tmp")).exists());
+
+        let info = loaded.training_info();
+        assert_eq!(info.iterations, 6);
+        assert_eq!(info.last_batch_size, Some(32));
+
+        let (lowest, highest) = info.learning_rate_bounds.unwrap();
+        assert!(lowest<=highest && lowest>0.0);
+    }
+
+    #[test]
+    fn neuron_values_lifecycle()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+
+        let sample = TrainSample::new(vec![0.3; 5], vec![1.0, 0.0, 0.0]);
+
+        network.feedforward(&sample.inputs);
+        let pre_activations = network.layer_pre_activations(1).unwrap().to_vec();
+        assert!(network.layer_deltas(1).is_none());
+
+        let outputs = network.feedforward(&sample.inputs);
+        assert_eq!(
+            pre_activations.iter().map(|n| TransferFunction::Sigmoid.t_f(*n)).collect::<Vec<_>>(),
+            outputs
+        );
+
+        network.backpropagate_nonapply(slice::from_ref(&sample));
+        for layer in 0..layers.len()
+        {
+            assert!(network.layer_pre_activations(layer).is_none());
+            assert!(network.layer_deltas(layer).is_some());
+        }
+
+        //the output delta is the loss derivative times the transfer derivative
+        let output = outputs[0];
+        let delta = network.layer_deltas(1).unwrap()[0];
+        let expected = (output - 1.0) * TransferFunction::Sigmoid.dt_f(pre_activations[0]);
+        assert!((delta - expected).abs() < 1e-12);
+
+        network.feedforward(&sample.inputs);
+        assert_eq!(network.layer_pre_activations(1).unwrap(), pre_activations);
+    }
+
+    #[test]
+    fn zero_head()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 6, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+        network.zero_head();
+        network.set_optimizer(Optimizer::Sgd{learning_rate: 0.5, momentum: 0.0});
+
+        let sample = TrainSample::new(vec![0.2, 0.9, 0.4, 0.0, 0.7], vec![0.0, 1.0, 0.0, 0.0]);
+
+        assert_eq!(network.feedforward(&sample.inputs), vec![0.5; 4]);
+
+        for _ in 0..20
+        {
+            network.backpropagate(slice::from_ref(&sample));
+        }
+
+        let outputs = network.feedforward(&sample.inputs);
+        assert_eq!(argmax(&outputs), 1);
+
+        //the gradients reached the hidden layer once the head wasnt zero anymore
+        let hidden = network.layer_weights(0).to_vec();
+        network.backpropagate(slice::from_ref(&sample));
+        assert_ne!(network.layer_weights(0), hidden);
+    }
+
+    #[test]
+    fn probe()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 6, transfer_function: TransferFunction::Relu},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create_seeded(4, &layers, 2);
+
+        let inputs = [0.3, 0.0, 0.8, 0.5];
+        let (logits, outputs) = network.probe(&inputs);
+
+        let transferred = logits.iter().map(|logit| TransferFunction::Sigmoid.t_f(*logit))
+            .collect::<Vec<f64>>();
+
+        assert_eq!(transferred, outputs);
+        assert_eq!(logits, network.logits(&inputs));
+        assert_eq!(outputs, network.feedforward(&inputs));
+    }
+
+    #[test]
+    fn dead_neurons_stay()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Relu},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create_seeded(5, &layers, 5);
+
+        let mut weights = network.layer_weights(0).to_vec();
+        *weights[0].last_mut().unwrap() = -1000.0;
+        network.set_layer_weights(0, weights).unwrap();
+
+        let before = network.layer_weights(0).to_vec();
+
+        let samples = (0..4).map(|i|
+        {
+            TrainSample::new(vec![0.1 * i as f64; 5], vec![1.0, 0.0, 0.0])
+        }).collect::<Vec<TrainSample>>();
+
+        network.backpropagate(&samples);
+        network.backpropagate_multithreaded(&samples, 2);
+
+        let after = network.layer_weights(0);
+        assert_eq!(after[0], before[0]);
+        assert!(after.iter().zip(before.iter()).skip(1).any(|(after, before)| after!=before));
+
+        for previous in 0..6
+        {
+            assert_eq!(*get_learning_rate(&mut network.layers[0], 0, previous), 0.1);
+        }
+    }
+
+    #[test]
+    fn weight_histogram()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let network = NeuralNet::create_seeded(5, &layers, 4);
+
+        let histograms = network.weight_histogram(8);
+        assert_eq!(histograms.len(), 2);
+
+        for (index, (histogram, (size, previous_size))) in histograms.iter()
+            .zip([(4, 5), (3, 4)])
+            .enumerate()
+        {
+            assert_eq!(histogram.weights.counts.len(), 8);
+            assert_eq!(histogram.weights.total(), size * previous_size);
+            assert_eq!(histogram.biases.total(), size);
+
+            let (weights, biases) = network.weight_matrix(index);
+            let lowest = weights.iter().flatten().copied().fold(f64::INFINITY, f64::min);
+
+            assert_eq!(histogram.weights.low, lowest);
+            assert_eq!(histogram.biases.high, biases.iter().copied().fold(f64::NEG_INFINITY, f64::max));
+        }
+    }
+
+    #[test]
+    fn learning_rate_stats()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create_seeded(5, &layers, 3);
+
+        for stats in network.learning_rate_stats()
+        {
+            assert_eq!((stats.min, stats.max), (0.1, 0.1));
+            assert!((stats.mean - 0.1).abs() < 1e-12);
+        }
+
+        //the rprop ceiling is below the initial rate, start low so theres room to grow
+        let start = 0.001;
+        let indices = network.layers.iter().enumerate().flat_map(|(i_layer, layer)|
+        {
+            layer.weights().iter().enumerate().flat_map(move |(neuron, weights)|
+            {
+                (0..weights.len()).map(move |previous| (i_layer, neuron, previous))
+            })
+        }).collect::<Vec<_>>();
+
+        for &(i_layer, neuron, previous) in indices.iter()
+        {
+            *get_learning_rate(&mut network.layers[i_layer], neuron, previous) = start;
+        }
+
+        //same gradient sign every step, the first step can halve rates whose sign flipped
+        for _ in 0..10
+        {
+            for &(i_layer, neuron, previous) in indices.iter()
+            {
+                *get_gradient(&mut network.layers[i_layer], neuron, previous) = 1.0;
+            }
+
+            network.apply_gradients();
+        }
+
+        for stats in network.learning_rate_stats()
+        {
+            assert!(stats.min>start, "{stats:?}");
+            assert!(stats.min<=stats.mean && stats.mean<=stats.max, "{stats:?}");
+            assert!(stats.max<=0.01, "{stats:?}");
+        }
+    }
+
+    #[test]
+    fn inspect()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 6, transfer_function: TransferFunction::Relu},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let network = NeuralNet::create(5, &layers);
+
+        let path = std::env::temp_dir().join("digitsrecog_inspect.nn");
+        let path = path.to_str().unwrap();
+
+        network.save(path).unwrap();
+        let info = NeuralNet::inspect(path).unwrap();
+
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(info.inputs, 5);
+        assert_eq!(info.layer_sizes, vec![6, 3]);
+        assert!(matches!(
+            info.transfer_functions.as_slice(),
+            [TransferFunction::Relu, TransferFunction::Sigmoid]
+        ));
+        assert_eq!(info.total_params, network.parameters_amount());
+    }
+
+    #[test]
+    fn frozen_layers()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+        network.set_frozen(0, true);
+
+        let original = network.clone();
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let samples = random_samples(&mut rng, 10, 5, 3);
+
+        for _ in 0..5
+        {
+            network.backpropagate(&samples);
+        }
+
+        assert_eq!(network.layers[0].weights(), original.layers[0].weights());
+        assert_ne!(network.layers[1].weights(), original.layers[1].weights());
+        assert_ne!(network.layers[2].weights(), original.layers[2].weights());
+    }
+
+    #[test]
+    fn feedforward_pure()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 6, transfer_function: TransferFunction::Relu},
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+        let mut scratch = network.scratch();
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..10
+        {
+            let inputs = (0..5).map(|_| rng.gen()).collect::<Vec<f64>>();
+
+            assert_eq!(network.feedforward_pure(&inputs, &mut scratch), network.feedforward(&inputs));
+        }
+    }
+
+    #[test]
+    fn seeded_create()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+
+        let network = NeuralNet::create_seeded(5, &layers, 1234);
+        let same = NeuralNet::create_seeded(5, &layers, 1234);
+        let other = NeuralNet::create_seeded(5, &layers, 4321);
+
+        for (layer, (same_layer, other_layer)) in network.layers.iter()
+            .zip(same.layers.iter().zip(other.layers.iter()))
+        {
+            assert_eq!(layer.weights(), same_layer.weights());
+            assert_ne!(layer.weights(), other_layer.weights());
+        }
+    }
+
+    #[test]
+    fn activation_stats()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 8, transfer_function: TransferFunction::Relu},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+
+        //kill half the relus
+        for neuron in 0..4
+        {
+            for previous in 0..6
+            {
+                *get_weight(&mut network.layers[0], neuron, previous) = -1.0;
+            }
+        }
+
+        network.track_activations();
+
+        let mut rng = StdRng::seed_from_u64(6);
+        let samples = random_samples(&mut rng, 30, 5, 3);
+
+        network.backpropagate_multithreaded_nonapply(&samples, 3);
+
+        let stats = network.activation_stats().unwrap();
+
+        assert!(stats.dead_fraction(0)>=0.5);
+        assert!(stats.inactive_fraction(0)>=0.5);
+    }
+
+    #[test]
+    fn reset_adaptive()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+        let initial = *get_learning_rate(&mut network.layers[0], 0, 0);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let samples = random_samples(&mut rng, 10, 5, 3);
+
+        for _ in 0..5
+        {
+            network.backpropagate(&samples);
+        }
+
+        network.reset_adaptive();
+
+        for layer in network.layers.iter_mut()
+        {
+            let shape = layer.weights().iter().map(|weights| weights.len()).collect::<Vec<_>>();
+            for (neuron, previous_amount) in shape.into_iter().enumerate()
+            {
+                for previous in 0..previous_amount
+                {
+                    assert_eq!(*get_learning_rate(layer, neuron, previous), initial);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_weights()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+        let mut other = NeuralNet::create(5, &layers);
 
+        let inputs = [0.1, 0.2, 0.3, 0.4, 0.5];
+        assert_ne!(network.feedforward(&inputs), other.feedforward(&inputs));
 
-mod layer;
+        let initial = *get_learning_rate(&mut other.layers[1], 0, 0);
+        *get_learning_rate(&mut other.layers[1], 0, 0) = 0.5;
 
+        for layer in 0..network.layers_amount()
+        {
+            let weights = network.layer_weights(layer).to_vec();
+            other.set_layer_weights(layer, weights).unwrap();
+        }
 
-#[derive(Debug, Clone)]
-pub struct TrainSample
-{
-    pub inputs: Vec<f64>,
-    pub outputs: Vec<f64>
-}
+        assert_eq!(network.feedforward(&inputs), other.feedforward(&inputs));
+        assert_eq!(*get_learning_rate(&mut other.layers[1], 0, 0), initial);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NeuralNet
-{
-    inputs_amount: usize,
-    layers: Vec<DefaultLayer>,
-}
+        let wrong = vec![vec![0.0; 4]; 3];
+        assert_eq!(
+            other.set_layer_weights(1, wrong),
+            Err(ShapeError{expected: vec![5; 3], found: vec![4; 3]})
+        );
+    }
 
-#[allow(dead_code)]
-impl NeuralNet
-{
-    pub fn create(
-        inputs_amount: usize,
-        layers: &[DefaultLayerSettings],
-    ) -> Self
+    #[test]
+    fn floored_learning_rates()
     {
-        assert!(!layers.is_empty());
-
-        let layers = layers.iter().cloned().enumerate().map(|(i, layer)|
-            {
-                let DefaultLayerSettings{size, transfer_function} = layer;
+        let layers = [
+            DefaultLayerSettings{size: 2, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 2, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(1, &layers);
 
-                let prev_size = if i==0
-                {
-                    inputs_amount
-                } else
-                {
-                    layers[i-1].size
-                };
+        assert_eq!(network.floored_fraction(), 0.0);
 
-                DefaultLayer::new(size, prev_size, transfer_function)
-            }).collect::<Vec<DefaultLayer>>();
+        //flipping the gradient sign halves the learning rate every other step
+        for i in 0..60
+        {
+            network.layers.iter_mut().for_each(|layer|
+            {
+                layer.weights().iter().map(|weights| weights.len()).collect::<Vec<usize>>()
+                    .into_iter().enumerate().for_each(|(neuron, previous_amount)|
+                    {
+                        for previous in 0..previous_amount
+                        {
+                            *get_gradient(layer, neuron, previous) = if i%2==0 {1.0} else {-1.0};
+                        }
+                    });
+            });
 
-        NeuralNet{
-            inputs_amount,
-            layers
+            network.apply_gradients();
         }
+
+        assert_eq!(network.floored_fraction(), 1.0);
     }
 
-    pub fn load(filename: &str) -> Result<Self, ciborium::de::Error<io::Error>>
+    #[test]
+    fn feedforward_sparse()
     {
-        let mut net = ciborium::de::from_reader::<Self, _>(File::open(filename)
-            .map_err(|err| ciborium::de::Error::Io(err))?)?;
+        let layers = [
+            DefaultLayerSettings{size: 6, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(10, &layers);
 
-        net.layers.iter_mut().for_each(|layer| layer.reset_temporary());
+        let mut rng = rand::thread_rng();
+        let inputs = (0..10).map(|_| if rng.gen_bool(0.3) {rng.gen()} else {0.0})
+            .collect::<Vec<f64>>();
 
-        Ok(net)
+        let (indices, values): (Vec<usize>, Vec<f64>) = inputs.iter().enumerate()
+            .filter(|(_, value)| **value!=0.0)
+            .unzip();
+
+        assert_eq!(network.feedforward_sparse(&indices, &values), network.feedforward(&inputs));
     }
 
-    pub fn save(&self, filename: &str) -> Result<(), ciborium::ser::Error<io::Error>>
+    #[test]
+    #[should_panic(expected = "layer 1 has no neurons")]
+    fn empty_layer()
     {
-        ciborium::ser::into_writer(&self, File::create(filename)
-            .map_err(|err| ciborium::ser::Error::Io(err))?)
+        let layers = [
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 0, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+
+        NeuralNet::create(5, &layers);
     }
 
-    pub fn feedforward(&mut self, inputs: &[f64]) -> Vec<f64>
+    #[test]
+    #[should_panic(expected = "network needs at least one input")]
+    fn no_inputs()
     {
-        self.feedforward_inner(inputs);
+        let layers = [DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}];
 
-        let last_layer = self.layers.last().unwrap();
-        
-        let transfer_function = last_layer.transfer_function();
-        last_layer.neurons().iter().map(|n| transfer_function.t_f(*n)).collect::<Vec<f64>>()
+        NeuralNet::create(0, &layers);
     }
 
-    fn feedforward_inner(&mut self, inputs: &[f64])
+    #[test]
+    fn train_one()
     {
-        for layer in 0..self.layers.len()
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 2, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(3, &layers);
+        network.set_optimizer(Optimizer::Sgd{learning_rate: 0.1, momentum: 0.0});
+
+        let sample = TrainSample::new(vec![0.2, 0.5, 0.9], vec![1.0, 0.0]);
+
+        let error = |network: &mut NeuralNet|
         {
-            if layer==0
-            {
-                let c_layer = unsafe{ self.layers.get_unchecked_mut(0) };
-                c_layer.feedforward(&inputs, TransferFunction::Nothing);
-            } else
-            {
-                let ptr = self.layers.as_mut_ptr();
-                let previous_layer = unsafe{ ptr.add(layer-1) };
-                let current_layer = unsafe{ ptr.add(layer) };
+            network.feedforward(&sample.inputs).iter().zip(sample.outputs.iter())
+                .map(|(output, correct)| (output - correct).abs())
+                .sum::<f64>()
+        };
 
-                unsafe
-                {
-                (*current_layer).feedforward((*previous_layer).neurons(),
-                    (*previous_layer).transfer_function());
-                }
-            }
+        let before = error(&mut network);
+        for _ in 0..50
+        {
+            network.train_one(&sample);
         }
+
+        assert!(error(&mut network) < before);
+    }
+
+    #[test]
+    fn dot_graph()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 2, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let network = NeuralNet::create(3, &layers);
+
+        let edges = |dot: &str| dot.lines().filter(|line| line.contains("->")).count();
+
+        let dot = network.to_dot(0.0).unwrap();
+        assert!(dot.starts_with("digraph"));
+        assert_eq!(edges(&dot), 3 * 4 + 4 * 2);
+        assert_eq!(dot.matches("subgraph").count(), 3);
+
+        assert_eq!(edges(&network.to_dot(f64::MAX).unwrap()), 0);
+    }
+
+    #[test]
+    fn dump_weights_text()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 2, transfer_function: TransferFunction::Relu},
+            DefaultLayerSettings{size: 1, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(3, &layers);
+
+        network.set_layer_weights(1, vec![vec![0.5, -1.25, 0.1]]).unwrap();
+
+        let text = network.dump_weights_text().unwrap();
+        let lines = text.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines.len(), 2 + 3);
+        assert_eq!(lines[0], "layer 0 (Relu, 3 inputs, 2 neurons)");
+        assert_eq!(lines[3], "layer 1 (Sigmoid, 2 inputs, 1 neurons)");
+        assert_eq!(lines[4], "    neuron 0:    0.5000   -1.2500 | bias    0.1000");
     }
 
-    pub fn backpropagate_multithreaded(&mut self, mut samples: &[TrainSample], threads: usize)
+    #[test]
+    fn input_gradient()
     {
-        thread::scope(|scope|
+        const EPSILON: f64 = 1e-6;
+
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create_seeded(5, &layers, 3);
+
+        let inputs = [0.1, 0.9, 0.3, 0.0, 0.5];
+        let weights_before = network.layer_weights(0).to_vec();
+
+        for class in 0..3
         {
-            let mut handles = Vec::new();
+            let gradient = network.input_gradient(&inputs, class);
+            assert_eq!(gradient.len(), inputs.len());
 
-            if samples.len()>=threads
+            for (index, gradient) in gradient.into_iter().enumerate()
             {
-                let samples_per_thread = samples.len()/threads;
-
-                for _ in 0..threads-1
+                let mut shifted = |shift: f64|
                 {
-                    let current_samples;
-                    (current_samples, samples) = samples.split_at(samples_per_thread);
+                    let mut inputs = inputs;
+                    inputs[index] += shift;
 
-                    let mut network_copy = self.clone();
+                    network.feedforward(&inputs)[class]
+                };
 
-                    handles.push(scope.spawn(move ||
-                    {
-                        network_copy.backpropagate_nonapply(current_samples);
-                        network_copy
-                    }));
-                }
+                let numeric = (shifted(EPSILON) - shifted(-EPSILON)) / (2.0 * EPSILON);
+                assert!((numeric - gradient).abs() < 1e-6, "{numeric} vs {gradient}");
             }
+        }
 
-            self.backpropagate_nonapply(samples);
+        assert_eq!(network.layer_weights(0), weights_before.as_slice());
+    }
 
-            for handle in handles
-            {
-                self.combine(&handle.join().unwrap());
-            }
-        });
+    #[test]
+    fn layer_learning_rates()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
 
-        self.apply_gradients();
+        network.set_learning_rates(0, 0.05);
+
+        let stats = network.learning_rate_stats();
+        assert_eq!((stats[0].min, stats[0].max), (0.05, 0.05));
+        assert!((stats[0].mean - 0.05).abs() < 1e-12);
+
+        //the other layer keeps the default
+        let default = stats[1].max;
+        assert_ne!(default, 0.05);
+        assert_eq!(stats[1].min, default);
+
+        network.reset_adaptive();
+        assert_eq!(network.learning_rate_stats()[0].max, default);
     }
 
-    pub fn backpropagate(&mut self, samples: &[TrainSample])
+    #[test]
+    fn inference()
     {
-        self.backpropagate_nonapply(samples);
-        self.apply_gradients();
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Relu},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+
+        let inputs = [0.1, 0.9, 0.3, 0.0, 0.5];
+        assert_eq!(network.inference().unwrap().feedforward(&inputs), network.feedforward(&inputs));
+
+        let mismatched = vec![InferenceLayer::new(vec![vec![0.0; 5]], TransferFunction::Tanh)];
+        assert!(InferenceNet::new(5, mismatched).is_none());
     }
 
-    fn backpropagate_nonapply(&mut self, samples: &[TrainSample])
+    #[test]
+    fn prune()
     {
-        for sample in samples
+        let layers = [
+            DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let network = NeuralNet::create(9, &layers);
+
+        let biases = |network: &NeuralNet|
         {
-            self.feedforward_inner(&sample.inputs);
-            self.backpropagate_inner(&sample.inputs, &sample.outputs);
-        }
+            network.layers.iter().flat_map(|layer|
+            {
+                layer.weights().iter().map(|weights| *weights.last().unwrap())
+            }).collect::<Vec<f64>>()
+        };
+
+        let mut pruned = network.clone();
+        let sparsity = pruned.prune_fraction(0.5);
+        assert!((sparsity - 0.5).abs() < 0.01, "{sparsity}");
+        assert_eq!(biases(&pruned), biases(&network));
+
+        let mut pruned = network.clone();
+        assert_eq!(pruned.prune(0.0), 0.0);
+        assert_eq!(pruned.prune(f64::INFINITY), 1.0);
+        assert_eq!(biases(&pruned), biases(&network));
     }
 
-    fn apply_gradients(&mut self)
+    #[test]
+    fn weight_matrix()
     {
-        self.layers.iter_mut().for_each(|layer|
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let network = NeuralNet::create(5, &layers);
+
+        for layer in 0..network.layers_amount()
         {
-            layer.apply_gradients();
-        });
+            let (matrix, biases) = network.weight_matrix(layer);
+
+            assert_eq!(matrix.len(), biases.len());
+
+            let rows = matrix.into_iter().zip(biases).map(|(mut row, bias)|
+            {
+                row.push(bias);
+                row
+            }).collect::<Vec<Vec<f64>>>();
+
+            assert_eq!(rows, network.layer_weights(layer));
+        }
     }
 
-    fn combine(&mut self, other: &NeuralNet)
+    #[test]
+    fn custom_transfer()
     {
-        self.layers.iter_mut().zip(other.layers.iter()).for_each(|(layer, other_layer)|
-        {
-            layer.combine(other_layer);
-        });
+        let softsign = register_transfer(|n| n / (1.0 + n.abs()), |n| 1.0 / (1.0 + n.abs()).powi(2));
+
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Custom(softsign)},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+
+        let sample = TrainSample::new(vec![0.3, -0.2, 0.9, 0.5, 0.1], vec![1.0, 0.0, 0.0]);
+
+        let check = network.gradient_check(&sample, 0.00001);
+        assert!(check.max_error<0.001, "{check:?}");
+
+        let path = std::env::temp_dir().join("digitsrecog_custom_transfer.nn");
+        network.save(path.to_str().unwrap()).unwrap();
+
+        let mut loaded = NeuralNet::load(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.feedforward(&sample.inputs), network.feedforward(&sample.inputs));
     }
 
-    fn backpropagate_inner(&mut self, inputs: &[f64], outputs: &[f64])
+    #[test]
+    fn summary()
     {
-        for layer in (0..self.layers.len()).rev()
+        let layers = [
+            DefaultLayerSettings{size: 7, transfer_function: TransferFunction::Relu},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let network = NeuralNet::create(5, &layers);
+
+        let weights_amount = network.layers.iter()
+            .map(|layer| layer.weights().iter().flatten().count())
+            .sum::<usize>();
+
+        assert_eq!(network.parameters_amount(), weights_amount);
+        assert_eq!(network.parameters_amount(), 7 * 6 + 3 * 8);
+
+        let summary = network.to_string();
+        assert!(summary.contains("inputs: 5"));
+        assert!(summary.ends_with(&format!("total parameters: {weights_amount}")));
+    }
+
+    #[test]
+    fn weighted_sample()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 4, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 3, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(5, &layers);
+
+        let mut rng = StdRng::seed_from_u64(8);
+        let sample = random_samples(&mut rng, 1, 5, 3).remove(0);
+
+        let gradients = |network: &mut NeuralNet, weight: f64|
         {
-            let last_layer = self.layers.len()-1;
+            network.backpropagate_nonapply(&[TrainSample{weight, ..sample.clone()}]);
 
-            let ptr = self.layers.as_mut_ptr();
+            let gradients = all_gradients(network);
 
-            let previous_layer = if layer==0
-            {
-                inputs.to_vec()
-            } else
-            {
-                let prev_layer = unsafe{ ptr.add(layer-1) };
-                let tf = unsafe{ (*prev_layer).transfer_function() };
-                unsafe
-                {
-                (*prev_layer).neurons().iter().map(|neuron| tf.t_f(*neuron)).collect::<Vec<f64>>()
-                }
-            };
+            network.layers.iter_mut().for_each(|layer| layer.reset_temporary());
 
-            if layer==last_layer
-            {
-                unsafe
-                {
-                    (*ptr.add(layer)).backpropagate(&previous_layer, InnerOuter::Outputs(outputs));
-                }
-            } else
-            {
-                let current_layer = unsafe{ ptr.add(layer) };
-                let next_layer = unsafe{ ptr.add(layer+1) };
+            gradients
+        };
 
-                let inners;
-                unsafe
-                {
-                inners = InnerOuter::Inners(
-                    (*next_layer).neurons(),
-                    (*next_layer).weights()
-                );
-                }
+        let normal = gradients(&mut network, 1.0);
+        let doubled = gradients(&mut network, 2.0);
 
-                unsafe
-                {
-                (*current_layer).backpropagate(&previous_layer, inners);
-                }
-            }
+        for (normal, doubled) in normal.iter().zip(doubled.iter())
+        {
+            assert_eq!(normal * 2.0, *doubled);
         }
     }
-}
 
-#[cfg(test)]
-mod tests
-{
-    use super::*;
-    use layer::tests::{get_weight, get_gradient};
+    #[test]
+    fn it_learns()
+    {
+        learns(Optimizer::default());
+    }
 
     #[test]
-    fn backprop()
+    fn it_learns_sgd_momentum()
     {
-        for _ in 0..10
-        {
-            backprop_single();
-        }
+        learns(Optimizer::Sgd{learning_rate: 0.5, momentum: 0.5});
     }
 
-    fn backprop_single()
+    //catches broken gradient directions which would leave the output shapes alone
+    #[test]
+    fn learning_curve()
     {
-        let mut rng = rand::thread_rng();
+        const RECORD_EVERY: usize = 25;
 
-        let layers_amount = rng.gen_range(1..10);
-        let layers = (0..layers_amount).map(|_|
+        let layers = [
+            DefaultLayerSettings{size: 8, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 2, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create_seeded(4, &layers, 7);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut batch = ||
+        {
+            (0..20).map(|_|
             {
-                let transfer_functions = [TransferFunction::Sigmoid];
-                let t_index = rng.gen_range(0..transfer_functions.len());
+                let inputs = (0..4).map(|_| rng.gen::<f64>()).collect::<Vec<f64>>();
+                let label = usize::from(inputs[0] + inputs[1] > inputs[2] + inputs[3]);
 
-                let transfer_function = transfer_functions[t_index];
+                TrainSample::new(inputs, (0..2).map(|i| if i==label {1.0} else {0.0}).collect())
+            }).collect::<Vec<TrainSample>>()
+        };
 
-                let size = rng.gen_range(1..10);
-                DefaultLayerSettings{size, transfer_function}
-            }).collect::<Vec<DefaultLayerSettings>>();
-    
-        let inputs_amount = rng.gen_range(1..10);
-        let mut network = NeuralNet::create(inputs_amount, &layers);
+        let mut curve = Vec::new();
+        for iteration in 0..600
+        {
+            let samples = batch();
 
-        let change = 0.1;
+            if iteration % RECORD_EVERY == 0
+            {
+                curve.push(mean_loss(&mut network, &samples));
+            }
 
-        for t_l in 0..layers_amount
-        {
-            for t_n in 0..network.layers[t_l].size()
+            network.backpropagate(&samples);
+        }
+
+        let (initial, last) = (curve[0], *curve.last().unwrap());
+        assert!(last < initial * 0.5, "loss went from {initial} to {last}, curve {curve:?}");
+
+        //every point of the second half has to be below the start, not just the last one
+        assert!(curve[curve.len() / 2..].iter().all(|loss| *loss < initial), "curve {curve:?}");
+    }
+
+    #[test]
+    fn rprop_variants()
+    {
+        const CLASSES: usize = 4;
+
+        let samples = crate::digiter::generate_dataset(5, 120, CLASSES, 8, 8)
+            .map(|(label, image)|
             {
-                let previous_amount = if t_l==0
-                {
-                    inputs_amount
-                } else
-                {
-                    network.layers[t_l-1].size()
-                };
-                for t_p in 0..(previous_amount+1)
-                {
-                    let test_input = (0..inputs_amount).map(|_| rng.gen())
-                        .collect::<Vec<f64>>();
+                TrainSample::new(
+                    image.iter().map(|pixel| *pixel as f64 / 255.0).collect(),
+                    (0..CLASSES).map(|i| if i==label as usize {1.0} else {0.0}).collect()
+                )
+            }).collect::<Vec<TrainSample>>();
 
-                    dbg!(t_l, t_n, t_p, &test_input);
-                    let normal_weight = *get_weight(&mut network.layers[t_l], t_n, t_p);
+        let layers = [
+            DefaultLayerSettings{size: 8, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: CLASSES, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let initial = NeuralNet::create_seeded(64, &layers, 3);
 
-                    *get_weight(&mut network.layers[t_l], t_n, t_p) = normal_weight + change;
-                    let output = network.feedforward(&test_input);
-                    let left = output.into_iter().sum::<f64>();
+        let train = |variant: RpropVariant|
+        {
+            let mut network = initial.clone();
+            network.set_optimizer(Optimizer::Rprop{variant});
 
-                    *get_weight(&mut network.layers[t_l], t_n, t_p) = normal_weight - change;
-                    let output = network.feedforward(&test_input);
-                    let right = output.into_iter().sum::<f64>();
+            for _ in 0..150
+            {
+                network.backpropagate(&samples);
+            }
 
-                    *get_weight(&mut network.layers[t_l], t_n, t_p) = normal_weight;
+            network
+        };
 
-                    network.feedforward_inner(&test_input);
+        let start_loss = mean_loss(&mut initial.clone(), &samples);
 
-                    let answers_layer = network.layers.last().unwrap();
-                    
-                    let tf = answers_layer.transfer_function();
-                    let answers = answers_layer.neurons();
-                    
-                    //set correct outputs so the error is always 1.0
-                    let test_output = answers.iter().map(|current|
-                    {
-                        tf.t_f(*current) - 1.0
-                    }).collect::<Vec<f64>>();
+        let mut minus = train(RpropVariant::IRpropMinus);
+        let mut plus = train(RpropVariant::RpropPlus);
 
-                    network.feedforward_inner(&test_input);
-                    network.backpropagate_inner(&test_input, &test_output);
+        let minus_loss = mean_loss(&mut minus, &samples);
+        let plus_loss = mean_loss(&mut plus, &samples);
+        assert!(minus_loss < start_loss * 0.01, "irprop- went from {start_loss} to {minus_loss}");
+        assert!(plus_loss < start_loss * 0.01, "rprop+ went from {start_loss} to {plus_loss}");
 
-                    let deriv = *get_gradient(&mut network.layers[t_l], t_n, t_p);
-                    let real_deriv = (left - right) / (2.0 * change);
+        //backtracking has to have happened somewhere for the paths to split
+        let weights = |network: &NeuralNet|
+        {
+            network.layers.iter().flat_map(|layer| layer.weights().concat()).collect::<Vec<f64>>()
+        };
+        assert_ne!(weights(&minus), weights(&plus));
 
-                    for c_l in 0..network.layers.len()
-                    {
-                        for c_n in 0..network.layers[c_l].size()
-                        {
-                            let previous_amount = if c_l==0
-                            {
-                                inputs_amount
-                            } else
-                            {
-                                network.layers[c_l-1].size()
-                            };
-
-                            for c_p in 0..(previous_amount+1)
-                            {
-                                *get_gradient(&mut network.layers[c_l], c_n, c_p) = 0.0;
-                            }
-                        }
-                    }
+        //the saved rprop state has to continue the training exactly where it left off
+        let path = std::env::temp_dir().join("digitsrecog_rprop_variants.nn");
+        let path = path.to_str().unwrap();
+        plus.save(path).unwrap();
 
-                    dbg!(&network);
-                    println!("left: {left}, right: {right}");
-                    print!("(layer: {t_l} neuron: {t_n} previous: {t_p})  ");
-                    println!("backprop: {deriv}, derivative: {real_deriv}");
-                    println!("diff: {}", deriv-real_deriv);
+        let mut loaded = NeuralNet::load(path).unwrap();
+        loaded.set_optimizer(Optimizer::Rprop{variant: RpropVariant::RpropPlus});
 
-                    assert!((deriv-real_deriv).abs()<0.001);
-                }
-            }
+        for _ in 0..5
+        {
+            plus.backpropagate(&samples);
+            loaded.backpropagate(&samples);
         }
+        assert_eq!(weights(&plus), weights(&loaded));
+
+        fs::remove_file(path).unwrap();
     }
 
-    #[test]
-    fn it_learns()
+    fn learns(optimizer: Optimizer)
     {
         let layers = [
             DefaultLayerSettings{size: 2, transfer_function: TransferFunction::Sigmoid2},
             DefaultLayerSettings{size: 2, transfer_function: TransferFunction::Sigmoid2},
             DefaultLayerSettings{size: 1, transfer_function: TransferFunction::Sigmoid}
         ];
-        let network = std::cell::RefCell::new(NeuralNet::create(2, &layers));
+        //seeded so a bad initialization cant make it flaky
+        let network = std::cell::RefCell::new(NeuralNet::create_seeded(2, &layers, 13));
+        network.borrow_mut().set_optimizer(optimizer);
     
-        let mut rng = rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(14);
         let mut gen_sample = |out: usize| -> TrainSample
         {
             let first = rng.gen::<f64>()*0.5;
@@ -362,7 +2872,7 @@ mod tests
                 (rng.gen::<f64>()*first).max(0.5)
             };
     
-            TrainSample{inputs: vec![first, second], outputs: vec![out as f64]}
+            TrainSample::new(vec![first, second], vec![out as f64])
         };
     
         let test = |sample: TrainSample|
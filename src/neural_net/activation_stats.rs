@@ -0,0 +1,95 @@
+use super::*;
+
+
+//how often each neuron was dead (relu) or saturated (squashing functions)
+#[derive(Debug, Clone)]
+pub struct ActivationStats
+{
+    samples: usize,
+    inactive: Vec<Vec<usize>>,
+    transfer_functions: Vec<TransferFunction>
+}
+
+impl ActivationStats
+{
+    //fraction of samples a neuron has to be inactive for to count as dead
+    pub const DEAD_THRESHOLD: f64 = 0.99;
+
+    pub fn new(layers: &[Box<dyn Layer>]) -> Self
+    {
+        ActivationStats{
+            samples: 0,
+            inactive: layers.iter().map(|layer| vec![0; layer.size()]).collect(),
+            transfer_functions: layers.iter().map(|layer| layer.transfer_function()).collect()
+        }
+    }
+
+    //neurons are the pre-activations of every layer for a sample
+    pub fn record(&mut self, neurons: &[&[f64]])
+    {
+        self.samples += 1;
+
+        let layers = self.inactive.iter_mut().zip(neurons.iter()).zip(self.transfer_functions.iter());
+        layers.for_each(|((inactive, neurons), transfer_function)|
+        {
+            inactive.iter_mut().zip(neurons.iter()).for_each(|(inactive, neuron)|
+            {
+                if transfer_function.inactive(*neuron)
+                {
+                    *inactive += 1;
+                }
+            });
+        });
+    }
+
+    pub fn combine(&mut self, other: &ActivationStats)
+    {
+        self.samples += other.samples;
+
+        self.inactive.iter_mut().flatten().zip(other.inactive.iter().flatten())
+            .for_each(|(inactive, other)| *inactive += other);
+    }
+
+    pub fn layers_amount(&self) -> usize
+    {
+        self.inactive.len()
+    }
+
+    pub fn transfer_function(&self, layer: usize) -> TransferFunction
+    {
+        self.transfer_functions[layer]
+    }
+
+    //fraction of all the activations in a layer that were inactive
+    pub fn inactive_fraction(&self, layer: usize) -> f64
+    {
+        let inactive = &self.inactive[layer];
+
+        let total = (self.samples * inactive.len()) as f64;
+        if total==0.0
+        {
+            0.0
+        } else
+        {
+            inactive.iter().sum::<usize>() as f64 / total
+        }
+    }
+
+    //fraction of neurons in a layer that were (almost) never active
+    pub fn dead_fraction(&self, layer: usize) -> f64
+    {
+        let inactive = &self.inactive[layer];
+
+        if self.samples==0 || inactive.is_empty()
+        {
+            return 0.0;
+        }
+
+        let dead = inactive.iter().filter(|inactive|
+        {
+            **inactive as f64 / self.samples as f64 >= Self::DEAD_THRESHOLD
+        }).count();
+
+        dead as f64 / inactive.len() as f64
+    }
+}
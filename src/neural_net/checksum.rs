@@ -0,0 +1,94 @@
+use std::mem;
+
+use super::LoadError;
+
+
+//"dnn" followed by the format version, unchecksummed files start with a cbor map instead
+const MAGIC: u32 = 0x646e6e02;
+const CHECKSUM_SIZE: usize = mem::size_of::<u32>();
+
+//crc32 with the ieee polynomial, the same one as zip and png
+pub fn crc32(bytes: &[u8]) -> u32
+{
+    !bytes.iter().fold(u32::MAX, |crc, byte|
+    {
+        (0..8).fold(crc ^ *byte as u32, |crc, _|
+        {
+            if (crc & 1)==1
+            {
+                (crc >> 1) ^ 0xedb88320
+            } else
+            {
+                crc >> 1
+            }
+        })
+    })
+}
+
+//layout: magic, payload, crc32 of the payload
+pub fn seal(payload: &[u8]) -> Vec<u8>
+{
+    let mut data = Vec::with_capacity(mem::size_of::<u32>() + payload.len() + CHECKSUM_SIZE);
+
+    data.extend(MAGIC.to_be_bytes());
+    data.extend(payload);
+    data.extend(crc32(payload).to_be_bytes());
+
+    data
+}
+
+//returns the payload if the checksum matches, files without one are only let through
+//with allow_unchecksummed
+pub fn unseal(data: &[u8], allow_unchecksummed: bool) -> Result<&[u8], LoadError>
+{
+    let body = match data.strip_prefix(&MAGIC.to_be_bytes())
+    {
+        Some(body) => body,
+        None if allow_unchecksummed => return Ok(data),
+        None => return Err(LoadError::Unchecksummed)
+    };
+
+    if body.len()<CHECKSUM_SIZE
+    {
+        return Err(LoadError::Corrupt);
+    }
+
+    let (payload, checksum) = body.split_at(body.len() - CHECKSUM_SIZE);
+
+    if crc32(payload).to_be_bytes()!=checksum
+    {
+        return Err(LoadError::Corrupt);
+    }
+
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn crc32_check_value()
+    {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(crc32(&[]), 0);
+    }
+
+    #[test]
+    fn sealed_roundtrip()
+    {
+        let sealed = seal(b"payload");
+        assert_eq!(unseal(&sealed, false).unwrap(), b"payload");
+
+        let mut flipped = sealed.clone();
+        flipped[6] ^= 0x10;
+        assert!(matches!(unseal(&flipped, false), Err(LoadError::Corrupt)));
+
+        assert!(matches!(unseal(&sealed[..sealed.len()-1], false), Err(LoadError::Corrupt)));
+        assert!(matches!(unseal(&sealed[..5], false), Err(LoadError::Corrupt)));
+
+        assert!(matches!(unseal(b"payload", false), Err(LoadError::Unchecksummed)));
+        assert_eq!(unseal(b"payload", true).unwrap(), b"payload");
+    }
+}
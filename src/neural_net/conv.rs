@@ -0,0 +1,619 @@
+use std::{
+    fmt,
+    any::Any,
+    mem
+};
+
+use serde::{Serialize, Deserialize};
+use rand::Rng;
+
+use super::{Layer, SavedLayer, Parameters, Gradients, TransferFunction};
+
+
+//size of an image like layer input or output, values are stored row by row with every
+//channel of a pixel next to each other like in the dataset images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shape
+{
+    pub width: usize,
+    pub height: usize,
+    pub channels: usize
+}
+
+#[allow(dead_code)]
+impl Shape
+{
+    pub fn new(width: usize, height: usize, channels: usize) -> Self
+    {
+        Shape{width, height, channels}
+    }
+
+    //a flat input which isnt an image, like the outputs of a dense layer
+    pub fn flat(size: usize) -> Self
+    {
+        Shape{width: size, height: 1, channels: 1}
+    }
+
+    pub fn size(&self) -> usize
+    {
+        self.width * self.height * self.channels
+    }
+
+    //whether a size by size window fits inside
+    pub fn fits(&self, size: usize) -> bool
+    {
+        size<=self.width && size<=self.height
+    }
+
+    #[inline(always)]
+    fn index(&self, channel: usize, y: usize, x: usize) -> usize
+    {
+        (y * self.width + x) * self.channels + channel
+    }
+
+    //values of a single channel
+    fn channel<'a>(&self, values: &'a [f64], channel: usize) -> impl Iterator<Item=&'a f64>
+    {
+        values.iter().skip(channel).step_by(self.channels)
+    }
+}
+
+impl fmt::Display for Shape
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}x{}x{}", self.width, self.height, self.channels)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ConvSettings
+{
+    pub channels: usize,
+    pub kernel_size: usize,
+    pub stride: usize,
+    pub transfer_function: TransferFunction
+}
+
+//where the kernels of a conv layer go over its input
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(super) struct Window
+{
+    pub input: Shape,
+    pub output: Shape,
+    kernel_size: usize,
+    stride: usize
+}
+
+impl Window
+{
+    //weights of a kernel over every input channel, without the bias
+    fn kernel_length(&self) -> usize
+    {
+        self.input.channels * self.kernel_size * self.kernel_size
+    }
+
+    //calls f with the output index, the input index and the index into the kernel of
+    //every connection of an output channel, the order is the same every time
+    fn for_each_connection(&self, output_channel: usize, mut f: impl FnMut(usize, usize, usize))
+    {
+        for output_y in 0..self.output.height
+        {
+            for output_x in 0..self.output.width
+            {
+                let output_index = self.output.index(output_channel, output_y, output_x);
+
+                for input_channel in 0..self.input.channels
+                {
+                    for kernel_y in 0..self.kernel_size
+                    {
+                        for kernel_x in 0..self.kernel_size
+                        {
+                            let input_index = self.input.index(
+                                input_channel,
+                                output_y * self.stride + kernel_y,
+                                output_x * self.stride + kernel_x
+                            );
+
+                            let kernel_index = (input_channel * self.kernel_size + kernel_y)
+                                * self.kernel_size + kernel_x;
+
+                            f(output_index, input_index, kernel_index);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+//square kernels sliding over the input without padding, the outputs are left linear
+//so the transfer function is up to the layer after it (like with DefaultLayer)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvLayer
+{
+    window: Window,
+
+    //a row per output channel with its kernels over every input channel and the bias last
+    #[serde(flatten)]
+    parameters: Parameters,
+
+    transfer_function: TransferFunction,
+
+    #[serde(skip)]
+    neurons: Vec<f64>
+}
+
+#[allow(dead_code)]
+impl ConvLayer
+{
+    pub fn new(input: Shape, settings: ConvSettings) -> Self
+    {
+        Self::new_with_rng(input, settings, &mut rand::thread_rng())
+    }
+
+    pub fn new_with_rng(input: Shape, settings: ConvSettings, rng: &mut impl Rng) -> Self
+    {
+        let ConvSettings{channels, kernel_size, stride, transfer_function} = settings;
+
+        assert!(kernel_size>0 && stride>0 && channels>0, "conv layer sizes must be above 0");
+        assert!(input.fits(kernel_size), "kernel doesnt fit in the input");
+
+        let output = Shape{
+            width: (input.width - kernel_size) / stride + 1,
+            height: (input.height - kernel_size) / stride + 1,
+            channels
+        };
+
+        let window = Window{input, output, kernel_size, stride};
+
+        let weights = (0..channels).map(|_|
+        {
+            //+1 for bias
+            (0..window.kernel_length()+1).map(|_| rng.gen::<f64>() * 2.0 - 1.0)
+                .collect::<Vec<f64>>()
+        }).collect::<Vec<Vec<f64>>>();
+
+        ConvLayer{
+            window,
+            parameters: Parameters::new(weights),
+            transfer_function,
+            neurons: vec![0.0; output.size()]
+        }
+    }
+
+    pub fn input_shape(&self) -> Shape
+    {
+        self.window.input
+    }
+
+    pub fn output_shape(&self) -> Shape
+    {
+        self.window.output
+    }
+
+    pub fn kernel_size(&self) -> usize
+    {
+        self.window.kernel_size
+    }
+
+    pub fn stride(&self) -> usize
+    {
+        self.window.stride
+    }
+}
+
+impl Layer for ConvLayer
+{
+    fn clone_box(&self) -> Box<dyn Layer>
+    {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn saved(&self) -> SavedLayer<'_>
+    {
+        SavedLayer::Conv(self)
+    }
+
+    fn size(&self) -> usize
+    {
+        self.neurons.len()
+    }
+
+    fn neurons(&self) -> &[f64]
+    {
+        &self.neurons
+    }
+
+    fn transfer_function(&self) -> TransferFunction
+    {
+        self.transfer_function
+    }
+
+    fn parameters(&self) -> Option<&Parameters>
+    {
+        Some(&self.parameters)
+    }
+
+    fn parameters_mut(&mut self) -> Option<&mut Parameters>
+    {
+        Some(&mut self.parameters)
+    }
+
+    fn feedforward(&mut self, previous_neurons: &[f64], transfer_function: TransferFunction)
+    {
+        let mut neurons = mem::take(&mut self.neurons);
+        self.feedforward_into(previous_neurons, transfer_function, &mut neurons);
+
+        self.neurons = neurons;
+    }
+
+    fn feedforward_into(
+        &self,
+        previous_neurons: &[f64],
+        transfer_function: TransferFunction,
+        neurons: &mut [f64]
+    )
+    {
+        let inputs = previous_neurons.iter().map(|neuron| transfer_function.t_f(*neuron))
+            .collect::<Vec<f64>>();
+
+        let window = self.window;
+        let weights = self.parameters.weights();
+
+        neurons.iter_mut().enumerate().for_each(|(index, neuron)|
+        {
+            *neuron = weights[index % window.output.channels][window.kernel_length()];
+        });
+
+        for (channel, weights) in weights.iter().enumerate()
+        {
+            window.for_each_connection(channel, |output_index, input_index, kernel_index|
+            {
+                neurons[output_index] += weights[kernel_index] * inputs[input_index];
+            });
+        }
+    }
+
+    fn backpropagate(&self, inputs: &[f64], deltas: &[f64], gradients: &mut Gradients)
+    {
+        let window = self.window;
+
+        for channel in 0..window.output.channels
+        {
+            //adding 0 times the inputs wouldnt change the gradients
+            if window.output.channel(deltas, channel).all(|delta| *delta==0.0)
+            {
+                continue;
+            }
+
+            let gradients = gradients.row_mut(channel);
+
+            window.for_each_connection(channel, |output_index, input_index, kernel_index|
+            {
+                gradients[kernel_index] += deltas[output_index] * inputs[input_index];
+            });
+
+            gradients[window.kernel_length()] += window.output.channel(deltas, channel).sum::<f64>();
+        }
+    }
+
+    fn input_errors(&self, _inputs: &[f64], deltas: &[f64], errors: &mut [f64])
+    {
+        errors.fill(0.0);
+
+        let window = self.window;
+        for (channel, weights) in self.parameters.weights().iter().enumerate()
+        {
+            window.for_each_connection(channel, |output_index, input_index, kernel_index|
+            {
+                errors[input_index] += deltas[output_index] * weights[kernel_index];
+            });
+        }
+    }
+
+    fn reset_temporary(&mut self)
+    {
+        self.neurons = vec![0.0; self.window.output.size()];
+
+        self.parameters.reset_temporary();
+    }
+}
+
+//largest value of each non overlapping size by size window, leftover rows and columns
+//that dont fill a window are dropped, it has no weights so its outputs are the maximums
+//of the previous layers activations as is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaxPool
+{
+    input: Shape,
+    size: usize,
+    #[serde(skip)]
+    neurons: Vec<f64>
+}
+
+#[allow(dead_code)]
+impl MaxPool
+{
+    pub fn new(input: Shape, size: usize) -> Self
+    {
+        assert!(size>0 && input.fits(size), "pool doesnt fit the input");
+
+        let mut pool = MaxPool{input, size, neurons: Vec::new()};
+        pool.reset_temporary();
+
+        pool
+    }
+
+    pub(super) fn pooled(input: Shape, size: usize) -> Shape
+    {
+        Shape{
+            width: input.width / size,
+            height: input.height / size,
+            channels: input.channels
+        }
+    }
+
+    pub fn input_shape(&self) -> Shape
+    {
+        self.input
+    }
+
+    pub fn output_shape(&self) -> Shape
+    {
+        Self::pooled(self.input, self.size)
+    }
+
+    pub fn pool_size(&self) -> usize
+    {
+        self.size
+    }
+
+    //input index of the maximum of every output, the first maximum in the window wins
+    //ties like argmax
+    fn maximums<'a>(
+        &'a self,
+        inputs: &'a [f64],
+        transfer_function: TransferFunction
+    ) -> impl Iterator<Item=usize> + 'a
+    {
+        let output = self.output_shape();
+        let (input, size) = (self.input, self.size);
+
+        (0..output.channels).flat_map(move |channel|
+        {
+            (0..output.height).flat_map(move |y| (0..output.width).map(move |x| (channel, y, x)))
+        }).map(move |(channel, y, x)|
+        {
+            (0..size).flat_map(|window_y| (0..size).map(move |window_x| (window_y, window_x)))
+                .map(|(window_y, window_x)|
+                {
+                    input.index(channel, y * size + window_y, x * size + window_x)
+                }).map(|index| (index, transfer_function.t_f(inputs[index])))
+                .reduce(|highest, current| if current.1>highest.1 {current} else {highest})
+                .unwrap().0
+        })
+    }
+}
+
+impl Layer for MaxPool
+{
+    fn clone_box(&self) -> Box<dyn Layer>
+    {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn saved(&self) -> SavedLayer<'_>
+    {
+        SavedLayer::MaxPool(self)
+    }
+
+    fn size(&self) -> usize
+    {
+        self.neurons.len()
+    }
+
+    fn neurons(&self) -> &[f64]
+    {
+        &self.neurons
+    }
+
+    //the previous transfer function is already applied to the maximums
+    fn transfer_function(&self) -> TransferFunction
+    {
+        TransferFunction::Nothing
+    }
+
+    fn parameters(&self) -> Option<&Parameters>
+    {
+        None
+    }
+
+    fn parameters_mut(&mut self) -> Option<&mut Parameters>
+    {
+        None
+    }
+
+    fn feedforward(&mut self, previous_neurons: &[f64], transfer_function: TransferFunction)
+    {
+        self.neurons = self.maximums(previous_neurons, transfer_function)
+            .map(|index| transfer_function.t_f(previous_neurons[index]))
+            .collect();
+    }
+
+    fn feedforward_into(
+        &self,
+        previous_neurons: &[f64],
+        transfer_function: TransferFunction,
+        neurons: &mut [f64]
+    )
+    {
+        neurons.iter_mut().zip(self.maximums(previous_neurons, transfer_function))
+            .for_each(|(neuron, index)| *neuron = transfer_function.t_f(previous_neurons[index]));
+    }
+
+    fn backpropagate(&self, _inputs: &[f64], _deltas: &[f64], _gradients: &mut Gradients) {}
+
+    //routes each delta to the input which was the maximum of its window
+    fn input_errors(&self, inputs: &[f64], deltas: &[f64], errors: &mut [f64])
+    {
+        errors.fill(0.0);
+
+        self.maximums(inputs, TransferFunction::Nothing).zip(deltas.iter())
+            .for_each(|(maximum, delta)| errors[maximum] += delta);
+    }
+
+    fn reset_temporary(&mut self)
+    {
+        self.neurons = vec![0.0; self.output_shape().size()];
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::neural_net::{NeuralNet, LayerSettings, DefaultLayerSettings, TrainSample};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn conv(channels: usize, kernel_size: usize, stride: usize) -> ConvSettings
+    {
+        ConvSettings{channels, kernel_size, stride, transfer_function: TransferFunction::Tanh}
+    }
+
+    #[test]
+    fn shapes()
+    {
+        let conv_layer = ConvLayer::new(Shape::new(28, 28, 1), conv(6, 5, 1));
+        assert_eq!(conv_layer.output_shape(), Shape::new(24, 24, 6));
+
+        let pool = MaxPool::new(conv_layer.output_shape(), 2);
+        assert_eq!(pool.output_shape(), Shape::new(12, 12, 6));
+
+        let strided = ConvLayer::new(Shape::new(7, 9, 2), conv(4, 3, 2));
+        assert_eq!(strided.output_shape(), Shape::new(3, 4, 4));
+    }
+
+    #[test]
+    fn max_pool()
+    {
+        let inputs = [1.0, 5.0, 2.0, 2.0, 3.0, 4.0, 0.0, -1.0];
+
+        let mut pool = MaxPool::new(Shape::new(4, 2, 1), 2);
+        pool.feedforward(&inputs, TransferFunction::Nothing);
+
+        assert_eq!(pool.neurons(), [5.0, 2.0]);
+
+        let mut errors = vec![0.0; 8];
+        pool.input_errors(&inputs, &[1.0, 2.0], &mut errors);
+        assert_eq!(errors, vec![0.0, 1.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+
+        let mut outputs = vec![0.0; 2];
+        pool.feedforward_into(&inputs, TransferFunction::Nothing, &mut outputs);
+        assert_eq!(outputs, [5.0, 2.0]);
+    }
+
+    fn conv_network(seed: u64) -> NeuralNet
+    {
+        let layers = [
+            LayerSettings::Conv(conv(2, 3, 1)),
+            LayerSettings::MaxPool{size: 2},
+            LayerSettings::Conv(ConvSettings{stride: 2, ..conv(3, 2, 1)}),
+            LayerSettings::Dense(DefaultLayerSettings{
+                size: 3,
+                transfer_function: TransferFunction::Sigmoid
+            })
+        ];
+
+        NeuralNet::create_shaped_seeded(Shape::new(10, 10, 2), &layers, seed).unwrap()
+    }
+
+    #[test]
+    fn gradient_check()
+    {
+        let mut rng = StdRng::seed_from_u64(5);
+
+        let mut network = conv_network(5);
+
+        let sample = TrainSample::new(
+            (0..200).map(|_| rng.gen::<f64>()).collect::<Vec<f64>>(),
+            vec![1.0, 0.0, 0.0]
+        );
+
+        let check = network.gradient_check(&sample, 1e-6);
+        assert!(check.max_error<1e-4, "{check:?}");
+    }
+
+    #[test]
+    fn input_gradient()
+    {
+        let mut rng = StdRng::seed_from_u64(2);
+
+        let mut network = conv_network(2);
+        let inputs = (0..200).map(|_| rng.gen::<f64>()).collect::<Vec<f64>>();
+
+        let gradient = network.input_gradient(&inputs, 1);
+
+        let epsilon = 1e-6;
+        for index in 0..inputs.len()
+        {
+            let mut changed = inputs.clone();
+
+            changed[index] += epsilon;
+            let higher = network.feedforward(&changed)[1];
+
+            changed[index] -= 2.0 * epsilon;
+            let lower = network.feedforward(&changed)[1];
+
+            let numerical = (higher - lower) / (2.0 * epsilon);
+            assert!((numerical - gradient[index]).abs() < 1e-6, "input {index}");
+        }
+    }
+
+    #[test]
+    fn pure_and_saved()
+    {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let mut network = conv_network(7);
+        let inputs = (0..200).map(|_| rng.gen::<f64>()).collect::<Vec<f64>>();
+
+        let outputs = network.feedforward(&inputs);
+        assert_eq!(network.feedforward_pure(&inputs, &mut network.scratch()), outputs);
+
+        let path = std::env::temp_dir().join("digitsrecog_conv_saved.nn");
+        network.save(path.to_str().unwrap()).unwrap();
+
+        let mut loaded = NeuralNet::load(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.to_string(), network.to_string());
+        assert_eq!(loaded.feedforward(&inputs), outputs);
+    }
+
+    #[test]
+    fn too_small_input()
+    {
+        let layers = [
+            LayerSettings::Conv(conv(2, 5, 1)),
+            LayerSettings::MaxPool{size: 4},
+            LayerSettings::Dense(DefaultLayerSettings{
+                size: 2,
+                transfer_function: TransferFunction::Sigmoid
+            })
+        ];
+
+        let err = NeuralNet::create_shaped(Shape::new(6, 6, 1), &layers).unwrap_err();
+        assert_eq!(err.layer, 1);
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt;
+
+
+//longest bar when displayed
+const BAR_WIDTH: usize = 40;
+
+//counts of values in equal width bins between the smallest and the largest value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram
+{
+    pub low: f64,
+    pub high: f64,
+    pub counts: Vec<usize>
+}
+
+impl Histogram
+{
+    pub fn new(values: impl Iterator<Item=f64> + Clone, bins: usize) -> Self
+    {
+        assert!(bins>0, "histogram needs at least 1 bin");
+
+        let (low, high) = values.clone().fold((f64::INFINITY, f64::NEG_INFINITY),
+            |(low, high), value| (low.min(value), high.max(value)));
+
+        let mut counts = vec![0; bins];
+        values.for_each(|value|
+        {
+            //a single distinct value all goes into the first bin
+            let bin = if high>low
+            {
+                ((value - low) / (high - low) * bins as f64) as usize
+            } else
+            {
+                0
+            };
+
+            counts[bin.min(bins - 1)] += 1;
+        });
+
+        Histogram{low, high, counts}
+    }
+
+    pub fn total(&self) -> usize
+    {
+        self.counts.iter().sum()
+    }
+
+    //lower and upper edge of a bin
+    pub fn bin_range(&self, bin: usize) -> (f64, f64)
+    {
+        let width = (self.high - self.low) / self.counts.len() as f64;
+
+        (self.low + width * bin as f64, self.low + width * (bin + 1) as f64)
+    }
+}
+
+impl fmt::Display for Histogram
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        if self.total()==0
+        {
+            return write!(f, "    (empty)");
+        }
+
+        let precision = f.precision().unwrap_or(3);
+        let highest = self.counts.iter().copied().max().unwrap_or(0).max(1);
+
+        for (bin, count) in self.counts.iter().enumerate()
+        {
+            let (low, high) = self.bin_range(bin);
+
+            //any nonempty bin gets at least one mark so outliers stay visible
+            let length = (count * BAR_WIDTH).div_ceil(highest);
+
+            if bin>0
+            {
+                writeln!(f)?;
+            }
+
+            write!(
+                f,
+                "    {low:>8.precision$} to {high:>8.precision$} |{:<BAR_WIDTH$}| {count}",
+                "#".repeat(length)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn bins()
+    {
+        let values = [-1.0, -0.9, 0.0, 0.1, 0.2, 1.0];
+        let histogram = Histogram::new(values.iter().copied(), 4);
+
+        assert_eq!((histogram.low, histogram.high), (-1.0, 1.0));
+        assert_eq!(histogram.counts, vec![2, 0, 3, 1]);
+        assert_eq!(histogram.bin_range(1), (-0.5, 0.0));
+
+        let constant = Histogram::new([0.5; 3].into_iter(), 5);
+        assert_eq!(constant.counts, vec![3, 0, 0, 0, 0]);
+
+        let text = histogram.to_string();
+        assert_eq!(text.lines().count(), 4);
+        assert!(text.lines().nth(2).unwrap().ends_with(&format!("|{}| 3", "#".repeat(BAR_WIDTH))));
+    }
+}
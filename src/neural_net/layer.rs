@@ -1,34 +1,30 @@
-use std::cmp::Ordering;
+use std::{
+    fmt,
+    any::Any
+};
 
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
 use rand::Rng;
 
-pub use transfer::*;
+use crate::neural_net_core::feedforward_layer;
 
+use super::{ConvLayer, ConvSettings, MaxPool};
 
-mod transfer;
+pub use crate::neural_net_core::{TransferFunction, argmax};
+//only used through the library
+#[allow(unused_imports)]
+pub use crate::neural_net_core::{CustomTransfer, register_transfer, softmax};
+pub use optimizer::*;
+pub use loss::*;
+pub use reduce::*;
+pub use parameters::*;
 
 
-pub enum InnerOuter<'a>
-{
-    Outputs(&'a [f64]),
-    Inners(&'a [f64], &'a [Vec<f64>])
-}
+mod optimizer;
+mod loss;
+mod reduce;
+mod parameters;
 
-type Sign = i8;
-fn new_sign(num: f64) -> Sign
-{
-    if num==0.0
-    {
-        0
-    } else if num>0.0
-    {
-        1
-    } else
-    {
-        -1
-    }
-}
 
 #[derive(Debug, Clone)]
 pub struct DefaultLayerSettings
@@ -37,227 +33,325 @@ pub struct DefaultLayerSettings
     pub transfer_function: TransferFunction
 }
 
+//any kind of layer, conv and pooling layers take the previous outputs as an image
+#[derive(Debug, Clone)]
+pub enum LayerSettings
+{
+    Dense(DefaultLayerSettings),
+    Conv(ConvSettings),
+    MaxPool{size: usize}
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DefaultLayer
 {
+    //pre-activations of the last feedforward (weighted sums before the transfer function)
     #[serde(skip)]
     neurons: Vec<f64>,
 
-    learning_rates: Vec<Vec<f64>>,
-    previous_signs: Vec<Vec<Sign>>,
-    #[serde(skip)]
-    gradient_batch: Vec<Vec<f64>>,
-    weights: Vec<Vec<f64>>,
+    //flattened so the weights and rprop state are saved under the same names as before
+    #[serde(flatten)]
+    parameters: Parameters,
 
+    transfer_function: TransferFunction
+}
 
+//everything the network needs from a layer, training goes through the parameters
+//so the network never needs to know which kind of layer it has
+pub trait Layer: fmt::Debug + Send + Sync
+{
+    fn clone_box(&self) -> Box<dyn Layer>;
 
-    transfer_function: TransferFunction
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    //what gets written when the network is saved
+    fn saved(&self) -> SavedLayer<'_>;
+
+    fn size(&self) -> usize;
+
+    //pre-activations of the last feedforward
+    fn neurons(&self) -> &[f64];
+
+    fn transfer_function(&self) -> TransferFunction;
+
+    //none for layers without trainable weights
+    fn parameters(&self) -> Option<&Parameters>;
+    fn parameters_mut(&mut self) -> Option<&mut Parameters>;
+
+    fn feedforward(&mut self, previous_neurons: &[f64], transfer_function: TransferFunction);
+
+    //same as feedforward but writes into an external buffer instead of the layer
+    fn feedforward_into(
+        &self,
+        previous_neurons: &[f64],
+        transfer_function: TransferFunction,
+        neurons: &mut [f64]
+    );
+
+    //adds the gradients for the deltas (error derivatives) of this layers neurons to
+    //gradients shaped like its parameters, inputs are the activations of the previous
+    //layer, its only called for layers with parameters
+    fn backpropagate(&self, inputs: &[f64], deltas: &[f64], gradients: &mut Gradients);
+
+    //errors of the previous layers activations (inputs) for the deltas of this layers neurons
+    fn input_errors(&self, inputs: &[f64], deltas: &[f64], errors: &mut [f64]);
+
+    fn reset_temporary(&mut self);
+
+    //rows of weights which each end with a bias, empty without parameters
+    fn weights(&self) -> &[Vec<f64>]
+    {
+        self.parameters().map(|parameters| parameters.weights()).unwrap_or(&[])
+    }
+
+    //the gradients are sums over the batch, samples_amount turns them into means
+    fn apply_gradients(&mut self, optimizer: Optimizer, samples_amount: usize)
+    {
+        if let Some(parameters) = self.parameters_mut()
+        {
+            parameters.apply(optimizer, samples_amount);
+        }
+    }
+
+    //adds gradients accumulated outside of the layer
+    fn combine(&mut self, gradients: &Gradients)
+    {
+        if let Some(parameters) = self.parameters_mut()
+        {
+            parameters.combine(gradients);
+        }
+    }
+}
+
+impl Clone for Box<dyn Layer>
+{
+    fn clone(&self) -> Self
+    {
+        self.clone_box()
+    }
+}
+
+//untagged so the files saved before there were multiple layer types still load,
+//a dense layer has none of the fields the others need so its last to try
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum SavedLayer<'a>
+{
+    Conv(&'a ConvLayer),
+    MaxPool(&'a MaxPool),
+    Default(&'a DefaultLayer)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LoadedLayer
+{
+    Conv(ConvLayer),
+    MaxPool(MaxPool),
+    Default(DefaultLayer)
+}
+
+impl Serialize for dyn Layer
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        self.saved().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Layer>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        Ok(match LoadedLayer::deserialize(deserializer)?
+        {
+            LoadedLayer::Conv(layer) => Box::new(layer),
+            LoadedLayer::MaxPool(layer) => Box::new(layer),
+            LoadedLayer::Default(layer) => Box::new(layer)
+        })
+    }
 }
 
+#[allow(dead_code)]
 impl DefaultLayer
 {
     pub fn new(size: usize, previous_size: usize, transfer_function: TransferFunction) -> Self
+    {
+        Self::new_with_rng(size, previous_size, transfer_function, &mut rand::thread_rng())
+    }
+
+    pub fn new_with_rng(
+        size: usize,
+        previous_size: usize,
+        transfer_function: TransferFunction,
+        rng: &mut impl Rng
+    ) -> Self
     {
         let neurons = (0..size).map(|_| 0.0).collect::<Vec<f64>>();
 
-        let mut rng = rand::thread_rng();
         let weights = (0..size).map(|_|
         {
             //+1 for bias
             (0..previous_size+1).map(|_| rng.gen::<f64>() * 2.0 - 1.0).collect::<Vec<f64>>()
         }).collect::<Vec<Vec<f64>>>();
 
-        let gradient_batch = weights.iter().map(|wc| vec![0.0; wc.len()])
-            .collect::<Vec<Vec<f64>>>();
-        let learning_rates = weights.iter().map(|wc| vec![0.1; wc.len()])
-            .collect::<Vec<Vec<f64>>>();
-        let previous_signs = weights.iter().map(|wc|
-        {
-            wc.iter().map(|w| new_sign(*w)).collect::<Vec<_>>()
-        }).collect::<Vec<Vec<_>>>();
-
         DefaultLayer{
             neurons,
-            learning_rates, previous_signs, gradient_batch,
-            weights,
+            parameters: Parameters::new(weights),
             transfer_function
         }
     }
 
-    pub fn size(&self) -> usize
+    //inputs missing from indices are zero
+    pub fn feedforward_sparse(&mut self, indices: &[usize], values: &[f64])
     {
-        self.neurons.len()
+        self.neurons.iter_mut().zip(self.parameters.weights().iter())
+            .for_each(|(neuron, neuron_weights)|
+            {
+                let bias = neuron_weights[neuron_weights.len()-1];
+
+                *neuron = indices.iter().zip(values.iter()).map(|(index, value)|
+                {
+                    neuron_weights[*index] * value
+                }).sum::<f64>() + bias;
+            });
     }
+}
 
-    pub fn neurons(&self) -> &[f64]
+impl Layer for DefaultLayer
+{
+    fn clone_box(&self) -> Box<dyn Layer>
     {
-        &self.neurons
+        Box::new(self.clone())
     }
 
-    pub fn weights(&self) -> &[Vec<f64>]
+    fn as_any_mut(&mut self) -> &mut dyn Any
     {
-        &self.weights
+        self
     }
 
-    pub fn transfer_function(&self) -> TransferFunction
+    fn saved(&self) -> SavedLayer<'_>
+    {
+        SavedLayer::Default(self)
+    }
+
+    fn size(&self) -> usize
+    {
+        self.neurons.len()
+    }
+
+    fn neurons(&self) -> &[f64]
+    {
+        &self.neurons
+    }
+
+    fn transfer_function(&self) -> TransferFunction
     {
         self.transfer_function
     }
 
-    pub fn reset_temporary(&mut self)
+    fn parameters(&self) -> Option<&Parameters>
     {
-        self.neurons = (0..self.weights.len()).map(|_| 0.0).collect::<Vec<f64>>();
+        Some(&self.parameters)
+    }
 
-        self.gradient_batch = self.weights.iter().map(|wc| vec![0.0; wc.len()])
-            .collect::<Vec<Vec<f64>>>();
+    fn parameters_mut(&mut self) -> Option<&mut Parameters>
+    {
+        Some(&mut self.parameters)
     }
 
-    pub fn feedforward(&mut self, previous_neurons: &[f64], transfer_function: TransferFunction)
+    fn feedforward(&mut self, previous_neurons: &[f64], transfer_function: TransferFunction)
     {
-        self.neurons.iter_mut().zip(self.weights.iter()).for_each(|(neuron, neuron_weights)|
-        {
-            let bias = unsafe{ neuron_weights.get_unchecked(neuron_weights.len()-1) };
+        feedforward_layer(
+            self.parameters.weights(),
+            previous_neurons,
+            transfer_function,
+            &mut self.neurons
+        );
+    }
 
-            *neuron = previous_neurons.iter()
-                .zip(neuron_weights.iter()).map(|(previous_neuron, weight)|
-                {
-                    transfer_function.t_f(*previous_neuron) * *weight
-                }).sum::<f64>() + bias;
-        });
+    fn feedforward_into(
+        &self,
+        previous_neurons: &[f64],
+        transfer_function: TransferFunction,
+        neurons: &mut [f64]
+    )
+    {
+        feedforward_layer(self.parameters.weights(), previous_neurons, transfer_function, neurons);
     }
 
-    pub fn apply_gradients(&mut self)
+    fn backpropagate(&self, inputs: &[f64], deltas: &[f64], gradients: &mut Gradients)
     {
-        for neuron in 0..self.weights.len()
+        for (i_neuron, delta) in deltas.iter().enumerate()
         {
-            let previous_length = unsafe{ self.weights.get_unchecked(neuron).len() };
-            for previous in 0..previous_length
+            //adding 0 times the inputs wouldnt change the gradients
+            if *delta==0.0
             {
-                let (gradient, previous_sign, learning_rate, weight);
-                unsafe
-                {
-                gradient = self.gradient_batch
-                    .get_unchecked_mut(neuron)
-                    .get_unchecked_mut(previous);
-
-                previous_sign = self.previous_signs
-                    .get_unchecked_mut(neuron)
-                    .get_unchecked_mut(previous);
-
-                learning_rate = self.learning_rates
-                    .get_unchecked_mut(neuron)
-                    .get_unchecked_mut(previous);
-                
-                weight = self.weights
-                    .get_unchecked_mut(neuron)
-                    .get_unchecked_mut(previous);
-                }
-
-                let current_sign = new_sign(*gradient);
-
-                let combination = current_sign * *previous_sign;
-                match combination.cmp(&0)
-                {
-                    Ordering::Greater =>
-                    {
-                        *learning_rate = (*learning_rate * 1.2).min(0.01);
-
-                        *weight -= *learning_rate * current_sign as f64;
-                        *previous_sign = current_sign;
-                    },
-                    Ordering::Less =>
-                    {
-                        *learning_rate = (*learning_rate * 0.5).max(0.000001);
-
-                        *previous_sign = 0;
-                    },
-                    Ordering::Equal =>
-                    {
-                        *weight -= *learning_rate * current_sign as f64;
-                        *previous_sign = current_sign;
-                    }
-                }
-    
-                *gradient = 0.0;
+                continue;
             }
-        }
-    }
 
-    pub fn combine(&mut self, other: &DefaultLayer)
-    {
-        for i_neuron in 0..self.gradient_batch.len()
-        {
-            unsafe
+            let gradients = gradients.row_mut(i_neuron);
+
+            inputs.iter().zip(gradients.iter_mut()).for_each(|(input, gradient)|
             {
-            for i_previous in 0..self.gradient_batch.get_unchecked(i_neuron).len()
+                *gradient += delta * *input;
+            });
+
+            //add bias gradient
+            if let Some(bias) = gradients.last_mut()
             {
-                *self.gradient_batch.get_unchecked_mut(i_neuron).get_unchecked_mut(i_previous) +=
-                    *other.gradient_batch.get_unchecked(i_neuron).get_unchecked(i_previous);
-            }
+                *bias += delta;
             }
         }
     }
 
-    pub fn backpropagate(
-        &mut self,
-        inputs: &[f64],
-        errors: InnerOuter
-    )
+    fn input_errors(&self, _inputs: &[f64], deltas: &[f64], errors: &mut [f64])
     {
-        for i_neuron in 0..self.neurons.len()
-        {
-            let neuron = unsafe{ self.neurons.get_unchecked_mut(i_neuron) };
+        errors.fill(0.0);
 
-            let error = match errors
+        deltas.iter().zip(self.parameters.weights().iter()).filter(|(delta, _)| **delta!=0.0)
+            .for_each(|(delta, weights)|
             {
-                InnerOuter::Outputs(correct) =>
+                //the bias isnt connected to anything so zip leaves it out
+                errors.iter_mut().zip(weights.iter()).for_each(|(error, weight)|
                 {
-                    unsafe
-                    {
-                    self.transfer_function.t_f(*neuron) - *correct.get_unchecked(i_neuron)
-                    }
-                },
-                InnerOuter::Inners(neurons, weights) =>
-                {
-                    neurons.iter().zip(weights.iter()).map(|(next_neuron, next_weight)|
-                    {
-                        next_neuron * unsafe{ *next_weight.get_unchecked(i_neuron) }
-                    }).sum::<f64>()
-                }
-            };
-
-            let deriv = self.transfer_function.dt_f(*neuron) * error;
-
-            let current_batch = unsafe{ self.gradient_batch.get_unchecked_mut(i_neuron) };
-
-            inputs.iter().zip(current_batch.iter_mut()).for_each(|(input, gradient)|
-            {
-                *gradient += deriv * *input;
+                    *error += delta * weight;
+                });
             });
+    }
 
-            let last_gradient = current_batch.len()-1;
-            //add bias gradient
-            unsafe{ *current_batch.get_unchecked_mut(last_gradient) += deriv };
+    fn reset_temporary(&mut self)
+    {
+        self.neurons = (0..self.parameters.weights().len()).map(|_| 0.0).collect::<Vec<f64>>();
 
-            //set current neuron to its derivative
-            *neuron = deriv;
-        }
+        self.parameters.reset_temporary();
     }
 }
 
 
 #[cfg(test)]
-pub mod tests
+pub(crate) mod tests
 {
     use super::*;
 
-    pub fn get_weight(layer: &mut DefaultLayer, neuron: usize, previous: usize) -> &mut f64
+    fn parameters(layer: &mut Box<dyn Layer>) -> &mut Parameters
+    {
+        layer.parameters_mut().unwrap()
+    }
+
+    pub fn get_weight(layer: &mut Box<dyn Layer>, neuron: usize, previous: usize) -> &mut f64
+    {
+        parameters(layer).weights_mut()[neuron].get_mut(previous).unwrap()
+    }
+
+    pub fn get_learning_rate(layer: &mut Box<dyn Layer>, neuron: usize, previous: usize) -> &mut f64
     {
-        layer.weights[neuron].get_mut(previous).unwrap()
+        parameters(layer).learning_rates_mut()[neuron].get_mut(previous).unwrap()
     }
 
-    pub fn get_gradient(layer: &mut DefaultLayer, neuron: usize, previous: usize) -> &mut f64
+    //marks the neuron as active so the gradient gets applied even if its the only nonzero one
+    pub fn get_gradient(layer: &mut Box<dyn Layer>, neuron: usize, previous: usize) -> &mut f64
     {
-        layer.gradient_batch[neuron].get_mut(previous).unwrap()
+        parameters(layer).gradients_mut(neuron).get_mut(previous).unwrap()
     }
 }
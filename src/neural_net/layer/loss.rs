@@ -0,0 +1,61 @@
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Loss
+{
+    #[default]
+    Mse,
+    Huber{delta: f64},
+    //binary cross entropy for each output, expects outputs between 0 and 1
+    CrossEntropy
+}
+
+//keeps the logarithms finite for saturated outputs
+const CROSS_ENTROPY_EPSILON: f64 = 1e-12;
+
+#[allow(dead_code)]
+impl Loss
+{
+    pub fn error(&self, output: f64, correct: f64) -> f64
+    {
+        let residual = output - correct;
+
+        match self
+        {
+            Loss::Mse => residual.powi(2) * 0.5,
+            Loss::Huber{delta} =>
+            {
+                if residual.abs()<=*delta
+                {
+                    residual.powi(2) * 0.5
+                } else
+                {
+                    delta * (residual.abs() - delta * 0.5)
+                }
+            },
+            Loss::CrossEntropy =>
+            {
+                let output = output.clamp(CROSS_ENTROPY_EPSILON, 1.0 - CROSS_ENTROPY_EPSILON);
+
+                -(correct * output.ln() + (1.0 - correct) * (1.0 - output).ln())
+            }
+        }
+    }
+
+    //derivative of the error with respect to the output
+    pub fn derivative(&self, output: f64, correct: f64) -> f64
+    {
+        let residual = output - correct;
+
+        match self
+        {
+            Loss::Mse => residual,
+            Loss::Huber{delta} => residual.clamp(-delta, *delta),
+            Loss::CrossEntropy =>
+            {
+                let output = output.clamp(CROSS_ENTROPY_EPSILON, 1.0 - CROSS_ENTROPY_EPSILON);
+
+                residual / (output * (1.0 - output))
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//what rprop does when a gradient changes its sign, meaning the last step jumped over a minimum
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum RpropVariant
+{
+    //skips the update and only shrinks the learning rate
+    #[default]
+    IRpropMinus,
+    //also undoes the last step of that weight (weight backtracking)
+    RpropPlus
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub enum Optimizer
+{
+    Rprop{variant: RpropVariant},
+    Sgd{learning_rate: f64, momentum: f64}
+}
+
+impl Default for Optimizer
+{
+    fn default() -> Self
+    {
+        Optimizer::Rprop{variant: RpropVariant::default()}
+    }
+}
+
+impl Optimizer
+{
+    //same optimizer with its learning rate multiplied by scale, rprop adapts its own
+    //learning rates so it stays the same
+    pub fn scaled(self, scale: f64) -> Self
+    {
+        match self
+        {
+            Optimizer::Rprop{..} => self,
+            Optimizer::Sgd{learning_rate, momentum} =>
+            {
+                Optimizer::Sgd{learning_rate: learning_rate * scale, momentum}
+            }
+        }
+    }
+}
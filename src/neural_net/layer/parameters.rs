@@ -0,0 +1,459 @@
+use std::{
+    mem,
+    cmp::Ordering
+};
+
+use serde::{Serialize, Deserialize};
+
+use crate::fast_index::FastIndex;
+
+use super::{Optimizer, RpropVariant, GradReduce};
+
+
+const LEARNING_RATE_INIT: f64 = 0.1;
+const LEARNING_RATE_MIN: f64 = 0.000001;
+const LEARNING_RATE_MAX: f64 = 0.01;
+
+type Sign = i8;
+fn new_sign(num: f64) -> Sign
+{
+    if num==0.0
+    {
+        0
+    } else if num>0.0
+    {
+        1
+    } else
+    {
+        -1
+    }
+}
+
+//sums of the gradients of some samples, shaped like the weights of the parameters
+//theyre for, kept apart from them so every thread can fill its own while sharing the layers
+#[derive(Debug, Clone, Default)]
+pub struct Gradients
+{
+    rows: Vec<Vec<f64>>,
+    //rows which got a nonzero gradient since the gradients were last cleared,
+    //every gradient of the other rows is 0 so applying them can be skipped
+    active_rows: Vec<bool>
+}
+
+impl Gradients
+{
+    pub fn new(weights: &[Vec<f64>]) -> Self
+    {
+        Gradients{
+            rows: weights.iter().map(|wc| vec![0.0; wc.len()]).collect(),
+            active_rows: vec![false; weights.len()]
+        }
+    }
+
+    pub fn rows(&self) -> &[Vec<f64>]
+    {
+        &self.rows
+    }
+
+    //gradients of a row to add to, the row gets applied next time
+    pub fn row_mut(&mut self, row: usize) -> &mut [f64]
+    {
+        self.active_rows[row] = true;
+
+        &mut self.rows[row]
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.rows.iter_mut().for_each(|gradients| gradients.fill(0.0));
+        self.active_rows.fill(false);
+    }
+
+    pub fn combine(&mut self, other: &Gradients)
+    {
+        let rows = self.rows.iter_mut().zip(self.active_rows.iter_mut())
+            .zip(other.rows.iter().zip(other.active_rows.iter()));
+
+        rows.filter(|(_, (_, other_active))| **other_active)
+            .for_each(|((gradients, active), (other_gradients, _))|
+            {
+                *active = true;
+
+                gradients.iter_mut().zip(other_gradients.iter()).for_each(|(gradient, other)|
+                {
+                    *gradient += *other;
+                });
+            });
+    }
+
+    //replaces the gradients with a reduction of them and the other gradients
+    pub fn reduce(&mut self, others: &[&Gradients], reduction: GradReduce)
+    {
+        if reduction==GradReduce::Sum
+        {
+            others.iter().for_each(|other| self.combine(other));
+            return;
+        }
+
+        let mut values = Vec::with_capacity(others.len() + 1);
+        for (row, (gradients, active)) in self.rows.iter_mut()
+            .zip(self.active_rows.iter_mut())
+            .enumerate()
+        {
+            //all zero gradients reduce to zeroes
+            if !(*active || others.iter().any(|other| other.active_rows[row]))
+            {
+                continue;
+            }
+
+            *active = true;
+
+            for (weight, gradient) in gradients.iter_mut().enumerate()
+            {
+                values.clear();
+                values.push(*gradient);
+                values.extend(others.iter().map(|other| other.rows[row][weight]));
+
+                *gradient = reduction.reduce(&mut values);
+            }
+        }
+    }
+}
+
+//trainable weights of a layer with their gradients and the optimizer state, the network
+//trains every layer through these so it doesnt have to know what kind of layer it is,
+//weights are in rows which each end with a bias (a row per neuron for a dense layer)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Parameters
+{
+    learning_rates: Vec<Vec<f64>>,
+    previous_signs: Vec<Vec<Sign>>,
+    #[serde(skip)]
+    gradients: Gradients,
+    #[serde(skip)]
+    velocities: Vec<Vec<f64>>,
+    //last rprop step of every weight, rprop+ undoes it when the gradient flips
+    //saved with the signs so a resumed training backtracks the same way
+    #[serde(default)]
+    previous_deltas: Vec<Vec<f64>>,
+    weights: Vec<Vec<f64>>,
+
+    //frozen layers still propagate errors but never update their weights
+    #[serde(default)]
+    frozen: bool
+}
+
+#[allow(dead_code)]
+impl Parameters
+{
+    pub fn new(weights: Vec<Vec<f64>>) -> Self
+    {
+        let learning_rates = Self::initial_learning_rates(&weights);
+        let previous_signs = Self::initial_signs(&weights);
+
+        let mut parameters = Parameters{
+            learning_rates,
+            previous_signs,
+            gradients: Gradients::default(),
+            velocities: Vec::new(),
+            previous_deltas: Vec::new(),
+            weights,
+            frozen: false
+        };
+
+        parameters.reset_temporary();
+
+        parameters
+    }
+
+    fn initial_learning_rates(weights: &[Vec<f64>]) -> Vec<Vec<f64>>
+    {
+        weights.iter().map(|wc| vec![LEARNING_RATE_INIT; wc.len()]).collect::<Vec<Vec<f64>>>()
+    }
+
+    fn initial_signs(weights: &[Vec<f64>]) -> Vec<Vec<Sign>>
+    {
+        weights.iter().map(|wc|
+        {
+            wc.iter().map(|w| new_sign(*w)).collect::<Vec<_>>()
+        }).collect::<Vec<Vec<_>>>()
+    }
+
+    fn zeroed(&self) -> Vec<Vec<f64>>
+    {
+        self.weights.iter().map(|wc| vec![0.0; wc.len()]).collect::<Vec<Vec<f64>>>()
+    }
+
+    pub fn weights(&self) -> &[Vec<f64>]
+    {
+        &self.weights
+    }
+
+    //the shape must stay the same
+    pub fn weights_mut(&mut self) -> &mut [Vec<f64>]
+    {
+        &mut self.weights
+    }
+
+    pub fn gradients(&self) -> &[Vec<f64>]
+    {
+        self.gradients.rows()
+    }
+
+    //gradients of a row to add to, the row gets applied next time
+    pub fn gradients_mut(&mut self, row: usize) -> &mut [f64]
+    {
+        self.gradients.row_mut(row)
+    }
+
+    //leaves empty gradients until they get set back
+    pub fn take_gradients(&mut self) -> Gradients
+    {
+        mem::take(&mut self.gradients)
+    }
+
+    //the shape must stay the same
+    pub fn set_gradients(&mut self, gradients: Gradients)
+    {
+        self.gradients = gradients;
+    }
+
+    //empty gradients to accumulate into away from the parameters
+    pub fn empty_gradients(&self) -> Gradients
+    {
+        Gradients::new(&self.weights)
+    }
+
+    //rprop learning rate of every weight, shaped like weights
+    pub fn learning_rates(&self) -> &[Vec<f64>]
+    {
+        &self.learning_rates
+    }
+
+    //sets every rprop learning rate to the same value
+    pub fn fill_learning_rates(&mut self, rate: f64)
+    {
+        self.learning_rates.iter_mut().for_each(|rates| rates.fill(rate));
+    }
+
+    //rprop learning rates which cant get any smaller
+    pub fn floored_learning_rates(&self) -> usize
+    {
+        self.learning_rates.iter().flatten().filter(|rate| **rate<=LEARNING_RATE_MIN).count()
+    }
+
+    //smallest and largest rprop learning rates
+    pub fn learning_rate_bounds(&self) -> (f64, f64)
+    {
+        self.learning_rates.iter().flatten().fold((f64::INFINITY, f64::NEG_INFINITY),
+            |(lowest, highest), rate| (lowest.min(*rate), highest.max(*rate)))
+    }
+
+    //resets the rprop state as if the current weights were freshly initialized
+    pub fn reset_adaptive(&mut self)
+    {
+        self.learning_rates = Self::initial_learning_rates(&self.weights);
+        self.previous_signs = Self::initial_signs(&self.weights);
+        self.previous_deltas = self.zeroed();
+    }
+
+    pub fn frozen(&self) -> bool
+    {
+        self.frozen
+    }
+
+    pub fn set_frozen(&mut self, frozen: bool)
+    {
+        self.frozen = frozen;
+    }
+
+    //weights including the biases
+    pub fn amount(&self) -> usize
+    {
+        self.weights.iter().map(|weights| weights.len()).sum()
+    }
+
+    //zeroes every weight and bias
+    pub fn zero(&mut self)
+    {
+        self.weights.iter_mut().for_each(|weights| weights.fill(0.0));
+    }
+
+    //zeroes weights (not biases) smaller than the threshold, returns how many
+    pub fn prune(&mut self, threshold: f64) -> usize
+    {
+        self.weights.iter_mut().map(|weights|
+        {
+            let bias = weights.len()-1;
+
+            weights[..bias].iter_mut().filter(|weight| weight.abs()<threshold && **weight!=0.0)
+                .map(|weight| *weight = 0.0)
+                .count()
+        }).sum()
+    }
+
+    //weights of each row without the bias and the biases separately
+    pub fn weight_matrix(&self) -> (Vec<Vec<f64>>, Vec<f64>)
+    {
+        self.weights.iter().map(|weights|
+        {
+            let (weights, bias) = weights.split_at(weights.len()-1);
+
+            (weights.to_vec(), bias[0])
+        }).unzip()
+    }
+
+    //weights without the biases
+    pub fn connections(&self) -> impl Iterator<Item=&f64> + '_
+    {
+        self.weights.iter().flat_map(|weights| weights[..weights.len()-1].iter())
+    }
+
+    //the buffers which arent saved, shaped like the weights
+    pub fn reset_temporary(&mut self)
+    {
+        self.gradients = self.empty_gradients();
+
+        self.velocities = self.zeroed();
+
+        //files saved before the deltas were kept dont have them
+        if self.previous_deltas.len()!=self.weights.len()
+        {
+            self.previous_deltas = self.zeroed();
+        }
+    }
+
+    pub fn clear_gradients(&mut self)
+    {
+        self.gradients.clear();
+    }
+
+    //the gradients are sums over the batch, samples_amount turns them into means
+    pub fn apply(&mut self, optimizer: Optimizer, samples_amount: usize)
+    {
+        if self.frozen
+        {
+            self.clear_gradients();
+            return;
+        }
+
+        match optimizer
+        {
+            Optimizer::Rprop{variant} => self.apply_rprop(variant),
+            Optimizer::Sgd{learning_rate, momentum} =>
+            {
+                self.apply_sgd(learning_rate, momentum, samples_amount.max(1) as f64)
+            }
+        }
+
+        self.gradients.active_rows.fill(false);
+    }
+
+    //adds gradients accumulated somewhere else
+    pub fn combine(&mut self, gradients: &Gradients)
+    {
+        self.gradients.combine(gradients);
+    }
+
+    fn apply_sgd(&mut self, learning_rate: f64, momentum: f64, samples_amount: f64)
+    {
+        let gradients = self.gradients.rows.iter_mut().flatten();
+        let velocities = self.velocities.iter_mut().flatten();
+        let weights = self.weights.iter_mut().flatten();
+
+        gradients.zip(velocities).zip(weights).for_each(|((gradient, velocity), weight)|
+        {
+            *velocity = momentum * *velocity - learning_rate * *gradient / samples_amount;
+            *weight += *velocity;
+
+            *gradient = 0.0;
+        });
+    }
+
+    //only the gradient signs matter so the sums work as is
+    fn apply_rprop(&mut self, variant: RpropVariant)
+    {
+        for row in 0..self.weights.len()
+        {
+            //all zero gradients leave the weights and learning rates as is and only reset the signs
+            if !unsafe{ *self.gradients.active_rows.fast_get(row) }
+            {
+                unsafe{ self.previous_signs.fast_get_mut(row) }.fill(0);
+                unsafe{ self.previous_deltas.fast_get_mut(row) }.fill(0.0);
+                continue;
+            }
+
+            let row_length = unsafe{ self.weights.fast_get(row).len() };
+            for index in 0..row_length
+            {
+                let (gradient, previous_sign, previous_delta, learning_rate, weight);
+                unsafe
+                {
+                gradient = self.gradients.rows
+                    .fast_get_mut(row)
+                    .fast_get_mut(index);
+
+                previous_sign = self.previous_signs
+                    .fast_get_mut(row)
+                    .fast_get_mut(index);
+
+                previous_delta = self.previous_deltas
+                    .fast_get_mut(row)
+                    .fast_get_mut(index);
+
+                learning_rate = self.learning_rates
+                    .fast_get_mut(row)
+                    .fast_get_mut(index);
+
+                weight = self.weights
+                    .fast_get_mut(row)
+                    .fast_get_mut(index);
+                }
+
+                let current_sign = new_sign(*gradient);
+
+                let combination = current_sign * *previous_sign;
+                match combination.cmp(&0)
+                {
+                    Ordering::Greater =>
+                    {
+                        *learning_rate = (*learning_rate * 1.2).min(LEARNING_RATE_MAX);
+
+                        *previous_delta = -*learning_rate * current_sign as f64;
+                        *weight += *previous_delta;
+                        *previous_sign = current_sign;
+                    },
+                    Ordering::Less =>
+                    {
+                        *learning_rate = (*learning_rate * 0.5).max(LEARNING_RATE_MIN);
+
+                        if variant==RpropVariant::RpropPlus
+                        {
+                            *weight -= *previous_delta;
+                        }
+
+                        *previous_delta = 0.0;
+                        *previous_sign = 0;
+                    },
+                    Ordering::Equal =>
+                    {
+                        *previous_delta = -*learning_rate * current_sign as f64;
+                        *weight += *previous_delta;
+                        *previous_sign = current_sign;
+                    }
+                }
+
+                *gradient = 0.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl Parameters
+{
+    pub(crate) fn learning_rates_mut(&mut self) -> &mut [Vec<f64>]
+    {
+        &mut self.learning_rates
+    }
+}
@@ -0,0 +1,61 @@
+//how the gradients of the threads get merged, mean and median merge the sums
+//of every thread so the result is the gradient of a single threads samples
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum GradReduce
+{
+    #[default]
+    Sum,
+    Mean,
+    //coordinate wise, so a thread with an outlier batch cant drag the gradient along
+    Median
+}
+
+#[allow(dead_code)]
+impl GradReduce
+{
+    //reorders values for the median
+    pub fn reduce(&self, values: &mut [f64]) -> f64
+    {
+        match self
+        {
+            GradReduce::Sum => values.iter().sum(),
+            GradReduce::Mean => values.iter().sum::<f64>() / values.len().max(1) as f64,
+            GradReduce::Median =>
+            {
+                if values.is_empty()
+                {
+                    return 0.0;
+                }
+
+                values.sort_unstable_by(|a, b| a.total_cmp(b));
+
+                let middle = values.len() / 2;
+                if values.len().is_multiple_of(2)
+                {
+                    (values[middle - 1] + values[middle]) * 0.5
+                } else
+                {
+                    values[middle]
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn reductions()
+    {
+        let values = [3.0, -1.0, 100.0, 2.0];
+
+        assert_eq!(GradReduce::Sum.reduce(&mut values.clone()), 104.0);
+        assert_eq!(GradReduce::Mean.reduce(&mut values.clone()), 26.0);
+        assert_eq!(GradReduce::Median.reduce(&mut values.clone()), 2.5);
+        assert_eq!(GradReduce::Median.reduce(&mut values[..3].to_vec()), 3.0);
+    }
+}
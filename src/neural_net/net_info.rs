@@ -0,0 +1,164 @@
+use std::fmt;
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    de::{IgnoredAny, SeqAccess, Visitor}
+};
+
+use super::*;
+
+
+//shape of a saved network, read without loading the weights
+#[derive(Debug, Clone)]
+pub struct NetInfo
+{
+    pub inputs: usize,
+    pub layer_sizes: Vec<usize>,
+    pub transfer_functions: Vec<TransferFunction>,
+    pub total_params: usize
+}
+
+impl NetInfo
+{
+    //also reads files saved before checksums
+    pub(super) fn read(filename: &str) -> Result<Self, LoadError>
+    {
+        let data = fs::read(filename).map_err(LoadError::Io)?;
+        let payload = checksum::unseal(&data, true)?;
+
+        let shape = ciborium::de::from_reader::<NetShape, _>(payload)
+            .map_err(LoadError::Decode)?;
+
+        Ok(NetInfo{
+            inputs: shape.inputs_amount,
+            layer_sizes: shape.layers.iter().map(|layer| layer.size()).collect(),
+            transfer_functions: shape.layers.iter().map(|layer|
+            {
+                layer.transfer_function.unwrap_or(TransferFunction::Nothing)
+            }).collect(),
+            total_params: shape.layers.iter().flat_map(|layer| layer.weights.0.iter()).sum()
+        })
+    }
+}
+
+impl fmt::Display for NetInfo
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        writeln!(f, "inputs: {}", self.inputs)?;
+
+        for (index, (size, transfer_function)) in self.layer_sizes.iter()
+            .zip(self.transfer_functions.iter())
+            .enumerate()
+        {
+            writeln!(f, "layer {index}: {size} neurons, {transfer_function:?}")?;
+        }
+
+        write!(f, "total parameters: {}", self.total_params)
+    }
+}
+
+//only the fields needed for the shape, serde skips the rest
+#[derive(Deserialize)]
+struct NetShape
+{
+    inputs_amount: usize,
+    layers: Vec<LayerShape>
+}
+
+//pooling layers have no weights or transfer function
+#[derive(Deserialize)]
+struct LayerShape
+{
+    #[serde(default)]
+    weights: WeightsShape,
+    transfer_function: Option<TransferFunction>,
+    //only in conv layers
+    window: Option<Window>,
+    //only in pooling layers
+    input: Option<Shape>,
+    size: Option<usize>
+}
+
+impl LayerShape
+{
+    fn size(&self) -> usize
+    {
+        match (self.window, self.input, self.size)
+        {
+            (Some(window), _, _) => window.output.size(),
+            (None, Some(input), Some(size)) => MaxPool::pooled(input, size).size(),
+            _ => self.weights.0.len()
+        }
+    }
+}
+
+//amount of weights of each neuron
+#[derive(Default)]
+struct WeightsShape(Vec<usize>);
+
+impl<'de> Deserialize<'de> for WeightsShape
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        struct NeuronsVisitor;
+
+        impl<'de> Visitor<'de> for NeuronsVisitor
+        {
+            type Value = Vec<usize>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+            {
+                write!(f, "a sequence of weight sequences")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            {
+                let mut sizes = Vec::new();
+                while let Some(size) = seq.next_element::<WeightsCount>()?
+                {
+                    sizes.push(size.0);
+                }
+
+                Ok(sizes)
+            }
+        }
+
+        deserializer.deserialize_seq(NeuronsVisitor).map(WeightsShape)
+    }
+}
+
+//counts the elements of a sequence without keeping them
+struct WeightsCount(usize);
+
+impl<'de> Deserialize<'de> for WeightsCount
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        struct CountVisitor;
+
+        impl<'de> Visitor<'de> for CountVisitor
+        {
+            type Value = usize;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+            {
+                write!(f, "a sequence of weights")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            {
+                let mut count = 0;
+                while seq.next_element::<IgnoredAny>()?.is_some()
+                {
+                    count += 1;
+                }
+
+                Ok(count)
+            }
+        }
+
+        deserializer.deserialize_seq(CountVisitor).map(WeightsCount)
+    }
+}
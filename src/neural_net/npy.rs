@@ -0,0 +1,34 @@
+//numpy .npy format version 1.0
+const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+//magic and the header length, the header gets padded so the data starts aligned to this
+const PREFIX_SIZE: usize = MAGIC.len() + 2;
+const ALIGNMENT: usize = 64;
+
+//little endian f64 array in c order, values has to hold the product of the shape
+pub fn encode(shape: &[usize], values: &[f64]) -> Vec<u8>
+{
+    debug_assert_eq!(shape.iter().product::<usize>(), values.len());
+
+    //one dimensional tuples need a trailing comma in python
+    let shape = match shape
+    {
+        [size] => format!("({size},)"),
+        _ => format!("({})", shape.iter().map(|size| size.to_string()).collect::<Vec<_>>().join(", "))
+    };
+
+    let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {shape}, }}");
+
+    //padded with spaces and ended with a newline
+    let padding = ALIGNMENT - (PREFIX_SIZE + header.len() + 1) % ALIGNMENT;
+    header.extend(std::iter::repeat_n(' ', padding % ALIGNMENT));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(PREFIX_SIZE + header.len() + values.len() * 8);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+
+    values.iter().for_each(|value| bytes.extend_from_slice(&value.to_le_bytes()));
+
+    bytes
+}
@@ -0,0 +1,121 @@
+use serde::{Serialize, Deserialize};
+
+
+//divisor for 8 bit pixels
+pub const DEFAULT_INPUT_SCALE: f64 = 255.0;
+
+fn default_input_scale() -> f64
+{
+    DEFAULT_INPUT_SCALE
+}
+
+//mean and standard deviation of every input of the training set after scaling
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Standardization
+{
+    pub mean: f64,
+    pub deviation: f64
+}
+
+//how raw pixels turn into network inputs, saved with the network so inference
+//preprocesses images the same way as training
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Preprocessing
+{
+    //named like the field networks were saved with before this struct existed
+    #[serde(rename = "input_scale", default = "default_input_scale")]
+    pub scale: f64,
+    //pixels at or above the threshold (after scaling) become 1 and the rest 0
+    #[serde(default)]
+    pub binarize: Option<f64>,
+    #[serde(default)]
+    pub standardize: Option<Standardization>
+}
+
+impl Default for Preprocessing
+{
+    fn default() -> Self
+    {
+        Preprocessing{scale: DEFAULT_INPUT_SCALE, binarize: None, standardize: None}
+    }
+}
+
+impl Preprocessing
+{
+    //scales, then binarizes and standardizes if those are set
+    pub fn apply(&self, image: &[u8]) -> Vec<f64>
+    {
+        image.iter().map(|pixel| self.scaled(*pixel)).map(|value|
+        {
+            match self.standardize
+            {
+                Some(Standardization{mean, deviation}) => (value - mean) / deviation,
+                None => value
+            }
+        }).collect()
+    }
+
+    //statistics of the inputs these settings give for the images (ignoring any standardization),
+    //a deviation of 0 is replaced with 1 so constant inputs dont divide by 0
+    pub fn standardization(&self, images: impl Iterator<Item=Vec<u8>>) -> Standardization
+    {
+        let (mut amount, mut sum, mut squared_sum) = (0_usize, 0.0, 0.0);
+        images.for_each(|image|
+        {
+            image.into_iter().map(|pixel| self.scaled(pixel)).for_each(|value|
+            {
+                amount += 1;
+                sum += value;
+                squared_sum += value * value;
+            });
+        });
+
+        let amount = amount.max(1) as f64;
+
+        let mean = sum / amount;
+        let deviation = (squared_sum / amount - mean * mean).max(0.0).sqrt();
+
+        Standardization{mean, deviation: if deviation>0.0 {deviation} else {1.0}}
+    }
+
+    fn scaled(&self, pixel: u8) -> f64
+    {
+        match self.binarize
+        {
+            Some(threshold) => if pixel as f64 >= threshold * self.scale {1.0} else {0.0},
+            None => pixel as f64 / self.scale
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn standardized()
+    {
+        let images = vec![vec![0, 51], vec![255, 102]];
+
+        let mut preprocessing = Preprocessing::default();
+        assert_eq!(preprocessing.apply(&images[0]), vec![0.0, 0.2]);
+
+        let standardization = preprocessing.standardization(images.clone().into_iter());
+        assert!((standardization.mean - 0.4).abs() < 1e-12);
+
+        preprocessing.standardize = Some(standardization);
+
+        let inputs = images.iter().flat_map(|image| preprocessing.apply(image)).collect::<Vec<f64>>();
+
+        let mean = inputs.iter().sum::<f64>() / inputs.len() as f64;
+        let variance = inputs.iter().map(|input| (input - mean).powi(2)).sum::<f64>()
+            / inputs.len() as f64;
+
+        assert!(mean.abs() < 1e-12);
+        assert!((variance - 1.0).abs() < 1e-12);
+
+        let constant = Preprocessing::default().standardization(vec![vec![7; 3]].into_iter());
+        assert_eq!(constant.deviation, 1.0);
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    fs::File,
+    io
+};
+
+use serde::{Serialize, Deserialize};
+
+use super::*;
+
+
+//weights stored as i8 with a shared scale per layer, bias included
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuantizedLayer
+{
+    size: usize,
+    scale: f64,
+    #[serde(with = "serde_bytes")]
+    weights: Vec<u8>,
+    transfer_function: TransferFunction
+}
+
+impl QuantizedLayer
+{
+    pub fn new(layer: &dyn Layer) -> Self
+    {
+        let max_weight = layer.weights().iter().flatten().fold(0.0_f64, |acc, weight|
+        {
+            acc.max(weight.abs())
+        });
+
+        let scale = if max_weight==0.0 {1.0} else {max_weight / i8::MAX as f64};
+
+        let weights = layer.weights().iter().flatten().map(|weight|
+        {
+            (weight / scale).round() as i8 as u8
+        }).collect::<Vec<u8>>();
+
+        QuantizedLayer{
+            size: layer.size(),
+            scale,
+            weights,
+            transfer_function: layer.transfer_function()
+        }
+    }
+
+    pub fn feedforward(
+        &self,
+        previous_neurons: &[f64],
+        transfer_function: TransferFunction
+    ) -> Vec<f64>
+    {
+        let row_length = self.weights.len() / self.size;
+
+        self.weights.chunks_exact(row_length).map(|neuron_weights|
+        {
+            let bias = neuron_weights[row_length-1] as i8 as f64 * self.scale;
+
+            previous_neurons.iter().zip(neuron_weights.iter()).map(|(previous_neuron, weight)|
+            {
+                transfer_function.t_f(*previous_neuron) * (*weight as i8 as f64 * self.scale)
+            }).sum::<f64>() + bias
+        }).collect::<Vec<f64>>()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuantizedNet
+{
+    inputs_amount: usize,
+    layers: Vec<QuantizedLayer>
+}
+
+#[allow(dead_code)]
+impl QuantizedNet
+{
+    pub fn new(network: &NeuralNet) -> Self
+    {
+        QuantizedNet{
+            inputs_amount: network.inputs_amount,
+            layers: network.layers.iter().map(|layer| QuantizedLayer::new(layer.as_ref())).collect()
+        }
+    }
+
+    pub fn load(filename: &str) -> Result<Self, ciborium::de::Error<io::Error>>
+    {
+        ciborium::de::from_reader::<Self, _>(File::open(filename)
+            .map_err(ciborium::de::Error::Io)?)
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), ciborium::ser::Error<io::Error>>
+    {
+        ciborium::ser::into_writer(&self, File::create(filename)
+            .map_err(ciborium::ser::Error::Io)?)
+    }
+
+    pub fn inputs_amount(&self) -> usize
+    {
+        self.inputs_amount
+    }
+
+    pub fn feedforward(&self, inputs: &[f64]) -> Vec<f64>
+    {
+        let mut neurons = inputs.to_vec();
+        let mut transfer_function = TransferFunction::Nothing;
+
+        for layer in &self.layers
+        {
+            neurons = layer.feedforward(&neurons, transfer_function);
+            transfer_function = layer.transfer_function;
+        }
+
+        neurons.into_iter().map(|n| transfer_function.t_f(n)).collect::<Vec<f64>>()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn quantized_outputs()
+    {
+        let layers = [
+            DefaultLayerSettings{size: 50, transfer_function: TransferFunction::Tanh},
+            DefaultLayerSettings{size: 10, transfer_function: TransferFunction::Sigmoid}
+        ];
+        let mut network = NeuralNet::create(100, &layers);
+        let quantized = network.quantize().unwrap();
+
+        let network_path = std::env::temp_dir().join("digitsrecog_quantized_original.nn");
+        let network_path = network_path.to_str().unwrap();
+        let quantized_path = std::env::temp_dir().join("digitsrecog_quantized.qnn");
+        let quantized_path = quantized_path.to_str().unwrap();
+
+        network.save(network_path).unwrap();
+        quantized.save(quantized_path).unwrap();
+
+        let loaded = QuantizedNet::load(quantized_path).unwrap();
+
+        let network_size = std::fs::metadata(network_path).unwrap().len();
+        let quantized_size = std::fs::metadata(quantized_path).unwrap().len();
+
+        std::fs::remove_file(network_path).unwrap();
+        std::fs::remove_file(quantized_path).unwrap();
+
+        //the rprop state is saved too so compare against the weights alone
+        let weights_amount = network.layers.iter().map(|layer| layer.weights().iter().flatten().count())
+            .sum::<usize>() as u64;
+        assert!(quantized_size * 6 < weights_amount * 9);
+        assert!(quantized_size < network_size);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..10
+        {
+            let inputs = (0..100).map(|_| rng.gen()).collect::<Vec<f64>>();
+
+            let quantized_outputs = loaded.feedforward(&inputs);
+            let outputs = network.feedforward(&inputs);
+
+            assert_eq!(quantized_outputs, quantized.feedforward(&inputs));
+            for (quantized_output, output) in quantized_outputs.iter().zip(outputs.iter())
+            {
+                assert!((quantized_output - output).abs() < 0.1);
+            }
+        }
+    }
+}
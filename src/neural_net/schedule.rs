@@ -0,0 +1,128 @@
+use std::f64::consts::PI;
+
+
+//cosine annealing with warm restarts, the learning rate decays from its full value
+//towards 0 over a cycle and then jumps back up, each cycle being multiplier times
+//longer than the one before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WarmRestarts
+{
+    pub cycle: usize,
+    pub multiplier: f64
+}
+
+impl WarmRestarts
+{
+    //learning rate scale for an iteration and whether a new cycle starts on it
+    pub fn scale(&self, iteration: usize) -> (f64, bool)
+    {
+        assert!(self.cycle>0, "restart cycle cant be empty");
+
+        let index = self.cycle_index(iteration);
+        let (start, end) = (self.cycle_start(index), self.cycle_start(index + 1));
+
+        let position = (iteration - start) as f64 / (end - start) as f64;
+        let scale = 0.5 * (1.0 + (PI * position).cos());
+
+        (scale, iteration==start && iteration>0)
+    }
+
+    //cycle lengths grow geometrically so a cycle starts after cycle * (m^index - 1) / (m - 1)
+    //iterations, rounded up to whole iterations
+    fn cycle_start(&self, index: usize) -> usize
+    {
+        if self.multiplier==1.0
+        {
+            return self.cycle * index;
+        }
+
+        let sum = (self.multiplier.powi(index as i32) - 1.0) / (self.multiplier - 1.0);
+
+        (self.cycle as f64 * sum).ceil() as usize
+    }
+
+    //inverse of the geometric sum, then nudged since the float estimate can be off by one
+    fn cycle_index(&self, iteration: usize) -> usize
+    {
+        if self.multiplier==1.0
+        {
+            return iteration / self.cycle;
+        }
+
+        let cycles = iteration as f64 * (self.multiplier - 1.0) / self.cycle as f64;
+        let mut index = ((1.0 + cycles).ln() / self.multiplier.ln()).floor() as usize;
+
+        while self.cycle_start(index + 1)<=iteration
+        {
+            index += 1;
+        }
+
+        while index>0 && self.cycle_start(index)>iteration
+        {
+            index -= 1;
+        }
+
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn restarts()
+    {
+        let schedule = WarmRestarts{cycle: 4, multiplier: 2.0};
+
+        let scales = (0..14).map(|i| schedule.scale(i)).collect::<Vec<(f64, bool)>>();
+
+        assert_eq!(scales[0], (1.0, false));
+        assert!((scales[2].0 - 0.5).abs() < 1e-12);
+
+        //cycles of 4 then 8 iterations
+        let restarts = scales.iter().enumerate()
+            .filter(|(_, (_, restarted))| *restarted)
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        assert_eq!(restarts, vec![4, 12]);
+
+        assert!(scales[3].0 < scales[2].0);
+        assert_eq!(scales[4].0, 1.0);
+        assert!((scales[8].0 - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn restart_positions()
+    {
+        //walks the cycles one by one like the schedule is defined
+        let walked = |schedule: WarmRestarts, iterations: usize|
+        {
+            let (mut start, mut length) = (0.0, schedule.cycle as f64);
+
+            let mut restarts = Vec::new();
+            while (start + length).ceil() < iterations as f64
+            {
+                start += length;
+                length *= schedule.multiplier;
+
+                restarts.push(start.ceil() as usize);
+            }
+
+            restarts
+        };
+
+        for (cycle, multiplier) in [(3, 1.0), (5, 1.5), (1, 2.0), (7, 1.1)]
+        {
+            let schedule = WarmRestarts{cycle, multiplier};
+
+            let restarts = (0..1000).filter(|i| schedule.scale(*i).1).collect::<Vec<usize>>();
+            assert_eq!(restarts, walked(schedule, 1000), "{cycle} {multiplier}");
+        }
+
+        //far iterations dont walk every cycle before them
+        let schedule = WarmRestarts{cycle: 10, multiplier: 1.0};
+        assert_eq!(schedule.scale(10_000_000_000), (1.0, true));
+    }
+}
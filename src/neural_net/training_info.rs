@@ -0,0 +1,34 @@
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+
+//how a network has been trained so far, saved with it for provenance
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrainingInfo
+{
+    pub iterations: usize,
+    pub last_batch_size: Option<usize>,
+    //smallest and largest rprop learning rates after the last training run
+    pub learning_rate_bounds: Option<(f64, f64)>
+}
+
+impl fmt::Display for TrainingInfo
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "trained for {} iterations", self.iterations)?;
+
+        if let Some(batch_size) = self.last_batch_size
+        {
+            write!(f, ", last batch size {batch_size}")?;
+        }
+
+        if let Some((lowest, highest)) = self.learning_rate_bounds
+        {
+            write!(f, ", learning rates between {lowest} and {highest}")?;
+        }
+
+        Ok(())
+    }
+}
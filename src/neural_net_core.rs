@@ -0,0 +1,296 @@
+//inference math that only needs core and alloc, so it also builds without std
+use alloc::vec::Vec;
+
+use serde::{Serialize, Deserialize};
+
+use crate::fast_index::FastIndex;
+
+use math::{exp, log};
+
+pub use transfer::*;
+
+
+mod transfer;
+mod math;
+
+
+//index of the highest value, the lowest index wins on ties
+pub fn argmax(values: &[f64]) -> usize
+{
+    values.iter().enumerate()
+        .reduce(|highest, current|
+        {
+            if current.1>highest.1 {current} else {highest}
+        }).unwrap().0
+}
+
+//exponentials normalized to sum to 1, shifted by the maximum so they cant overflow
+#[allow(dead_code)]
+pub fn softmax(values: &[f64]) -> Vec<f64>
+{
+    let highest = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    let exponents = values.iter().map(|value| exp(value - highest)).collect::<Vec<f64>>();
+    let total = exponents.iter().sum::<f64>();
+
+    exponents.into_iter().map(|value| value / total).collect()
+}
+
+//ln of the sum of exponentials in a single pass, the running sum gets rescaled whenever
+//a new maximum shows up so no exponent is ever positive
+#[allow(dead_code)]
+pub fn log_sum_exp(values: &[f64]) -> f64
+{
+    let (highest, total) = values.iter().fold((f64::NEG_INFINITY, 0.0), |(highest, total), value|
+    {
+        if *value<=highest
+        {
+            (highest, total + exp(value - highest))
+        } else
+        {
+            (*value, total * exp(highest - value) + 1.0)
+        }
+    });
+
+    highest + log(total)
+}
+
+//ln of the softmax, stays finite for probabilities too small to represent
+#[allow(dead_code)]
+pub fn log_softmax(values: &[f64]) -> Vec<f64>
+{
+    let total = log_sum_exp(values);
+
+    values.iter().map(|value| value - total).collect()
+}
+
+//cross entropy of the softmax of the logits against a target distribution
+#[allow(dead_code)]
+pub fn softmax_cross_entropy(logits: &[f64], correct: &[f64]) -> f64
+{
+    let total = log_sum_exp(logits);
+
+    logits.iter().zip(correct.iter()).map(|(logit, correct)| correct * (total - logit)).sum()
+}
+
+//previous_neurons are the previous layers values before its transfer function,
+//the last weight of each neuron is its bias
+pub fn feedforward_layer(
+    weights: &[Vec<f64>],
+    previous_neurons: &[f64],
+    transfer_function: TransferFunction,
+    neurons: &mut [f64]
+)
+{
+    neurons.iter_mut().zip(weights.iter()).for_each(|(neuron, neuron_weights)|
+    {
+        let bias = unsafe{ neuron_weights.fast_get(neuron_weights.len()-1) };
+
+        *neuron = weighted_sum(previous_neurons, neuron_weights, transfer_function) + bias;
+    });
+}
+
+#[cfg(not(feature = "simd"))]
+#[inline(always)]
+fn weighted_sum(
+    previous_neurons: &[f64],
+    weights: &[f64],
+    transfer_function: TransferFunction
+) -> f64
+{
+    previous_neurons.iter().zip(weights.iter()).map(|(previous_neuron, weight)|
+    {
+        transfer_function.t_f(*previous_neuron) * *weight
+    }).sum::<f64>()
+}
+
+#[cfg(feature = "simd")]
+#[inline(always)]
+fn weighted_sum(
+    previous_neurons: &[f64],
+    weights: &[f64],
+    transfer_function: TransferFunction
+) -> f64
+{
+    use wide::f64x4;
+
+    const LANES: usize = 4;
+
+    let lanes_end = previous_neurons.len() / LANES * LANES;
+
+    let (previous_lanes, previous_tail) = previous_neurons.split_at(lanes_end);
+    let (weights_lanes, weights_tail) = weights[..previous_neurons.len()].split_at(lanes_end);
+
+    //the input layer has no transfer function so the values can be loaded directly
+    let is_nothing = matches!(transfer_function, TransferFunction::Nothing);
+
+    let sum = previous_lanes.chunks_exact(LANES).zip(weights_lanes.chunks_exact(LANES))
+        .fold(f64x4::ZERO, |sum, (previous, weights)|
+        {
+            let mut previous: [f64; LANES] = previous.try_into().unwrap();
+            if !is_nothing
+            {
+                previous = previous.map(|value| transfer_function.t_f(value));
+            }
+
+            let weights: [f64; LANES] = weights.try_into().unwrap();
+
+            f64x4::from(previous).mul_add(f64x4::from(weights), sum)
+        }).reduce_add();
+
+    sum + previous_tail.iter().zip(weights_tail.iter()).map(|(previous_neuron, weight)|
+    {
+        transfer_function.t_f(*previous_neuron) * *weight
+    }).sum::<f64>()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InferenceLayer
+{
+    weights: Vec<Vec<f64>>,
+    transfer_function: TransferFunction
+}
+
+impl InferenceLayer
+{
+    pub fn new(weights: Vec<Vec<f64>>, transfer_function: TransferFunction) -> Self
+    {
+        InferenceLayer{weights, transfer_function}
+    }
+}
+
+//a network that can only feedforward, the weights can come from anywhere
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InferenceNet
+{
+    inputs_amount: usize,
+    layers: Vec<InferenceLayer>
+}
+
+#[allow(dead_code)]
+impl InferenceNet
+{
+    //none if the weights dont line up with the previous layers
+    pub fn new(inputs_amount: usize, layers: Vec<InferenceLayer>) -> Option<Self>
+    {
+        let mut previous_size = inputs_amount;
+        for layer in layers.iter()
+        {
+            if layer.weights.is_empty()
+                || layer.weights.iter().any(|weights| weights.len()!=previous_size + 1)
+            {
+                return None;
+            }
+
+            previous_size = layer.weights.len();
+        }
+
+        (inputs_amount>0 && !layers.is_empty()).then_some(InferenceNet{inputs_amount, layers})
+    }
+
+    pub fn inputs_amount(&self) -> usize
+    {
+        self.inputs_amount
+    }
+
+    pub fn predict(&self, inputs: &[f64]) -> usize
+    {
+        argmax(&self.feedforward(inputs))
+    }
+
+    pub fn feedforward(&self, inputs: &[f64]) -> Vec<f64>
+    {
+        let mut previous = inputs.to_vec();
+        let mut transfer_function = TransferFunction::Nothing;
+
+        for layer in self.layers.iter()
+        {
+            let mut neurons = alloc::vec![0.0; layer.weights.len()];
+            feedforward_layer(&layer.weights, &previous, transfer_function, &mut neurons);
+
+            previous = neurons;
+            transfer_function = layer.transfer_function;
+        }
+
+        previous.into_iter().map(|n| transfer_function.t_f(n)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn argmax_ties()
+    {
+        assert_eq!(argmax(&[0.1, 0.7, 0.3, 0.7, 0.2]), 1);
+        assert_eq!(argmax(&[0.5; 10]), 0);
+        assert_eq!(argmax(&[0.0, 0.2, 0.1]), 1);
+    }
+
+    #[test]
+    fn weighted_sum_tails()
+    {
+        for length in 0..11
+        {
+            let previous = (0..length).map(|i| i as f64 * 0.3 - 1.0).collect::<Vec<f64>>();
+            let weights = (0..=length).map(|i| 0.5 - i as f64 * 0.1).collect::<Vec<f64>>();
+
+            for transfer_function in [TransferFunction::Nothing, TransferFunction::Tanh]
+            {
+                let expected = previous.iter().zip(weights.iter())
+                    .map(|(value, weight)| transfer_function.t_f(*value) * weight)
+                    .sum::<f64>();
+
+                let sum = weighted_sum(&previous, &weights, transfer_function);
+                assert!((sum - expected).abs() < 1e-12, "length {length}: {sum} != {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn softmax_sums_to_one()
+    {
+        let probabilities = softmax(&[1000.0, 999.0, -1000.0, 0.0]);
+
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(argmax(&probabilities), 0);
+        assert!(probabilities[0]>probabilities[1] && probabilities[1]>probabilities[3]);
+
+        assert_eq!(softmax(&[2.0; 4]), vec![0.25; 4]);
+    }
+
+    #[test]
+    fn large_logits()
+    {
+        let logits: [f64; 5] = [100.0, -100.0, 99.0, 0.0, -100.0];
+
+        let exponents = logits.map(|logit| (logit - 100.0).exp());
+        let expected_total = 100.0 + exponents.iter().sum::<f64>().ln();
+
+        let total = log_sum_exp(&logits);
+        assert!((total - expected_total).abs() < 1e-12, "{total} {expected_total}");
+
+        let probabilities = softmax(&logits);
+        let logs = log_softmax(&logits);
+        assert!(probabilities.iter().chain(logs.iter()).all(|value| value.is_finite()));
+
+        probabilities.iter().zip(logs.iter()).for_each(|(probability, log)|
+        {
+            assert!((log.exp() - probability).abs() < 1e-12);
+        });
+
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!((logs[1] - (-200.0 - exponents.iter().sum::<f64>().ln())).abs() < 1e-9);
+
+        //the naive ln of the softmax would be infinite here
+        let loss = softmax_cross_entropy(&[1000.0, -1000.0], &[0.0, 1.0]);
+        assert!((loss - 2000.0).abs() < 1e-9, "{loss}");
+
+        let loss = softmax_cross_entropy(&logits, &[1.0, 0.0, 0.0, 0.0, 0.0]);
+        assert!((loss + probabilities[0].ln()).abs() < 1e-12);
+
+        assert_eq!(log_sum_exp(&[]), f64::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,22 @@
+//float functions which are f64 methods with std, without it they come from libm
+
+#[cfg(feature = "std")]
+pub fn exp(n: f64) -> f64
+{
+    n.exp()
+}
+
+#[cfg(feature = "std")]
+pub fn log(n: f64) -> f64
+{
+    n.ln()
+}
+
+#[cfg(feature = "std")]
+pub fn tanh(n: f64) -> f64
+{
+    n.tanh()
+}
+
+#[cfg(not(feature = "std"))]
+pub use libm::{exp, log, tanh};
@@ -0,0 +1,254 @@
+use serde::{Serialize, Deserialize};
+
+use super::math::{tanh, exp};
+
+#[cfg(feature = "std")]
+use std::{sync::RwLock, vec::Vec};
+
+
+//a function and its derivative, both get the value before the transfer function
+pub type CustomTransfer = (fn(f64) -> f64, fn(f64) -> f64);
+
+#[cfg(feature = "std")]
+static CUSTOM_TRANSFERS: RwLock<Vec<CustomTransfer>> = RwLock::new(Vec::new());
+
+//returns the index for TransferFunction::Custom, networks only save that index
+//so the functions have to be registered in the same order before loading them
+#[allow(dead_code)]
+#[cfg(feature = "std")]
+pub fn register_transfer(forward: fn(f64) -> f64, derivative: fn(f64) -> f64) -> usize
+{
+    let mut transfers = CUSTOM_TRANSFERS.write().unwrap();
+    transfers.push((forward, derivative));
+
+    transfers.len() - 1
+}
+
+fn custom_transfer(index: usize) -> CustomTransfer
+{
+    #[cfg(feature = "std")]
+    {
+        CUSTOM_TRANSFERS.read().unwrap().get(index).copied().unwrap_or_else(||
+        {
+            panic!("custom transfer function {index} isnt registered")
+        })
+    }
+
+    #[cfg(not(feature = "std"))]
+    {
+        panic!("custom transfer function {index} needs std to be registered")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum TransferFunction
+{
+    Nothing,
+    Relu,
+    LeakyRelu,
+    Tanh,
+    Sigmoid,
+    //same as ScaledTanh with the lecun constants, kept so old models still load
+    Sigmoid2,
+    ScaledTanh{amplitude: f64, slope: f64},
+    //index from register_transfer
+    Custom(usize)
+}
+
+const LECUN_AMPLITUDE: f64 = 1.7159;
+const LECUN_SLOPE: f64 = 0.66666666;
+
+impl TransferFunction
+{
+    #[inline(always)]
+    pub fn t_f(&self, n: f64) -> f64
+    {
+        match self
+        {
+            TransferFunction::Nothing => n,
+            TransferFunction::Relu => n.max(0.0),
+            TransferFunction::LeakyRelu => n.max(0.01),
+            TransferFunction::Tanh => tanh(n),
+            TransferFunction::Sigmoid => Self::sigmoid(n),
+            TransferFunction::Sigmoid2 => Self::scaled_tanh(LECUN_AMPLITUDE, LECUN_SLOPE, n),
+            TransferFunction::ScaledTanh{amplitude, slope} =>
+            {
+                Self::scaled_tanh(*amplitude, *slope, n)
+            },
+            TransferFunction::Custom(index) => (custom_transfer(*index).0)(n)
+        }
+    }
+
+    //dtf? funy
+    #[inline(always)]
+    pub fn dt_f(&self, n: f64) -> f64
+    {
+        match self
+        {
+            TransferFunction::Nothing => 1.0,
+            TransferFunction::Relu => if n>0.0 {1.0} else {0.0},
+            TransferFunction::LeakyRelu => if n>0.0 {1.0} else {0.01},
+            TransferFunction::Tanh =>
+            {
+                let value = tanh(n);
+                1.0 - value * value
+            },
+            TransferFunction::Sigmoid =>
+            {
+                //symmetric, the negative side keeps the precision of tiny values
+                let value = Self::sigmoid(-n.abs());
+                value * (1.0 - value)
+            },
+            TransferFunction::Sigmoid2 =>
+            {
+                Self::scaled_tanh_derivative(LECUN_AMPLITUDE, LECUN_SLOPE, n)
+            },
+            TransferFunction::ScaledTanh{amplitude, slope} =>
+            {
+                Self::scaled_tanh_derivative(*amplitude, *slope, n)
+            },
+            TransferFunction::Custom(index) => (custom_transfer(*index).1)(n)
+        }
+    }
+
+    //dead for the relus, saturated for the squashing functions
+    pub fn inactive(&self, n: f64) -> bool
+    {
+        match self
+        {
+            TransferFunction::Nothing => false,
+            TransferFunction::Relu | TransferFunction::LeakyRelu => n<=0.0,
+            TransferFunction::Tanh => tanh(n).abs()>0.99,
+            TransferFunction::Sigmoid =>
+            {
+                let value = self.t_f(n);
+                !(0.01..=0.99).contains(&value)
+            },
+            TransferFunction::Sigmoid2 => tanh(LECUN_SLOPE * n).abs()>0.99,
+            TransferFunction::ScaledTanh{slope, ..} => tanh(slope * n).abs()>0.99,
+            //nothing is known about it
+            TransferFunction::Custom(_) => false
+        }
+    }
+
+    //1 / (1 + e^-n) without e^-n overflowing, the 0.5 + 0.5 * tanh(n / 2) form
+    //rounds everything below about -37 to exactly 0
+    #[inline(always)]
+    fn sigmoid(n: f64) -> f64
+    {
+        if n>=0.0
+        {
+            1.0 / (1.0 + exp(-n))
+        } else
+        {
+            let e = exp(n);
+            e / (1.0 + e)
+        }
+    }
+
+    #[inline(always)]
+    fn scaled_tanh(amplitude: f64, slope: f64, n: f64) -> f64
+    {
+        amplitude * tanh(slope * n)
+    }
+
+    //d/dn a*tanh(s*n) = a*s*(1 - tanh(s*n)^2)
+    #[inline(always)]
+    fn scaled_tanh_derivative(amplitude: f64, slope: f64, n: f64) -> f64
+    {
+        let scale = amplitude * slope;
+        let value = tanh(slope * n);
+        scale - scale * value * value
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn scaled_tanh_derivative()
+    {
+        let change = 0.0001;
+
+        let combinations = [
+            (LECUN_AMPLITUDE, LECUN_SLOPE),
+            (1.0, 1.0),
+            (0.5, 2.0),
+            (2.5, 0.1),
+            (3.0, -1.5)
+        ];
+
+        for (amplitude, slope) in combinations
+        {
+            let transfer_function = TransferFunction::ScaledTanh{amplitude, slope};
+
+            for i in -20..=20
+            {
+                let n = i as f64 * 0.25;
+
+                let left = transfer_function.t_f(n + change);
+                let right = transfer_function.t_f(n - change);
+
+                let real_deriv = (left - right) / (2.0 * change);
+                let deriv = transfer_function.dt_f(n);
+
+                println!("amplitude: {amplitude}, slope: {slope}, n: {n}");
+                println!("deriv: {deriv}, real deriv: {real_deriv}");
+
+                assert!((deriv-real_deriv).abs()<0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn sigmoid_extremes()
+    {
+        let extremes = (-50..=50).map(|n| n as f64)
+            .chain([-1e300, 1e300, f64::MIN, f64::MAX, f64::NEG_INFINITY, f64::INFINITY]);
+
+        for n in extremes
+        {
+            let value = TransferFunction::Sigmoid.t_f(n);
+            let deriv = TransferFunction::Sigmoid.dt_f(n);
+
+            assert!((0.0..=1.0).contains(&value), "t_f({n}) = {value}");
+            assert!(deriv.is_finite() && deriv>=0.0, "dt_f({n}) = {deriv}");
+        }
+
+        //tiny but not rounded to zero
+        let small = TransferFunction::Sigmoid.t_f(-50.0);
+        assert!((small - 1.0 / (1.0 + 50.0_f64.exp())).abs() < 1e-30 && small>0.0);
+        assert_eq!(TransferFunction::Sigmoid.t_f(0.0), 0.5);
+        assert_eq!(TransferFunction::Sigmoid.dt_f(0.0), 0.25);
+
+        for i in -20..=20
+        {
+            let n = i as f64 * 0.25;
+            let change = 0.0001;
+
+            let real_deriv = (TransferFunction::Sigmoid.t_f(n + change)
+                - TransferFunction::Sigmoid.t_f(n - change)) / (2.0 * change);
+
+            assert!((TransferFunction::Sigmoid.dt_f(n) - real_deriv).abs()<0.0001);
+        }
+    }
+
+    #[test]
+    fn sigmoid2_matches_lecun()
+    {
+        let lecun = TransferFunction::ScaledTanh{
+            amplitude: LECUN_AMPLITUDE,
+            slope: LECUN_SLOPE
+        };
+
+        for i in -20..=20
+        {
+            let n = i as f64 * 0.25;
+
+            assert_eq!(TransferFunction::Sigmoid2.t_f(n), lecun.t_f(n));
+            assert_eq!(TransferFunction::Sigmoid2.dt_f(n), lecun.dt_f(n));
+        }
+    }
+}